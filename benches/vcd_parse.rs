@@ -2,32 +2,179 @@
 
 use anyhow::Result;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rvcd::wave::store::{WaveStore, WaveStoreBuilder};
 use rvcd::wave::vcd_parser::Vcd;
-use rvcd::wave::WaveLoader;
+use rvcd::wave::{Wave, WaveDataItem, WaveDataValue, WaveLoader};
+use std::collections::HashMap;
 use std::fs::File;
+use std::io::Write;
 use tracing::{info, warn};
+use vcd::{Command, IdCode};
 
-fn optimize_vcd_parser(path: &str) -> Result<()> {
+fn optimize_vcd_parser(path: &str) -> Result<Option<Wave>> {
     info!("optimize_vcd_parser({})", path);
     if let Ok(mut input) = File::open(path) {
-        Vcd::load(&mut input, |_, _| {}, None)?;
+        Ok(Some(Vcd::load(&mut input, |_, _| {}, None)?))
     } else {
         warn!("file not found: {}", path);
+        Ok(None)
+    }
+}
+
+/// Generate a vcd file with clock, counters and a bus containing x/z
+fn generate_vcd(path: &str, steps: u64) -> Result<()> {
+    let mut f = std::io::BufWriter::new(File::create(path)?);
+    writeln!(f, "$timescale 1ns $end")?;
+    writeln!(f, "$scope module top $end")?;
+    writeln!(f, "$var wire 1 ! clk $end")?;
+    writeln!(f, "$var wire 32 \" cnt $end")?;
+    writeln!(f, "$var wire 8 # bus $end")?;
+    writeln!(f, "$var wire 1 $ valid $end")?;
+    writeln!(f, "$upscope $end")?;
+    writeln!(f, "$enddefinitions $end")?;
+    for t in 0..steps {
+        writeln!(f, "#{}", t * 5)?;
+        writeln!(f, "{}!", t % 2)?;
+        if t % 2 == 0 {
+            writeln!(f, "b{:b} \"", t / 2)?;
+            writeln!(f, "{}$", (t / 2) % 3 % 2)?;
+        }
+        if t % 6 == 0 {
+            match t % 4 {
+                0 => writeln!(f, "bxxxx{:04b} #", t % 16)?,
+                1 => writeln!(f, "bz #")?,
+                _ => writeln!(f, "b{:b} #", t % 256)?,
+            }
+        }
     }
     Ok(())
 }
 
+/// Data layout before [WaveStore]
+fn legacy_data(wave: &Wave) -> HashMap<u64, Vec<WaveDataItem>> {
+    wave.data
+        .keys()
+        .map(|id| (*id, wave.data.get(id).unwrap().iter().collect()))
+        .collect()
+}
+
+/// Parse vcd file into data layout before [WaveStore], like the loader did then
+fn legacy_parse(path: &str) -> Result<HashMap<u64, Vec<WaveDataItem>>> {
+    let mut parser = vcd::Parser::new(std::io::BufReader::new(File::open(path)?));
+    parser.parse_header()?;
+    let mut data: HashMap<u64, Vec<WaveDataItem>> = HashMap::new();
+    let mut timestamp = 0u64;
+    for command in parser {
+        let (IdCode(id), value) = match command? {
+            Command::Timestamp(t) => {
+                timestamp = t;
+                continue;
+            }
+            Command::ChangeScalar(i, v) => (i, WaveDataValue::Raw(vec![v.into()])),
+            Command::ChangeVector(i, v) => (
+                i,
+                WaveDataValue::Raw(v.into_iter().map(|x| x.into()).collect()),
+            ),
+            Command::ChangeReal(i, v) => (i, WaveDataValue::Real(v)),
+            Command::ChangeString(i, v) => (i, WaveDataValue::Str(v)),
+            _ => continue,
+        };
+        let item = WaveDataItem { value, timestamp }.compress()?;
+        data.entry(id).or_default().push(item);
+    }
+    Ok(data)
+}
+
+/// Approximate heap memory used by legacy data layout
+fn legacy_memory_size(data: &HashMap<u64, Vec<WaveDataItem>>) -> usize {
+    data.capacity() * std::mem::size_of::<(u64, Vec<WaveDataItem>)>()
+        + data
+            .values()
+            .map(|list| {
+                list.capacity() * std::mem::size_of::<WaveDataItem>()
+                    + list
+                        .iter()
+                        .map(|item| match &item.value {
+                            WaveDataValue::Comp(v) => v.capacity(),
+                            WaveDataValue::Raw(v) => v.capacity(),
+                            WaveDataValue::Real(_) => 0,
+                            WaveDataValue::Str(v) => v.capacity(),
+                        })
+                        .sum::<usize>()
+            })
+            .sum::<usize>()
+}
+
 pub fn criterion_benchmark(c: &mut Criterion) {
+    let generated = std::env::temp_dir().join("rvcd_bench.vcd");
+    let generated = generated.to_str().unwrap().to_string();
+    generate_vcd(generated.as_str(), 1_000_000).unwrap();
     let files = [
         // "data/testbench.vcd",
-        // "data/cpu_ila_commit.vcd",
-        "/home/chiro/programs/scaleda-sample-project/.sim/Icarus-Run iverilog simulation/tb_waterfall_waveform.vcd"
+        "data/cpu_ila_commit.vcd".to_string(),
+        generated,
     ];
     for file in files {
         let id = format!("load {file}");
         println!("id: {id}");
+        let wave = match optimize_vcd_parser(file.as_str()).unwrap() {
+            Some(wave) => wave,
+            None => continue,
+        };
+        let legacy = legacy_data(&wave);
+        println!(
+            "memory {file}: legacy {} bytes, store {} bytes",
+            legacy_memory_size(&legacy),
+            wave.data.memory_size()
+        );
         c.bench_function(id.as_ref(), |b| {
-            b.iter(|| black_box(optimize_vcd_parser(file)))
+            b.iter(|| black_box(optimize_vcd_parser(file.as_str())))
+        });
+        c.bench_function(format!("legacy load {file}").as_ref(), |b| {
+            b.iter(|| black_box(legacy_parse(file.as_str())))
+        });
+        let changes = legacy
+            .iter()
+            .flat_map(|(id, list)| list.iter().map(move |item| (*id, item.clone())))
+            .collect::<Vec<_>>();
+        c.bench_function(format!("legacy build {file}").as_ref(), |b| {
+            b.iter(|| {
+                let mut data: HashMap<u64, Vec<WaveDataItem>> = HashMap::new();
+                for (id, item) in &changes {
+                    data.entry(*id).or_default().push(item.clone());
+                }
+                black_box(data)
+            })
+        });
+        c.bench_function(format!("store build {file}").as_ref(), |b| {
+            b.iter(|| {
+                let mut data = WaveStoreBuilder::default();
+                for (id, item) in &changes {
+                    data.push(*id, item);
+                }
+                black_box(data.finish())
+            })
+        });
+        let (start, stop) = wave.info.range;
+        let step = ((stop - start) / 1000).max(1);
+        c.bench_function(format!("legacy find {file}").as_ref(), |b| {
+            b.iter(|| {
+                for list in legacy.values() {
+                    for pos in (start..stop).step_by(step as usize) {
+                        let index = list.partition_point(|x| x.timestamp <= pos);
+                        black_box(index.checked_sub(1).map(|i| list[i].clone()));
+                    }
+                }
+            })
+        });
+        c.bench_function(format!("store find {file}").as_ref(), |b| {
+            b.iter(|| {
+                for id in wave.data.keys() {
+                    for pos in (start..stop).step_by(step as usize) {
+                        black_box(wave.find_value(*id, pos));
+                    }
+                }
+            })
         });
     }
 }
@@ -0,0 +1,67 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rvcd::wave::lod::paint_changes;
+use rvcd::wave::store::{WaveStore, WaveStoreBuilder};
+use rvcd::wave::{WaveDataItem, WaveDataValue, WireValue};
+
+/// Pixels of wave panel to prepare painting for
+const VIEW_WIDTH: f64 = 1000.0;
+
+/// Store with a clock, a counter and a bus with x, changing in every step
+fn generate_store(steps: u64) -> WaveStore {
+    let mut builder = WaveStoreBuilder::default();
+    for t in 0..steps {
+        let timestamp = t * 5;
+        builder.push(
+            0,
+            &WaveDataItem {
+                value: WaveDataValue::Comp(vec![(t % 2) as u8]),
+                timestamp,
+            },
+        );
+        builder.push(
+            1,
+            &WaveDataItem {
+                value: WaveDataValue::Comp(t.to_le_bytes().to_vec()),
+                timestamp,
+            },
+        );
+        let value = match t % 64 {
+            0 => WaveDataValue::Raw(vec![WireValue::X; 8]),
+            _ => WaveDataValue::Comp(vec![t as u8]),
+        };
+        builder.push(2, &WaveDataItem { value, timestamp });
+    }
+    builder.finish()
+}
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let steps = 1_000_000;
+    let store = generate_store(steps);
+    let end = steps * 5;
+    // whole dump in view, then a window of about one change per pixel
+    for (name, range) in [
+        ("zoomed out", (0, end)),
+        ("zoomed in", (end / 2, end / 2 + 5000)),
+    ] {
+        let min_span = (range.1 - range.0) as f64 / VIEW_WIDTH;
+        c.bench_function(format!("paint changes {name}").as_ref(), |b| {
+            b.iter(|| {
+                for id in 0..3 {
+                    let data = store.get(&id).unwrap();
+                    black_box(paint_changes(&data, range.0, range.1, min_span));
+                }
+            })
+        });
+        c.bench_function(format!("paint changes {name} without lod").as_ref(), |b| {
+            b.iter(|| {
+                for id in 0..3 {
+                    let data = store.get(&id).unwrap();
+                    black_box(paint_changes(&data, range.0, range.1, 0.0));
+                }
+            })
+        });
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,40 @@
+module fsm #(
+    parameter WIDTH = 2
+) (
+    input wire clk,
+    input wire rst,
+    input wire start,
+    input wire done,
+    output reg busy
+);
+
+localparam IDLE = 2'd0, LOAD = 2'd1;
+localparam RUN = 2'b10;
+localparam STOP = 3;
+localparam HALT = STOP;
+
+reg [WIDTH-1:0] state;
+
+always @(posedge clk or posedge rst) begin
+    if (rst) begin
+        state <= IDLE;
+        busy <= 1'b0;
+    end else begin
+        case (state)
+            IDLE: if (start) state <= LOAD;
+            LOAD: begin
+                busy <= 1'b1;
+                state <= RUN;
+            end
+            RUN, HALT: begin
+                case (done)
+                    1'b1: state <= IDLE;
+                    default: state <= RUN;
+                endcase
+            end
+            default: state <= IDLE;
+        endcase
+    end
+end
+
+endmodule
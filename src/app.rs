@@ -9,6 +9,7 @@ use crate::manager::{RvcdRpcMessage, RvcdExitMessage, RvcdManagerMessage};
 use crate::rpc::{EventType, RvcdInputEvent};
 use crate::run_mode::RunMode;
 use crate::rvcd::State;
+use crate::shortcut::Shortcuts;
 use crate::utils::sleep_ms;
 use crate::verilog::VerilogGotoSource;
 use crate::Rvcd;
@@ -49,6 +50,8 @@ pub struct RvcdApp {
     pub debug_panel: bool,
     pub sst_enabled: bool,
     pub locale: String,
+    /// Keyboard shortcut bindings
+    pub shortcuts: Shortcuts,
     #[cfg(not(target_arch = "wasm32"))]
     #[serde(skip)]
     pub rpc_rx: Option<mpsc::Receiver<RvcdRpcMessage>>,
@@ -95,6 +98,7 @@ impl Default for RvcdApp {
             debug_panel: false,
             sst_enabled: true,
             locale: "".to_string(),
+            shortcuts: Default::default(),
             #[cfg(not(target_arch = "wasm32"))]
             rpc_rx: None,
             #[cfg(not(target_arch = "wasm32"))]
@@ -249,6 +253,29 @@ impl RvcdApp {
         });
         egui::warn_if_debug_build(ui);
     }
+    /// Run actions of pressed shortcuts in app window under pointer, or the maximized app
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        let actions = self.shortcuts.take_actions(ctx);
+        if actions.is_empty() {
+            return;
+        }
+        let hovered = ctx
+            .input(|i| i.pointer.hover_pos())
+            .and_then(|pos| ctx.layer_id_at(pos))
+            .map(|layer| layer.id);
+        let target = self
+            .apps
+            .iter()
+            .find(|app| hovered == Some(Id::new(app.id)))
+            .map(|app| app.id)
+            .or(self.app_now_id)
+            .or_else(|| self.apps.first().map(|app| app.id));
+        if let Some(app) = self.apps.iter_mut().find(|app| Some(app.id) == target) {
+            for action in actions {
+                app.handle_shortcut(action);
+            }
+        }
+    }
     fn new_id(&self) -> usize {
         self.apps
             .iter()
@@ -593,6 +620,9 @@ impl eframe::App for RvcdApp {
                         }
                     }
                 });
+                if ui.button(t!("menu.shortcuts")).clicked() {
+                    self.shortcuts.open = true;
+                }
                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                     #[cfg(not(target_arch = "wasm32"))]
                     if ui.button(t!("menu.quit")).clicked() {
@@ -636,6 +666,8 @@ impl eframe::App for RvcdApp {
                 self.debug_panel(ui);
             });
         }
+        self.handle_shortcuts(ctx);
+        self.shortcuts.ui(ctx);
         let app_now_id = self.app_now_id;
         let mut show_app_in_window = |app: &mut Rvcd, ctx: &egui::Context| {
             let open_app = self.open_apps.iter_mut().find(|x| x.0 == app.id);
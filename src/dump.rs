@@ -0,0 +1,292 @@
+use crate::radix::Radix;
+use crate::wave::fst_parser::Fst;
+use crate::wave::vcd_parser::Vcd;
+use crate::wave::vcd_writer::write_vcd;
+use crate::wave::{Wave, WaveDataValue, WaveFormat, WaveLoader, WavePreLoader};
+use anyhow::{anyhow, Result};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+
+/// Output format of `rvcd dump`
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DumpFormat {
+    /// `#time path value` per line
+    #[default]
+    Text,
+    /// `time,signal,value` rows with header
+    Csv,
+    /// Value changes grouped by signal
+    Json,
+}
+
+/// Arguments of `rvcd dump`
+#[derive(clap::Args, Debug, Clone)]
+pub struct DumpArgs {
+    /// Wave file to load
+    pub file: String,
+    /// Signal paths like `top.cpu.pc`, matched from the end of paths.
+    /// Dump all signals when empty
+    pub signals: Vec<String>,
+    /// Start timestamp of time window
+    #[arg(long)]
+    pub from: Option<u64>,
+    /// End timestamp of time window
+    #[arg(long)]
+    pub to: Option<u64>,
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = DumpFormat::Text)]
+    pub format: DumpFormat,
+    /// Radix of values: bin, oct, dec, hex, sdec, q8.8, uq8.8, half, float, double or ascii
+    #[arg(short, long, default_value = "hex")]
+    pub radix: Radix,
+    /// Write to file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+/// Arguments of `rvcd export`
+#[derive(clap::Args, Debug, Clone)]
+pub struct ExportArgs {
+    /// Wave file to load
+    pub file: String,
+    /// Signal paths like `top.cpu.pc`, matched from the end of paths.
+    /// Export all signals when empty
+    pub signals: Vec<String>,
+    /// Start timestamp of time window
+    #[arg(long)]
+    pub from: Option<u64>,
+    /// End timestamp of time window
+    #[arg(long)]
+    pub to: Option<u64>,
+    /// Write to file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+/// Value changes of one signal in time window
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct DumpSignal {
+    pub path: String,
+    pub width: u64,
+    /// (timestamp, value in radix)
+    pub changes: Vec<(u64, String)>,
+}
+
+/// Load whole wave file, loader is picked by file format
+pub fn load_wave_file(path: &str) -> Result<Wave> {
+    match WaveFormat::detect_file(path) {
+        WaveFormat::Fst => Fst::load(&mut File::open(path)?, |_, _| {}, None),
+        WaveFormat::Vcd => {
+            let (last_timestamp, reader) = Vcd::last_timestamp(BufReader::new(File::open(path)?));
+            Vcd::load(&mut reader?.into_inner(), |_, _| {}, last_timestamp)
+        }
+    }
+}
+
+/// Find signal ids whose paths end with `pattern` components, sorted by path
+pub fn match_signals(wave: &Wave, pattern: &str) -> Vec<u64> {
+    wave.info.match_paths(pattern)
+}
+
+/// Collect value changes of signals in `[from, to]`,
+/// value at `from` is included when it changed before `from`
+pub fn dump_signals(
+    wave: &Wave,
+    ids: &[u64],
+    from: Option<u64>,
+    to: Option<u64>,
+    radix: Radix,
+) -> Vec<DumpSignal> {
+    let from = from.unwrap_or(wave.info.range.0);
+    let to = to.unwrap_or(u64::MAX);
+    ids.iter()
+        .map(|id| {
+            let width = wave
+                .info
+                .code_signal_info
+                .get(id)
+                .map(|s| s.width)
+                .unwrap_or_default();
+            let text = |value: &WaveDataValue| value.as_radix_width(radix.clone(), width);
+            let mut changes = vec![];
+            if let Some(data) = wave.data.get(id) {
+                if let Some(item) = data.find(from).filter(|item| item.timestamp < from) {
+                    changes.push((from, text(&item.value)));
+                }
+                changes.extend(
+                    data.iter_at(data.position(from))
+                        .take_while(|item| item.timestamp <= to)
+                        .map(|item| (item.timestamp, text(&item.value))),
+                );
+            }
+            DumpSignal {
+                path: wave
+                    .info
+                    .code_paths
+                    .get(id)
+                    .map(|p| p.join("."))
+                    .unwrap_or_else(|| id.to_string()),
+                width,
+                changes,
+            }
+        })
+        .collect()
+}
+
+fn csv_escape(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Write dumped signals, changes of all signals are sorted by time in text and csv
+pub fn write_dump<W: Write>(
+    writer: &mut W,
+    wave: &Wave,
+    signals: &[DumpSignal],
+    format: DumpFormat,
+) -> Result<()> {
+    let rows = || {
+        let mut rows = signals
+            .iter()
+            .flat_map(|s| s.changes.iter().map(move |(t, v)| (*t, s.path.as_str(), v)))
+            .collect::<Vec<_>>();
+        rows.sort_by_key(|r| r.0);
+        rows
+    };
+    match format {
+        DumpFormat::Text => {
+            for (time, path, value) in rows() {
+                writeln!(writer, "#{time} {path} {value}")?;
+            }
+        }
+        DumpFormat::Csv => {
+            writeln!(writer, "time,signal,value")?;
+            for (time, path, value) in rows() {
+                writeln!(
+                    writer,
+                    "{},{},{}",
+                    time,
+                    csv_escape(path),
+                    csv_escape(value)
+                )?;
+            }
+        }
+        DumpFormat::Json => {
+            let json = serde_json::json!({
+                "timescale": format!("{}{}", wave.info.timescale.0, wave.info.timescale.1),
+                "range": [wave.info.range.0, wave.info.range.1],
+                "signals": signals,
+            });
+            serde_json::to_writer_pretty(&mut *writer, &json)?;
+            writeln!(writer)?;
+        }
+    }
+    Ok(())
+}
+
+/// Signal ids matching any of `patterns` in order, all signals sorted by path when empty
+pub fn select_signals(wave: &Wave, patterns: &[String]) -> Result<Vec<u64>> {
+    if patterns.is_empty() {
+        let mut ids = wave.info.code_paths.keys().copied().collect::<Vec<_>>();
+        ids.sort_by_key(|id| &wave.info.code_paths[id]);
+        return Ok(ids);
+    }
+    let mut ids: Vec<u64> = vec![];
+    for pattern in patterns {
+        let matches = match_signals(wave, pattern.as_str());
+        if matches.is_empty() {
+            return Err(anyhow!("no signal matches {}", pattern));
+        }
+        for id in matches {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    Ok(ids)
+}
+
+/// Run `rvcd dump`
+pub fn run_dump(args: &DumpArgs) -> Result<()> {
+    let wave = load_wave_file(args.file.as_str())?;
+    let ids = select_signals(&wave, &args.signals)?;
+    let range = (
+        args.from.unwrap_or(wave.info.range.0),
+        args.to.unwrap_or(u64::MAX),
+    );
+    if range.0 > range.1 {
+        return Err(anyhow!("invalid time window #{}~#{}", range.0, range.1));
+    }
+    let signals = dump_signals(&wave, &ids, args.from, args.to, args.radix.clone());
+    match &args.output {
+        Some(path) => write_dump(&mut File::create(path)?, &wave, &signals, args.format),
+        None => write_dump(&mut std::io::stdout().lock(), &wave, &signals, args.format),
+    }
+}
+
+/// Run `rvcd export`
+pub fn run_export(args: &ExportArgs) -> Result<()> {
+    let wave = load_wave_file(args.file.as_str())?;
+    let ids = select_signals(&wave, &args.signals)?;
+    let range = (
+        args.from.unwrap_or(wave.info.range.0),
+        args.to.unwrap_or(wave.info.range.1),
+    );
+    if range.0 > range.1 {
+        return Err(anyhow!("invalid time window #{}~#{}", range.0, range.1));
+    }
+    match &args.output {
+        Some(path) => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            write_vcd(&mut writer, &wave, &ids, range)?;
+            writer.flush()?;
+            Ok(())
+        }
+        None => write_vcd(&mut std::io::stdout().lock(), &wave, &ids, range),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dump::{
+        dump_signals, load_wave_file, match_signals, run_dump, write_dump, DumpArgs, DumpFormat,
+    };
+    use crate::radix::Radix;
+
+    #[test]
+    fn test_dump() -> anyhow::Result<()> {
+        let wave = load_wave_file("data/cpu_ila_commit.vcd")?;
+        let ids = match_signals(&wave, "dut.commits_0_w_en");
+        assert_eq!(ids.len(), 1);
+        assert_eq!(match_signals(&wave, "commits_0_w_en"), ids);
+        assert!(match_signals(&wave, "w_en").is_empty());
+        let signals = dump_signals(&wave, &ids, Some(200), Some(210), Radix::Bin);
+        let mut csv = vec![];
+        write_dump(&mut csv, &wave, &signals, DumpFormat::Csv)?;
+        assert_eq!(
+            String::from_utf8(csv)?,
+            "time,signal,value\n\
+            200,dut.commits_0_w_en,0\n\
+            204,dut.commits_0_w_en,1\n\
+            206,dut.commits_0_w_en,0\n"
+        );
+        let mut json = vec![];
+        write_dump(&mut json, &wave, &signals, DumpFormat::Json)?;
+        let json: serde_json::Value = serde_json::from_slice(&json)?;
+        assert_eq!(json["signals"][0]["changes"][1][0], 204);
+        let args = DumpArgs {
+            file: "data/cpu_ila_commit.vcd".to_string(),
+            signals: vec![],
+            from: Some(210),
+            to: Some(200),
+            format: DumpFormat::Text,
+            radix: Radix::Hex,
+            output: None,
+        };
+        assert!(run_dump(&args).is_err());
+        Ok(())
+    }
+}
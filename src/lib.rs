@@ -1,6 +1,7 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 pub mod app;
+pub mod dump;
 pub mod files;
 pub mod frame_history;
 pub mod message;
@@ -9,6 +10,7 @@ pub mod run_mode;
 pub mod rvcd;
 pub mod manager;
 pub mod service;
+pub mod shortcut;
 pub mod size;
 pub mod tree_view;
 pub mod utils;
@@ -21,8 +21,10 @@ static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
 /// Simple program to greet a person
 #[cfg(not(target_arch = "wasm32"))]
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct RvcdArgs {
+    #[command(subcommand)]
+    command: Option<RvcdCommand>,
     /// Files to open
     file: Vec<String>,
     /// Input sources
@@ -38,6 +40,15 @@ struct RvcdArgs {
     hidden: bool,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(clap::Subcommand)]
+enum RvcdCommand {
+    /// Print value changes of signals without gui
+    Dump(rvcd::dump::DumpArgs),
+    /// Write signals in time window to a new vcd file without gui
+    Export(rvcd::dump::ExportArgs),
+}
+
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
 // #[tokio::main]
@@ -56,6 +67,17 @@ fn main() -> Result<()> {
     rt.block_on(async {
         let args = RvcdArgs::parse();
 
+        if let Some(command) = &args.command {
+            // keep stdout for dumped values
+            tracing_subscriber::fmt()
+                .with_writer(std::io::stderr)
+                .init();
+            return match command {
+                RvcdCommand::Dump(dump_args) => rvcd::dump::run_dump(dump_args),
+                RvcdCommand::Export(export_args) => rvcd::dump::run_export(export_args),
+            };
+        }
+
         // Log to stdout (if you run with `RUST_LOG=debug`).
         tracing_subscriber::fmt::init();
 
@@ -152,8 +174,8 @@ fn main() -> Result<()> {
             rpc_tx5,
         ));
         gui.await;
-    });
-    Ok(())
+        Ok(())
+    })
 }
 
 // when compiling to web using trunk.
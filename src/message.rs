@@ -1,5 +1,8 @@
+use crate::radix::Radix;
 use crate::verilog::{VerilogGotoSource, VerilogSource};
-use crate::wave::Wave;
+use crate::wave::store::WaveStore;
+use crate::wave::vcd_index::VcdIndex;
+use crate::wave::{Wave, WaveInfo};
 use egui_toast::Toast;
 use rfd::FileHandle;
 use std::fmt::{Debug, Formatter};
@@ -26,7 +29,35 @@ pub enum RvcdMsg {
     GotNoSource,
     SetAlternativeGotoSources(Vec<VerilogGotoSource>),
     CallGotoSources(VerilogGotoSource),
-    SetGotoSignals(Vec<u64>)
+    SetGotoSignals(Vec<u64>),
+    /// Enable loading signal data on demand
+    SetLazyLoading(bool),
+    /// Vcd file indexed in lazy loading mode: (path, info, index)
+    ServiceIndexReady(String, WaveInfo, VcdIndex),
+    /// Request data of signals in lazy loading mode
+    LoadSignals(Vec<u64>),
+    /// Data of signals loaded on demand
+    UpdateSignalData(WaveStore),
+    /// Signals requested in lazy loading mode failed to load: (ids, error)
+    LoadSignalsFailed(Vec<u64>, String),
+    /// Follow value changes appended to loaded file
+    SetFollow(bool),
+    /// Watched file is modified: (path, whether it is settled after modifications)
+    ServiceFileChanged(String, bool),
+    /// Value changes appended to followed file: (data, last timestamp)
+    AppendWaveData(WaveStore, u64),
+    /// Watch loaded file to reload it when modified
+    SetWatchFile(bool),
+    /// Loaded file is modified on disk: (path)
+    FileModified(String),
+    /// Export signals in view and time window to vcd file: (path)
+    ExportVcd(String),
+    /// Save view layout to file: (path)
+    SaveLayout(String),
+    /// Load view layout from file: (path)
+    LoadLayout(String),
+    /// Load value names of signals from file: (signal ids, path, radix of numbers)
+    LoadValueTable(Vec<u64>, String, Radix),
 }
 
 impl Debug for RvcdMsg {
@@ -59,7 +90,38 @@ impl Debug for RvcdMsg {
             }
             RvcdMsg::GotNoSource => write!(f, "RvcdMg: GotNoSource"),
             RvcdMsg::SetGotoSignals(v) => write!(f, "RvcdMg: SetGotoSignals({})", v.len()),
-            RvcdMsg::UpdateSource(path) => write!(f, "RvcdMg: UpdateSource({})", path)
+            RvcdMsg::UpdateSource(path) => write!(f, "RvcdMg: UpdateSource({})", path),
+            RvcdMsg::SetLazyLoading(v) => write!(f, "RvcdMsg: SetLazyLoading({})", v),
+            RvcdMsg::ServiceIndexReady(path, _, index) => write!(
+                f,
+                "RvcdMsg: ServiceIndexReady({}, {} chunks)",
+                path,
+                index.chunks.len()
+            ),
+            RvcdMsg::LoadSignals(v) => write!(f, "RvcdMsg: LoadSignals({})", v.len()),
+            RvcdMsg::UpdateSignalData(v) => write!(f, "RvcdMsg: UpdateSignalData({})", v.len()),
+            RvcdMsg::LoadSignalsFailed(v, e) => {
+                write!(f, "RvcdMsg: LoadSignalsFailed({}, {})", v.len(), e)
+            }
+            RvcdMsg::SetFollow(v) => write!(f, "RvcdMsg: SetFollow({})", v),
+            RvcdMsg::ServiceFileChanged(path, settled) => {
+                write!(f, "RvcdMsg: ServiceFileChanged({}, {})", path, settled)
+            }
+            RvcdMsg::AppendWaveData(v, t) => {
+                write!(f, "RvcdMsg: AppendWaveData({}, #{})", v.len(), t)
+            }
+            RvcdMsg::SetWatchFile(v) => write!(f, "RvcdMsg: SetWatchFile({})", v),
+            RvcdMsg::FileModified(path) => write!(f, "RvcdMsg: FileModified({})", path),
+            RvcdMsg::ExportVcd(path) => write!(f, "RvcdMsg: ExportVcd({})", path),
+            RvcdMsg::SaveLayout(path) => write!(f, "RvcdMsg: SaveLayout({})", path),
+            RvcdMsg::LoadLayout(path) => write!(f, "RvcdMsg: LoadLayout({})", path),
+            RvcdMsg::LoadValueTable(ids, path, radix) => write!(
+                f,
+                "RvcdMsg: LoadValueTable({}, {}, {})",
+                ids.len(),
+                path,
+                radix
+            ),
         }
     }
 }
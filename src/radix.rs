@@ -1,7 +1,9 @@
 use crate::wave::WireValue;
+use anyhow::anyhow;
 use num_bigint::BigUint;
 use std::cmp::min;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use tracing::trace;
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Ord, PartialOrd, Eq, PartialEq, Clone)]
@@ -10,37 +12,116 @@ pub enum Radix {
     Oct,
     Dec,
     Hex,
+    /// Two's complement signed decimal
+    SignedDec,
+    /// Fixed-point number with `frac` fraction bits, `Qm.n` when `signed`, `UQm.n` otherwise,
+    /// in lower `int + frac` bits, or all bits when `int` is not given
+    Fixed {
+        int: Option<u32>,
+        frac: u32,
+        signed: bool,
+    },
+    /// IEEE-754 half precision float in lower 16 bits
+    Half,
+    /// IEEE-754 single precision float in lower 32 bits
+    Float,
+    /// IEEE-754 double precision float in lower 64 bits
+    Double,
+    /// Bytes as characters
+    Ascii,
 }
 
 impl Display for Radix {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{self:?}")
+        match self {
+            Radix::Fixed { int, frac, signed } => {
+                let prefix = if *signed { "Q" } else { "UQ" };
+                match int {
+                    Some(int) => write!(f, "{prefix}{int}.{frac}"),
+                    None => write!(f, "{prefix}{frac}"),
+                }
+            }
+            _ => write!(f, "{self:?}"),
+        }
+    }
+}
+
+impl FromStr for Radix {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "bin" | "b" => Ok(Radix::Bin),
+            "oct" | "o" => Ok(Radix::Oct),
+            "dec" | "d" => Ok(Radix::Dec),
+            "hex" | "h" => Ok(Radix::Hex),
+            "signeddec" | "sdec" | "s" => Ok(Radix::SignedDec),
+            "half" | "f16" => Ok(Radix::Half),
+            "float" | "f32" => Ok(Radix::Float),
+            "double" | "f64" => Ok(Radix::Double),
+            "ascii" | "a" => Ok(Radix::Ascii),
+            lower => {
+                // `q8`, `q7.8` or `uq8.8`, number after `.` is count of fraction bits
+                let (fixed, signed) = match lower.strip_prefix("uq") {
+                    Some(fixed) => (Some(fixed), false),
+                    None => (lower.strip_prefix('q'), true),
+                };
+                // digits only, without sign accepted by `u32::from_str`
+                let number = |n: &str| match n.bytes().all(|c| c.is_ascii_digit()) {
+                    true => n.parse::<u32>().ok(),
+                    false => None,
+                };
+                let parsed = fixed.and_then(|fixed| match fixed.split_once('.') {
+                    Some((int, frac)) => Some((Some(number(int)?), number(frac)?)),
+                    None => Some((None, number(fixed)?)),
+                });
+                match parsed {
+                    Some((int, frac)) => Ok(Radix::Fixed { int, frac, signed }),
+                    None => Err(anyhow!("unknown radix: {}", s)),
+                }
+            }
+        }
     }
 }
 
 impl Radix {
-    pub fn to_number(&self) -> usize {
+    /// Radixes without parameters, in menu order
+    pub const PRESETS: [Radix; 9] = [
+        Radix::Hex,
+        Radix::Oct,
+        Radix::Dec,
+        Radix::SignedDec,
+        Radix::Bin,
+        Radix::Half,
+        Radix::Float,
+        Radix::Double,
+        Radix::Ascii,
+    ];
+    /// Base of number, `None` for radixes depending on signal width
+    pub fn to_number(&self) -> Option<usize> {
         match self {
-            Radix::Bin => 2,
-            Radix::Oct => 8,
-            Radix::Dec => 10,
-            Radix::Hex => 16,
+            Radix::Bin => Some(2),
+            Radix::Oct => Some(8),
+            Radix::Dec => Some(10),
+            Radix::Hex => Some(16),
+            _ => None,
         }
     }
 }
 
 /// Convert [Vec<WireValue>] to string in radix
 pub fn radix_vector_to_string(radix: Radix, vec: &Vec<WireValue>) -> String {
-    if radix == Radix::Dec {
-        radix_vector_dec(vec)
-    } else {
-        let n: usize = match radix {
-            Radix::Bin => 1,
-            Radix::Oct => 3,
-            Radix::Hex => 4,
-            _ => panic!("internal err"),
-        };
-        radix_vector_to_string_n(vec, n)
+    match radix {
+        Radix::Bin => radix_vector_to_string_n(vec, 1),
+        Radix::Oct => radix_vector_to_string_n(vec, 3),
+        Radix::Hex => radix_vector_to_string_n(vec, 4),
+        Radix::Dec => radix_vector_dec(vec),
+        Radix::SignedDec => radix_vector_signed_dec(vec),
+        Radix::Fixed { int, frac, signed } => radix_vector_fixed(vec, int, frac, signed),
+        Radix::Half => radix_vector_float(vec, 16),
+        Radix::Float => radix_vector_float(vec, 32),
+        Radix::Double => radix_vector_float(vec, 64),
+        Radix::Ascii => radix_vector_ascii(vec),
     }
 }
 
@@ -156,9 +237,162 @@ pub fn radix_vector_dec(vec: &Vec<WireValue>) -> String {
     }
 }
 
+/// `x` when any bit is [WireValue::X], or `z` when any bit is [WireValue::Z]
+fn vector_unknown(vec: &[WireValue]) -> Option<&'static str> {
+    if vec.contains(&WireValue::X) {
+        Some("x")
+    } else if vec.contains(&WireValue::Z) {
+        Some("z")
+    } else {
+        None
+    }
+}
+
+/// Bits of vector as float number, precision is lost over 53 bits
+fn vector_f64(vec: &[WireValue]) -> f64 {
+    vec.iter()
+        .rev()
+        .fold(0.0, |acc, v| acc * 2.0 + f64::from(value_map_val(v)))
+}
+
+/// Lower 64 bits of vector
+fn vector_u64(vec: &[WireValue]) -> u64 {
+    vec.iter()
+        .rev()
+        .fold(0u64, |acc, v| acc << 1 | u64::from(value_map_val(v)))
+}
+
+/// Number of vector used in analog painting, two's complement when `signed`,
+/// `None` when any bit is unknown
+pub fn vector_number(vec: &[WireValue], signed: bool) -> Option<f64> {
+    if vector_unknown(vec).is_some() {
+        return None;
+    }
+    let mut value = vector_f64(vec);
+    if signed && vec.last() == Some(&WireValue::V1) {
+        value -= 2f64.powi(vec.len() as i32);
+    }
+    Some(value)
+}
+
+/// Number shown in signed, fixed-point or float `radix`, unsigned in other radixes,
+/// `None` when any used bit is unknown
+pub fn vector_radix_number(vec: &[WireValue], radix: &Radix) -> Option<f64> {
+    match radix {
+        Radix::SignedDec => vector_number(vec, true),
+        Radix::Fixed { int, frac, signed } => vector_number(fixed_bits(vec, *int, *frac), *signed)
+            .map(|value| value / 2f64.powi(*frac as i32)),
+        Radix::Half => vector_float(vec, 16),
+        Radix::Float => vector_float(vec, 32),
+        Radix::Double => vector_float(vec, 64),
+        _ => vector_number(vec, false),
+    }
+}
+
+/// Signed decimal in two's complement, `x` and `z` like [radix_vector_dec]
+pub fn radix_vector_signed_dec(vec: &Vec<WireValue>) -> String {
+    match (vec.last(), vector_unknown(vec)) {
+        (Some(WireValue::V1), None) => {
+            let modulus = BigUint::from(1u8) << vec.len();
+            format!("-{}", modulus - radix_value_big_uint(vec))
+        }
+        _ => radix_vector_dec(vec),
+    }
+}
+
+/// Lower `int + frac` bits of fixed-point number, or all bits when `int` is not given
+fn fixed_bits(vec: &[WireValue], int: Option<u32>, frac: u32) -> &[WireValue] {
+    match int {
+        Some(int) => &vec[..min(vec.len(), int as usize + frac as usize)],
+        None => vec,
+    }
+}
+
+/// Fixed-point number with `frac` fraction bits in lower `int + frac` bits of vector,
+/// two's complement when `signed`, `x` or `z` when any of these bits is unknown
+pub fn radix_vector_fixed(vec: &[WireValue], int: Option<u32>, frac: u32, signed: bool) -> String {
+    let vec = fixed_bits(vec, int, frac);
+    match vector_number(vec, signed) {
+        Some(value) => (value / 2f64.powi(frac as i32)).to_string(),
+        None => vector_unknown(vec).unwrap_or_default().to_string(),
+    }
+}
+
+/// Half precision float bits to [f32]
+fn half_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+/// Half precision float bits of [f32], `None` when it is not exactly representable
+pub fn f32_to_half(value: f32) -> Option<u16> {
+    (0..=u16::MAX).find(|bits| half_to_f32(*bits) == value)
+}
+
+/// IEEE-754 float in lower `bits` of vector, which is 16, 32 or 64,
+/// `x` or `z` when any of these bits is unknown
+pub fn radix_vector_float(vec: &[WireValue], bits: usize) -> String {
+    let vec = &vec[..min(vec.len(), bits)];
+    if let Some(unknown) = vector_unknown(vec) {
+        return unknown.to_string();
+    }
+    let raw = vector_u64(vec);
+    match bits {
+        16 => half_to_f32(raw as u16).to_string(),
+        32 => f32::from_bits(raw as u32).to_string(),
+        _ => f64::from_bits(raw).to_string(),
+    }
+}
+
+/// IEEE-754 float in lower `bits` of vector like [radix_vector_float],
+/// `None` when any of these bits is unknown or `bits` is not 16, 32 or 64
+pub fn vector_float(vec: &[WireValue], bits: usize) -> Option<f64> {
+    let vec = &vec[..min(vec.len(), bits)];
+    if vector_unknown(vec).is_some() {
+        return None;
+    }
+    let raw = vector_u64(vec);
+    match bits {
+        16 => Some(half_to_f32(raw as u16) as f64),
+        32 => Some(f32::from_bits(raw as u32) as f64),
+        64 => Some(f64::from_bits(raw)),
+        _ => None,
+    }
+}
+
+/// Bytes from the most significant one as characters, leading zero bytes are skipped,
+/// unprintable bytes are `.` and bytes with unknown bits are `x` or `z`
+pub fn radix_vector_ascii(vec: &[WireValue]) -> String {
+    vec.chunks(8)
+        .rev()
+        .map(|byte| match vector_unknown(byte) {
+            Some(unknown) => Err(unknown),
+            None => Ok(byte
+                .iter()
+                .rev()
+                .fold(0u8, |acc, v| acc << 1 | value_map_val(v))),
+        })
+        .skip_while(|byte| *byte == Ok(0))
+        .map(|byte| match byte {
+            Ok(c @ 0x20..=0x7e) => (c as char).to_string(),
+            Ok(_) => ".".to_string(),
+            Err(unknown) => unknown.to_string(),
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
-    use crate::radix::{radix_value_big_uint, radix_vector_to_string, Radix};
+    use crate::radix::{
+        radix_value_big_uint, radix_vector_to_string, vector_float, vector_number, Radix,
+    };
     use crate::wave::WireValue::*;
     use crate::wave::{WaveDataItem, WaveDataValue, WireValue};
     use anyhow::Result;
@@ -211,6 +445,84 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_radix_modes() -> Result<()> {
+        // lsb first
+        let bits = |text: &str| {
+            text.chars()
+                .rev()
+                .map(|c| match c {
+                    '0' => V0,
+                    '1' => V1,
+                    'x' => X,
+                    _ => Z,
+                })
+                .collect::<Vec<_>>()
+        };
+        let to_string = |radix: &str, text: &str| -> Result<String> {
+            Ok(radix_vector_to_string(radix.parse()?, &bits(text)))
+        };
+        assert_eq!(to_string("sdec", "11111110")?, "-2");
+        assert_eq!(to_string("sdec", "01111111")?, "127");
+        assert_eq!(to_string("sdec", "10000000")?, "-128");
+        assert_eq!(to_string("sdec", "1x00")?, "xx");
+        assert_eq!(to_string("q4", "11111000")?, "-0.5");
+        assert_eq!(to_string("uq4.4", "11111000")?, "15.5");
+        assert_eq!(to_string("q1.2", "z10")?, "z");
+        // integer bits select lower bits of vector
+        assert_eq!(to_string("q2.2", "01111000")?, "-2");
+        assert_eq!(to_string("uq2.2", "x1111000")?, "2");
+        assert_eq!(to_string("half", "0011110000000000")?, "1");
+        assert_eq!(to_string("half", "1100000001000000")?, "-2.125");
+        assert_eq!(to_string("half", "0111110000000000")?, "inf");
+        assert_eq!(
+            to_string("float", "01000000010010010000111111011011")?,
+            "3.1415927"
+        );
+        assert_eq!(to_string("float", "x1000000010010010000111111011011")?, "x");
+        assert_eq!(
+            to_string(
+                "double",
+                "0011111111110000000000000000000000000000000000000000000000000000"
+            )?,
+            "1"
+        );
+        assert_eq!(to_string("ascii", "000000000100100001101001")?, "Hi");
+        assert_eq!(to_string("ascii", "0100100000000001xxxxxxxx")?, "H.x");
+        for radix in [
+            Radix::SignedDec,
+            Radix::Fixed {
+                int: None,
+                frac: 8,
+                signed: false,
+            },
+            Radix::Fixed {
+                int: Some(7),
+                frac: 8,
+                signed: true,
+            },
+            Radix::Ascii,
+        ] {
+            assert_eq!(radix.to_string().parse::<Radix>()?, radix);
+        }
+        for text in [
+            "q", "q.8", "q8.", "uq", "qu8", "xyzq.3", "seq.4", "q1.2.3", "q+8",
+        ] {
+            assert!(text.parse::<Radix>().is_err(), "{text}");
+        }
+        assert!("dex".parse::<Radix>().is_err());
+        assert_eq!(vector_number(&bits("11111110"), true), Some(-2.0));
+        assert_eq!(vector_number(&bits("11111110"), false), Some(254.0));
+        assert_eq!(vector_number(&bits("1z0"), false), None);
+        assert_eq!(vector_float(&bits("1100000001000000"), 16), Some(-2.125));
+        assert_eq!(
+            vector_float(&bits("x0111110000000000"), 16),
+            Some(f64::INFINITY)
+        );
+        assert_eq!(vector_float(&bits("01000000"), 8), None);
+        Ok(())
+    }
+
     #[test]
     fn test_radix_value_big_uint() {
         use WireValue::*;
@@ -269,7 +581,7 @@ mod test {
             .unwrap();
             let item_value_v = match &item.value {
                 WaveDataValue::Comp(v) => v.as_slice(),
-                WaveDataValue::Raw(_) => &[],
+                _ => &[],
             };
             let item_value = BigUint::from_bytes_le(item_value_v);
             println!(
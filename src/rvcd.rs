@@ -9,14 +9,20 @@ use crate::rpc::rvcd_rpc_client::RvcdRpcClient;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::rpc::{RvcdEmpty, RvcdRemoveClient};
 use crate::service::Service;
+use crate::shortcut::ShortcutAction;
 use crate::size::FileSizeUnit;
 use crate::tree_view::{TreeAction, TreeView};
 #[allow(unused_imports)]
 use crate::utils::{execute, file_basename};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::verilog::{parse_verilog_file, VerilogGotoSource, VerilogViewSource};
+use crate::view::layout::WaveLayout;
 use crate::view::signal::SignalView;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::view::value_table::ValueTable;
 use crate::view::{WaveView, SIGNAL_LEAF_HEIGHT_DEFAULT};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::wave::vcd_writer::write_vcd;
 use crate::wave::{Wave, WaveSignalInfo, WaveTreeNode};
 use eframe::emath::Align;
 use egui::WidgetText;
@@ -30,6 +36,7 @@ use egui_toast::{Toast, ToastKind, ToastOptions, Toasts};
 use num_traits::Float;
 use regex::Regex;
 use rfd::FileHandle;
+use std::collections::HashSet;
 use std::fmt::{Debug, Display, Formatter};
 #[allow(unused_imports)]
 use std::path::PathBuf;
@@ -47,6 +54,40 @@ pub enum State {
     Working,
 }
 
+/// Action when loaded file is modified on disk
+#[derive(serde::Deserialize, serde::Serialize, Default, PartialEq, Debug, Clone, Copy)]
+pub enum AutoReload {
+    #[default]
+    Off,
+    /// Ask before reloading
+    Prompt,
+    Automatic,
+}
+
+impl Display for AutoReload {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            AutoReload::Off => t!("menu.auto_reload_mode.off"),
+            AutoReload::Prompt => t!("menu.auto_reload_mode.prompt"),
+            AutoReload::Automatic => t!("menu.auto_reload_mode.automatic"),
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// Toast message with text, native toasts disappear after 5 seconds
+pub(crate) fn toast_new(kind: ToastKind, text: impl Into<String>) -> Toast {
+    #[cfg(not(target_arch = "wasm32"))]
+    let options = ToastOptions::default().duration_in_seconds(5.0);
+    #[cfg(target_arch = "wasm32")]
+    let options = ToastOptions::default();
+    Toast {
+        kind,
+        text: WidgetText::RichText(RichText::new(text)),
+        options,
+    }
+}
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
@@ -65,6 +106,23 @@ pub struct Rvcd {
     ///
     /// **Only available on native**
     pub filepath: String,
+    /// Load signal data on demand instead of parsing whole file.
+    ///
+    /// **Only available on native**
+    pub lazy_loading: bool,
+    /// Signals requested in lazy loading mode
+    #[serde(skip)]
+    pub lazy_requested: HashSet<u64>,
+    /// Last error of loading signals in lazy loading mode, reported once
+    #[serde(skip)]
+    pub lazy_error: Option<String>,
+    /// Action when loaded file is modified on disk.
+    ///
+    /// **Only available on native**
+    pub auto_reload: AutoReload,
+    /// Loaded file is modified, waiting for user to confirm reloading
+    #[serde(skip)]
+    pub reload_pending: bool,
     #[serde(skip)]
     pub load_progress: (f32, usize),
     #[serde(skip)]
@@ -142,6 +200,11 @@ impl Default for Rvcd {
             channel: None,
             loop_self: None,
             filepath: "".to_string(),
+            lazy_loading: false,
+            lazy_requested: HashSet::new(),
+            lazy_error: None,
+            auto_reload: AutoReload::default(),
+            reload_pending: false,
             load_progress: (0.0, 0),
             parse_progress: (0.0, 0),
             last_progress_msg: RvcdMsg::LoadingProgress(0.0, 0),
@@ -203,6 +266,15 @@ impl Rvcd {
                 "last file: {}; last source dir: {}",
                 filepath, self.source_dir
             );
+            channel_req_tx
+                .send(RvcdMsg::SetLazyLoading(self.lazy_loading))
+                .unwrap();
+            channel_req_tx
+                .send(RvcdMsg::SetFollow(self.view.follow))
+                .unwrap();
+            channel_req_tx
+                .send(RvcdMsg::SetWatchFile(self.auto_reload != AutoReload::Off))
+                .unwrap();
             if !filepath.is_empty() {
                 channel_req_tx
                     .send(RvcdMsg::FileOpen(rfd::FileHandle::from(
@@ -312,6 +384,7 @@ impl Rvcd {
                 self.message_handler(rx);
             }
         }
+        self.request_lazy_signals();
 
         #[cfg(not(target_arch = "wasm32"))]
         if let Some(rx) = &self.rpc_rx {
@@ -362,6 +435,27 @@ impl Rvcd {
                 });
             ctx.request_repaint();
         }
+        if self.reload_pending {
+            egui::Window::new(t!("reload.title"))
+                .id(Id::from(format!("reload_rvcd_{}", self.id)))
+                .resizable(false)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    ui.label(t!(
+                        "reload.message",
+                        file = file_basename(self.filepath.as_str())
+                    ));
+                    ui.horizontal(|ui| {
+                        if ui.button(t!("reload.reload")).clicked() {
+                            self.reload_pending = false;
+                            self.reload();
+                        }
+                        if ui.button(t!("reload.ignore")).clicked() {
+                            self.reload_pending = false;
+                        }
+                    });
+                });
+        }
         // auto update sources
         #[cfg(not(target_arch = "wasm32"))]
         if let Some(channel) = &self.channel {
@@ -477,6 +571,29 @@ impl Rvcd {
             }
         }
     }
+    /// Request data of added signals not loaded yet in lazy loading mode
+    fn request_lazy_signals(&mut self) {
+        if let (Some(wave), Some(channel)) = (&self.wave, &self.channel) {
+            if !wave.lazy {
+                return;
+            }
+            let ids = self
+                .view
+                .signals
+                .iter()
+                .map(|s| s.s.id)
+                .filter(|id| self.view.derived_get(*id).is_none())
+                .chain(self.view.derived_sources.iter().copied())
+                .filter(|id| !wave.data.contains_key(id) && !self.lazy_requested.contains(id))
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect::<Vec<_>>();
+            if !ids.is_empty() {
+                self.lazy_requested.extend(ids.iter().copied());
+                channel.tx.send(RvcdMsg::LoadSignals(ids)).unwrap();
+            }
+        }
+    }
     pub fn sidebar(&mut self, ui: &mut Ui) {
         // test if regex is valid
         let test_regex = Regex::new(if self.search_regex {
@@ -648,6 +765,16 @@ impl Rvcd {
                                 }
                             }
                         }
+                        TreeAction::AddGroup(nodes) => {
+                            let signals = nodes
+                                .into_iter()
+                                .filter_map(|(path, node)| match node {
+                                    WaveTreeNode::WaveVar(d) => Some((path, d.id)),
+                                    _ => None,
+                                })
+                                .collect();
+                            self.view.group_add_signals(signals, &wave.info);
+                        }
                     }
                 } else {
                     ui.centered_and_justified(|ui| ui.label(t!("sidebar.leaf.no_file")));
@@ -685,10 +812,13 @@ impl Rvcd {
             RvcdMsg::UpdateWave(wave) => {
                 info!("ui recv wave: {}", wave);
                 self.wave = Some(wave);
+                self.lazy_requested.clear();
+                self.lazy_error = None;
                 self.signal_leaves.clear();
                 if let Some(wave) = &self.wave {
                     self.view.signals_clean_unavailable(&wave.info);
                 }
+                self.view.derived_dirty = true;
                 // FIXME: update range
                 self.state = State::Working;
             }
@@ -764,6 +894,129 @@ impl Rvcd {
                     self.state = State::Loading;
                 }
             }
+            RvcdMsg::UpdateSignalData(data) => {
+                if let Some(wave) = &mut self.wave {
+                    wave.data.extend(data);
+                    self.view.derived_dirty = true;
+                    self.lazy_error = None;
+                }
+            }
+            RvcdMsg::LoadSignalsFailed(ids, error) => {
+                // requested again in next frame
+                for id in &ids {
+                    self.lazy_requested.remove(id);
+                }
+                if self.lazy_error.as_ref() != Some(&error) {
+                    self.toast(
+                        ToastKind::Error,
+                        t!(
+                            "msg.load_signals_failed",
+                            count = ids.len(),
+                            error = error.as_str()
+                        ),
+                    );
+                    self.lazy_error = Some(error);
+                }
+            }
+            RvcdMsg::SetFollow(follow) => {
+                // re-direct this to service side
+                if let Some(channel) = &self.channel {
+                    channel.tx.send(RvcdMsg::SetFollow(follow)).unwrap();
+                }
+            }
+            RvcdMsg::AppendWaveData(mut data, timestamp) => {
+                if let Some(wave) = &mut self.wave {
+                    if wave.lazy {
+                        // other signals will be loaded from extended index on demand
+                        data.retain(|id| wave.data.contains_key(id));
+                    }
+                    self.view.derived_appended.extend(data.keys().copied());
+                    wave.data.append(data);
+                    wave.info.range.1 = wave.info.range.1.max(timestamp);
+                    if self.view.follow && self.view.auto_scroll {
+                        self.view.scroll_to_end(&wave.info);
+                    }
+                }
+            }
+            RvcdMsg::FileModified(path) => {
+                if path == self.filepath && self.state == State::Working {
+                    match self.auto_reload {
+                        AutoReload::Off => {}
+                        AutoReload::Prompt => self.reload_pending = true,
+                        AutoReload::Automatic => self.reload(),
+                    }
+                }
+            }
+            RvcdMsg::ExportVcd(_path) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    match self.export_vcd(_path.as_str()) {
+                        Ok(skipped) if skipped.is_empty() => {
+                            self.toast(ToastKind::Success, t!("msg.export_vcd_done", file = _path))
+                        }
+                        Ok(skipped) => {
+                            warn!("signals skipped in exported vcd: {:?}", skipped);
+                            self.toast(
+                                ToastKind::Warning,
+                                t!(
+                                    "msg.export_vcd_skipped",
+                                    file = _path,
+                                    count = skipped.len(),
+                                    names = skipped.join(", ")
+                                ),
+                            );
+                        }
+                        Err(e) => {
+                            warn!("export vcd to {} failed: {}", _path, e);
+                            self.toast(ToastKind::Error, t!("msg.export_vcd_failed", error = e));
+                        }
+                    }
+                }
+            }
+            RvcdMsg::SaveLayout(_path) =>
+            {
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(wave) = &self.wave {
+                    match self.view.to_layout(&wave.info).save(_path.as_str()) {
+                        Ok(_) => {
+                            self.toast(ToastKind::Success, t!("msg.layout_saved", file = _path))
+                        }
+                        Err(e) => self.toast(ToastKind::Error, t!("msg.layout_failed", error = e)),
+                    }
+                }
+            }
+            RvcdMsg::LoadLayout(_path) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    let gtkw = _path.to_ascii_lowercase().ends_with(".gtkw");
+                    let layout = match &self.wave {
+                        // layout is only applied to loaded wave
+                        None if gtkw => Ok(WaveLayout::default()),
+                        Some(wave) if gtkw => WaveLayout::load_gtkw(_path.as_str(), &wave.info),
+                        _ => WaveLayout::load(_path.as_str()),
+                    };
+                    match layout {
+                        Ok(layout) => self.apply_layout(&layout),
+                        Err(e) => self.toast(ToastKind::Error, t!("msg.layout_failed", error = e)),
+                    }
+                }
+            }
+            RvcdMsg::LoadValueTable(_ids, _path, _radix) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    match ValueTable::load(_path.as_str(), _radix) {
+                        Ok(table) => self.view.value_table_set(&_ids, Some(table)),
+                        Err(e) => {
+                            self.toast(ToastKind::Error, t!("msg.value_table_failed", error = e))
+                        }
+                    }
+                }
+            }
+            RvcdMsg::SetWatchFile(_) => {}
+            RvcdMsg::ServiceFileChanged(..) => {}
+            RvcdMsg::SetLazyLoading(_) => {}
+            RvcdMsg::ServiceIndexReady(..) => {}
+            RvcdMsg::LoadSignals(_) => {}
             RvcdMsg::FileLoadCancel => {}
             RvcdMsg::ServiceDataReady(_) => {}
             RvcdMsg::StopService => {}
@@ -916,33 +1169,112 @@ impl Rvcd {
             }
         }
     }
+    /// Show a toast message, see [toast_new]
+    pub fn toast(&mut self, kind: ToastKind, text: impl Into<String>) {
+        self.toasts.add(toast_new(kind, text));
+    }
+    /// Apply layout to view of loaded wave, report signals not found
+    pub fn apply_layout(&mut self, layout: &WaveLayout) {
+        if let Some(wave) = &self.wave {
+            let unresolved = self.view.apply_layout(layout, &wave.info);
+            if unresolved.is_empty() {
+                self.toast(ToastKind::Success, t!("msg.layout_loaded"));
+            } else {
+                warn!("signals not found in layout: {:?}", unresolved);
+                self.toast(
+                    ToastKind::Warning,
+                    t!(
+                        "msg.layout_unresolved",
+                        count = unresolved.len(),
+                        paths = unresolved.join(", ")
+                    ),
+                );
+            }
+        }
+    }
     pub fn reset(&mut self) {
         self.wave = None;
         self.filepath.clear();
         self.signal_leaves.clear();
         self.state = State::Idle;
+        self.reload_pending = false;
         self.view = self.view.reset();
+        if let Some(channel) = &self.channel {
+            channel.tx.send(RvcdMsg::SetFollow(self.view.follow)).ok();
+        }
         self.title = format!("Rvcd-{}", self.id);
     }
+    /// Write signals in view and visible time window to vcd file,
+    /// return names of derived signals and signals not loaded yet, which are skipped
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export_vcd(&self, path: &str) -> anyhow::Result<Vec<String>> {
+        use std::io::Write;
+        let wave = self
+            .wave
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("no wave loaded"))?;
+        let mut visited = HashSet::new();
+        let mut ids = vec![];
+        let mut skipped = vec![];
+        for signal in &self.view.signals {
+            let id = signal.s.id;
+            if !visited.insert(id) {
+                continue;
+            }
+            let loaded = !wave.lazy || wave.data.contains_key(&id);
+            match self.view.derived_get(id).is_none() && loaded {
+                true => ids.push(id),
+                false => skipped.push(signal.s.name.clone()),
+            }
+        }
+        let from = (self.view.range.0.max(0.0) as u64).max(wave.info.range.0);
+        let to = (self.view.range.1.max(0.0) as u64).min(wave.info.range.1);
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        write_vcd(&mut writer, wave, &ids, (from, to.max(from)))?;
+        writer.flush()?;
+        Ok(skipped)
+    }
+    /// Pick a wave file to open
+    pub fn open_file_dialog(&self) {
+        if let Some(channel) = &self.channel {
+            let task = rfd::AsyncFileDialog::new()
+                .add_filter(t!("menu.wave_file"), &["vcd", "fst"])
+                .add_filter(t!("menu.vcd_file"), &["vcd"])
+                .add_filter(t!("menu.fst_file"), &["fst"])
+                .pick_file();
+            let sender = channel.tx.clone();
+            execute(async move {
+                let file = task.await;
+                if let Some(file) = file {
+                    // let path = PathBuf::from(file);
+                    // let path = file.path().to_str().unwrap().to_string();
+                    sender.send(RvcdMsg::FileOpen(file)).ok();
+                }
+            });
+        }
+    }
+    /// Run action of keyboard shortcut
+    pub fn handle_shortcut(&mut self, action: ShortcutAction) {
+        match action {
+            ShortcutAction::OpenFile => self.open_file_dialog(),
+            ShortcutAction::Reload => {
+                if self.wave.is_some() {
+                    self.reload();
+                }
+            }
+            action => {
+                if let Some(wave) = &self.wave {
+                    self.view.handle_shortcut(action, wave);
+                }
+            }
+        }
+    }
     pub fn menubar(&mut self, ui: &mut Ui, _maximum: bool) {
         egui::widgets::global_dark_light_mode_switch(ui);
         ui.menu_button(t!("menu.file"), |ui| {
             // #[cfg(not(target_arch = "wasm32"))]
             if ui.button(t!("menu.open")).clicked() {
-                if let Some(channel) = &self.channel {
-                    let task = rfd::AsyncFileDialog::new()
-                        .add_filter(t!("menu.vcd_file"), &["vcd"])
-                        .pick_file();
-                    let sender = channel.tx.clone();
-                    execute(async move {
-                        let file = task.await;
-                        if let Some(file) = file {
-                            // let path = PathBuf::from(file);
-                            // let path = file.path().to_str().unwrap().to_string();
-                            sender.send(RvcdMsg::FileOpen(file)).ok();
-                        }
-                    });
-                }
+                self.open_file_dialog();
                 ui.close_menu();
             }
             #[cfg(not(target_arch = "wasm32"))]
@@ -967,6 +1299,90 @@ impl Rvcd {
                 }
                 ui.close_menu();
             }
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui
+                .checkbox(&mut self.lazy_loading, t!("menu.lazy_loading"))
+                .changed()
+            {
+                if let Some(channel) = &self.channel {
+                    channel
+                        .tx
+                        .send(RvcdMsg::SetLazyLoading(self.lazy_loading))
+                        .unwrap();
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.menu_button(
+                format!("{}: {}", t!("menu.auto_reload"), self.auto_reload),
+                |ui| {
+                    use AutoReload::*;
+                    for mode in [Off, Prompt, Automatic] {
+                        if ui
+                            .radio_value(&mut self.auto_reload, mode, mode.to_string())
+                            .clicked()
+                        {
+                            if let Some(channel) = &self.channel {
+                                channel
+                                    .tx
+                                    .send(RvcdMsg::SetWatchFile(self.auto_reload != Off))
+                                    .unwrap();
+                            }
+                            ui.close_menu();
+                        }
+                    }
+                },
+            );
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.add_enabled_ui(
+                self.state == State::Working && !self.view.signals.is_empty(),
+                |ui| {
+                    if ui.button(t!("menu.export_vcd")).clicked() {
+                        let task = rfd::AsyncFileDialog::new()
+                            .add_filter(t!("menu.vcd_file"), &["vcd"])
+                            .set_file_name("export.vcd")
+                            .save_file();
+                        let loop_self = self.loop_self.clone();
+                        execute(async move {
+                            if let (Some(file), Some(loop_self)) = (task.await, loop_self) {
+                                let path = file.path().to_str().unwrap().to_string();
+                                loop_self.send(RvcdMsg::ExportVcd(path)).ok();
+                            }
+                        });
+                        ui.close_menu();
+                    }
+                },
+            );
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.add_enabled_ui(self.state == State::Working, |ui| {
+                if ui.button(t!("menu.save_layout")).clicked() {
+                    let task = rfd::AsyncFileDialog::new()
+                        .add_filter(t!("menu.layout_file"), &["json"])
+                        .set_file_name("layout.json")
+                        .save_file();
+                    let loop_self = self.loop_self.clone();
+                    execute(async move {
+                        if let (Some(file), Some(loop_self)) = (task.await, loop_self) {
+                            let path = file.path().to_str().unwrap().to_string();
+                            loop_self.send(RvcdMsg::SaveLayout(path)).ok();
+                        }
+                    });
+                    ui.close_menu();
+                }
+                if ui.button(t!("menu.load_layout")).clicked() {
+                    let task = rfd::AsyncFileDialog::new()
+                        .add_filter(t!("menu.layout_file"), &["json", "gtkw"])
+                        .add_filter(t!("menu.gtkw_file"), &["gtkw"])
+                        .pick_file();
+                    let loop_self = self.loop_self.clone();
+                    execute(async move {
+                        if let (Some(file), Some(loop_self)) = (task.await, loop_self) {
+                            let path = file.path().to_str().unwrap().to_string();
+                            loop_self.send(RvcdMsg::LoadLayout(path)).ok();
+                        }
+                    });
+                    ui.close_menu();
+                }
+            });
             ui.add_enabled_ui(self.state == State::Working, |ui| {
                 if ui.button(t!("menu.close")).clicked() {
                     ui.close_menu();
@@ -1,41 +1,86 @@
 use crate::message::{RvcdChannel, RvcdMsg};
 use crate::utils::{execute, sleep_ms};
+use crate::wave::fst_parser::Fst;
+use crate::wave::store::WaveStore;
+use crate::wave::vcd_index::{VcdIndex, VcdTail};
 use crate::wave::vcd_parser::Vcd;
-use crate::wave::{WaveLoader, WavePreLoader};
+use crate::wave::{Wave, WaveFormat, WaveLoader, WavePreLoader};
 use anyhow::Result;
 #[allow(unused_imports)]
+use std::collections::HashMap;
+#[allow(unused_imports)]
 use std::io::{BufReader, Cursor, Read};
 use std::sync::{mpsc, Arc, Mutex};
 use tracing::{debug, error, info};
 
+/// Interval in milliseconds to check modification of watched file
+pub const WATCH_INTERVAL_MS: u64 = 200;
+/// Watched file is reloaded after it is not modified for this time in milliseconds
+pub const WATCH_SETTLE_MS: u64 = 1000;
+
 pub struct Service {
     pub channel: RvcdChannel,
     pub self_loop: RvcdChannel,
     pub cancel: Arc<Mutex<bool>>,
     pub loading: Arc<Mutex<bool>>,
+    /// Name of file being loaded, used to detect wave format
+    pub file_name: Option<String>,
+    /// Whether to load signal data on demand, **only available on native**
+    pub lazy_loading: bool,
+    /// Indexed file path and index in lazy loading mode
+    pub index: Option<(String, VcdIndex)>,
+    /// Path of loaded file, **only available on native**
+    pub file_path: Option<String>,
+    /// Whether to follow value changes appended to loaded file
+    pub follow: bool,
+    /// Parser of value changes appended to loaded vcd file
+    pub tail: Option<VcdTail>,
+    /// Stop flag of running file watcher
+    pub watcher: Option<Arc<Mutex<bool>>>,
+    /// Whether to watch loaded file to reload it when modified
+    pub watch_file: bool,
 }
 
 unsafe impl Send for Service {}
 
 impl Service {
-    fn parse_data_send(&self, data: Vec<u8>) -> bool {
-        let (last_timestamp, reader) = Vcd::last_timestamp(BufReader::new(Cursor::new(data)));
-        let reader = reader.unwrap();
-        if let Ok(wave) = Vcd::load(
-            &mut reader.into_inner(),
-            |percent, pos| {
-                self.channel
-                    .tx
-                    .send(RvcdMsg::ParsingProgress(percent, pos))
-                    .unwrap();
-            },
-            last_timestamp,
-        ) {
-            info!("service load wave: {}", wave);
-            self.channel.tx.send(RvcdMsg::UpdateWave(wave)).unwrap();
-            true
-        } else {
-            false
+    fn parse_data_send(&mut self, data: Vec<u8>) -> bool {
+        let progress = |percent, pos| {
+            self.channel
+                .tx
+                .send(RvcdMsg::ParsingProgress(percent, pos))
+                .unwrap();
+        };
+        let format = WaveFormat::detect(&data, self.file_name.as_deref());
+        info!("service detected wave format: {:?}", format);
+        // followed value changes start after the last complete line
+        let len = match data.iter().rposition(|c| *c == b'\n') {
+            Some(p) => p as u64 + 1,
+            None => data.len() as u64,
+        };
+        let wave = match format {
+            WaveFormat::Fst => Fst::load(&mut Cursor::new(data), progress, None),
+            WaveFormat::Vcd => {
+                let (last_timestamp, reader) =
+                    Vcd::last_timestamp(BufReader::new(Cursor::new(data)));
+                let reader = reader.unwrap();
+                Vcd::load(&mut reader.into_inner(), progress, last_timestamp)
+            }
+        };
+        match wave {
+            Ok(wave) => {
+                info!("service load wave: {}", wave);
+                if format == WaveFormat::Vcd && self.file_path.is_some() {
+                    self.tail = Some(VcdTail::new(len, wave.info.range.1));
+                }
+                self.channel.tx.send(RvcdMsg::UpdateWave(wave)).unwrap();
+                self.update_watcher();
+                true
+            }
+            Err(e) => {
+                error!("service load wave failed: {}", e);
+                false
+            }
         }
     }
     #[cfg(not(target_arch = "wasm32"))]
@@ -48,6 +93,140 @@ impl Service {
         let data = Self::load_data(path, tx, cancel);
         loop_tx.send(RvcdMsg::ServiceDataReady(data)).unwrap();
     }
+    /// Build index of vcd file instead of reading all data, used in lazy loading mode
+    #[cfg(not(target_arch = "wasm32"))]
+    fn index_data_loop(
+        path: String,
+        tx: mpsc::Sender<RvcdMsg>,
+        loop_tx: mpsc::Sender<RvcdMsg>,
+        cancel: Arc<Mutex<bool>>,
+        loading: Arc<Mutex<bool>>,
+    ) {
+        let result = std::fs::File::open(path.as_str())
+            .map_err(anyhow::Error::from)
+            .and_then(|file| {
+                let total_sz = file.metadata()?.len().max(1);
+                let mut reader = BufReader::new(file);
+                VcdIndex::build(&mut reader, |pos| {
+                    tx.send(RvcdMsg::LoadingProgress(
+                        pos as f32 / total_sz as f32,
+                        pos as usize,
+                    ))
+                    .unwrap();
+                    match cancel.lock() {
+                        Ok(mut r) => {
+                            if *r {
+                                info!("cancel flag detected, false");
+                                *r = false;
+                                false
+                            } else {
+                                true
+                            }
+                        }
+                        Err(_e) => {
+                            error!("{}", _e);
+                            false
+                        }
+                    }
+                })
+            });
+        match result {
+            Ok((info, index)) => {
+                loop_tx
+                    .send(RvcdMsg::ServiceIndexReady(path, info, index))
+                    .unwrap();
+            }
+            Err(e) => {
+                error!("index file failed: {}", e);
+                *loading.lock().unwrap() = false;
+                tx.send(RvcdMsg::FileOpenFailed(path)).unwrap();
+            }
+        }
+    }
+    /// Check modification of file and notify service loop until stopped,
+    /// notify again when file is settled after modifications
+    #[cfg(not(target_arch = "wasm32"))]
+    fn watch_file_loop(path: String, loop_tx: mpsc::Sender<RvcdMsg>, stop: Arc<Mutex<bool>>) {
+        info!("start watching file: {}", path);
+        let stat = |path: &str| {
+            std::fs::metadata(path)
+                .ok()
+                .map(|m| (m.len(), m.modified().ok()))
+        };
+        let settle = std::time::Duration::from_millis(WATCH_SETTLE_MS);
+        let mut last = stat(path.as_str());
+        // time of last modification not settled yet
+        let mut modified_at = None;
+        while !*stop.lock().unwrap() {
+            std::thread::sleep(std::time::Duration::from_millis(WATCH_INTERVAL_MS));
+            let current = stat(path.as_str());
+            let settled = match (current != last, modified_at) {
+                (true, _) => {
+                    last = current;
+                    modified_at = Some(std::time::Instant::now());
+                    false
+                }
+                (false, Some(t)) if t.elapsed() >= settle => {
+                    modified_at = None;
+                    true
+                }
+                _ => continue,
+            };
+            if loop_tx
+                .send(RvcdMsg::ServiceFileChanged(path.clone(), settled))
+                .is_err()
+            {
+                break;
+            }
+        }
+        info!("stop watching file: {}", path);
+    }
+    fn stop_watcher(&mut self) {
+        if let Some(stop) = self.watcher.take() {
+            *stop.lock().unwrap() = true;
+        }
+    }
+    /// Start file watcher when following or watching loaded file, stop it otherwise
+    fn update_watcher(&mut self) {
+        self.stop_watcher();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let following = self.follow && self.tail.is_some();
+            if let (true, Some(path)) = (following || self.watch_file, &self.file_path) {
+                if following {
+                    // catch up changes appended before watching
+                    self.self_loop
+                        .tx
+                        .send(RvcdMsg::ServiceFileChanged(path.clone(), false))
+                        .unwrap();
+                }
+                let stop = Arc::new(Mutex::new(false));
+                let path = path.clone();
+                let loop_tx = self.self_loop.tx.clone();
+                let watcher_stop = stop.clone();
+                let _th =
+                    std::thread::spawn(move || Self::watch_file_loop(path, loop_tx, watcher_stop));
+                self.watcher = Some(stop);
+            }
+        }
+    }
+    /// Parse value changes appended to followed file and send them to ui
+    fn poll_tail(&mut self, path: &str) -> Result<()> {
+        if let Some(tail) = &mut self.tail {
+            let start = (tail.timestamp, tail.offset);
+            let mut file = std::fs::File::open(path)?;
+            if let Some(data) = tail.poll(&mut file)? {
+                if let Some((_, index)) = &mut self.index {
+                    index.extend(start, tail, &data);
+                }
+                self.channel
+                    .tx
+                    .send(RvcdMsg::AppendWaveData(data, tail.timestamp))
+                    .unwrap();
+            }
+        }
+        Ok(())
+    }
     #[cfg(not(target_arch = "wasm32"))]
     fn load_data(path: String, tx: mpsc::Sender<RvcdMsg>, cancel: Arc<Mutex<bool>>) -> Vec<u8> {
         let file = std::fs::File::open(path);
@@ -55,7 +234,8 @@ impl Service {
             Ok(file) => {
                 // TODO: cancel progress that loading to memory first
                 let total_sz = file.metadata().unwrap().len();
-                let mut reader = BufReader::new(file);
+                // file may grow while reading when followed
+                let mut reader = BufReader::new(file.take(total_sz));
                 if total_sz != 0 {
                     const BUF_SIZE: usize = 1024 * 256;
                     // const BUF_SIZE: usize = 8;
@@ -112,6 +292,14 @@ impl Service {
         match msg {
             RvcdMsg::FileOpen(file) => {
                 info!("loading file: {:?}", file);
+                self.file_name = Some(file.file_name());
+                self.index = None;
+                self.tail = None;
+                self.stop_watcher();
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    self.file_path = file.path().to_str().map(|p| p.to_string());
+                }
                 // let mut file = File::open(path.as_os_str().to_str().unwrap()).unwrap();
                 // let mut file = File::open(path.to_string()).unwrap();
                 #[cfg(not(target_arch = "wasm32"))]
@@ -146,8 +334,16 @@ impl Service {
                         let tx = self.channel.tx.clone();
                         let loop_tx = self.self_loop.tx.clone();
                         let cancel = self.cancel.clone();
+                        // fst files are compressed, so only index vcd files
+                        let lazy = self.lazy_loading
+                            && WaveFormat::detect_file(path.as_str()) == WaveFormat::Vcd;
+                        let loading = self.loading.clone();
                         let _th = std::thread::spawn(move || {
-                            Self::load_data_loop(path, tx, loop_tx, cancel)
+                            if lazy {
+                                Self::index_data_loop(path, tx, loop_tx, cancel, loading)
+                            } else {
+                                Self::load_data_loop(path, tx, loop_tx, cancel)
+                            }
                         });
                         *self.loading.lock().unwrap() = true;
                         None
@@ -171,6 +367,11 @@ impl Service {
                 }
             }
             RvcdMsg::FileOpenData(data) => {
+                self.file_name = None;
+                self.index = None;
+                self.file_path = None;
+                self.tail = None;
+                self.stop_watcher();
                 // TODO: reduce this data clone
                 if !self.parse_data_send(data.to_vec()) {
                     self.channel
@@ -187,8 +388,16 @@ impl Service {
                     }
                 }
             }
-            RvcdMsg::ServiceDataReady(data) => {
+            RvcdMsg::ServiceDataReady(mut data) => {
                 *self.loading.lock().unwrap() = false;
+                if self.follow
+                    && WaveFormat::detect(&data, self.file_name.as_deref()) == WaveFormat::Vcd
+                {
+                    // last line may be still being written, leave it to tail parser
+                    if let Some(p) = data.iter().rposition(|c| *c == b'\n') {
+                        data.truncate(p + 1);
+                    }
+                }
                 info!("start parsing data");
                 let time_start = std::time::Instant::now();
                 if !self.parse_data_send(data) {
@@ -201,7 +410,77 @@ impl Service {
                 let duration = time_stop - time_start;
                 info!("stop parsing data, used {} ms", duration.as_millis());
             }
-            RvcdMsg::StopService => return Ok(true),
+            RvcdMsg::ServiceIndexReady(path, info, index) => {
+                *self.loading.lock().unwrap() = false;
+                self.tail = Some(VcdTail::new(index.end, index.range.1));
+                self.index = Some((path, index));
+                let wave = Wave {
+                    info,
+                    data: WaveStore::default(),
+                    lazy: true,
+                };
+                info!("service load wave: {}", wave);
+                self.channel.tx.send(RvcdMsg::UpdateWave(wave)).unwrap();
+                self.update_watcher();
+            }
+            RvcdMsg::SetLazyLoading(lazy) => {
+                self.lazy_loading = lazy;
+            }
+            RvcdMsg::LoadSignals(ids) => {
+                if let Some((path, index)) = &self.index {
+                    let result = std::fs::File::open(path.as_str())
+                        .map_err(anyhow::Error::from)
+                        .and_then(|file| index.load_signals(&mut BufReader::new(file), &ids));
+                    match result {
+                        Ok(data) => {
+                            self.channel
+                                .tx
+                                .send(RvcdMsg::UpdateSignalData(data))
+                                .unwrap();
+                        }
+                        Err(e) => {
+                            error!("service load signals failed: {}", e);
+                            self.channel
+                                .tx
+                                .send(RvcdMsg::LoadSignalsFailed(ids, e.to_string()))
+                                .unwrap();
+                        }
+                    }
+                }
+            }
+            RvcdMsg::SetFollow(follow) => {
+                self.follow = follow;
+                self.update_watcher();
+            }
+            RvcdMsg::SetWatchFile(watch) => {
+                self.watch_file = watch;
+                self.update_watcher();
+            }
+            RvcdMsg::ServiceFileChanged(path, settled) => {
+                if self.file_path.as_deref() == Some(path.as_str()) {
+                    // appended value changes are handled in follow mode as they come,
+                    // other modifications need reloading when file is settled
+                    if !settled && self.follow && self.tail.is_some() {
+                        if let Err(e) = self.poll_tail(path.as_str()) {
+                            info!("service cannot follow file: {}", e);
+                            self.tail = None;
+                        }
+                    }
+                    let following = self.follow && self.tail.is_some();
+                    // file may be removed before written again
+                    if settled
+                        && !following
+                        && self.watch_file
+                        && std::path::Path::new(path.as_str()).exists()
+                    {
+                        self.channel.tx.send(RvcdMsg::FileModified(path)).unwrap();
+                    }
+                }
+            }
+            RvcdMsg::StopService => {
+                self.stop_watcher();
+                return Ok(true);
+            }
             RvcdMsg::UpdateSourceDir(_path) => {
                 #[cfg(not(target_arch = "wasm32"))]
                 {
@@ -238,6 +517,14 @@ impl Service {
             self_loop,
             cancel: Arc::new(Mutex::new(false)),
             loading: Arc::new(Mutex::new(false)),
+            file_name: None,
+            lazy_loading: false,
+            index: None,
+            file_path: None,
+            follow: false,
+            tail: None,
+            watcher: None,
+            watch_file: false,
         }
     }
 
@@ -0,0 +1,274 @@
+use egui::{Context, Event, Grid, Key, KeyboardShortcut, Modifiers, Window};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+/// Operations that can be bound to keyboard shortcuts
+#[derive(
+    serde::Deserialize, serde::Serialize, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy,
+)]
+pub enum ShortcutAction {
+    OpenFile,
+    Reload,
+    ZoomIn,
+    ZoomOut,
+    ZoomFit,
+    ZoomBack,
+    ZoomForward,
+    PanLeft,
+    PanRight,
+    AddCursor,
+    RemoveSignals,
+    ClearSelection,
+    FindValue,
+    NextEdge,
+    PrevEdge,
+    NextRisingEdge,
+    PrevRisingEdge,
+    NextFallingEdge,
+    PrevFallingEdge,
+}
+
+impl ShortcutAction {
+    pub const ALL: [ShortcutAction; 19] = [
+        ShortcutAction::OpenFile,
+        ShortcutAction::Reload,
+        ShortcutAction::ZoomIn,
+        ShortcutAction::ZoomOut,
+        ShortcutAction::ZoomFit,
+        ShortcutAction::ZoomBack,
+        ShortcutAction::ZoomForward,
+        ShortcutAction::PanLeft,
+        ShortcutAction::PanRight,
+        ShortcutAction::AddCursor,
+        ShortcutAction::RemoveSignals,
+        ShortcutAction::ClearSelection,
+        ShortcutAction::FindValue,
+        ShortcutAction::NextEdge,
+        ShortcutAction::PrevEdge,
+        ShortcutAction::NextRisingEdge,
+        ShortcutAction::PrevRisingEdge,
+        ShortcutAction::NextFallingEdge,
+        ShortcutAction::PrevFallingEdge,
+    ];
+    pub fn name(&self) -> String {
+        match self {
+            ShortcutAction::OpenFile => t!("shortcut.action.open_file"),
+            ShortcutAction::Reload => t!("shortcut.action.reload"),
+            ShortcutAction::ZoomIn => t!("shortcut.action.zoom_in"),
+            ShortcutAction::ZoomOut => t!("shortcut.action.zoom_out"),
+            ShortcutAction::ZoomFit => t!("shortcut.action.zoom_fit"),
+            ShortcutAction::ZoomBack => t!("shortcut.action.zoom_back"),
+            ShortcutAction::ZoomForward => t!("shortcut.action.zoom_forward"),
+            ShortcutAction::PanLeft => t!("shortcut.action.pan_left"),
+            ShortcutAction::PanRight => t!("shortcut.action.pan_right"),
+            ShortcutAction::AddCursor => t!("shortcut.action.add_cursor"),
+            ShortcutAction::RemoveSignals => t!("shortcut.action.remove_signals"),
+            ShortcutAction::ClearSelection => t!("shortcut.action.clear_selection"),
+            ShortcutAction::FindValue => t!("shortcut.action.find_value"),
+            ShortcutAction::NextEdge => t!("shortcut.action.next_edge"),
+            ShortcutAction::PrevEdge => t!("shortcut.action.prev_edge"),
+            ShortcutAction::NextRisingEdge => t!("shortcut.action.next_rising_edge"),
+            ShortcutAction::PrevRisingEdge => t!("shortcut.action.prev_rising_edge"),
+            ShortcutAction::NextFallingEdge => t!("shortcut.action.next_falling_edge"),
+            ShortcutAction::PrevFallingEdge => t!("shortcut.action.prev_falling_edge"),
+        }
+        .to_string()
+    }
+    pub fn default_shortcut(&self) -> Option<Shortcut> {
+        let (modifiers, key) = match self {
+            ShortcutAction::OpenFile => (Modifiers::COMMAND, Key::O),
+            ShortcutAction::Reload => (Modifiers::NONE, Key::F5),
+            ShortcutAction::ZoomIn => (Modifiers::NONE, Key::Equals),
+            ShortcutAction::ZoomOut => (Modifiers::NONE, Key::Minus),
+            ShortcutAction::ZoomFit => (Modifiers::NONE, Key::F),
+            ShortcutAction::ZoomBack => (Modifiers::NONE, Key::Backspace),
+            ShortcutAction::ZoomForward => (Modifiers::SHIFT, Key::Backspace),
+            ShortcutAction::PanLeft => (Modifiers::COMMAND, Key::ArrowLeft),
+            ShortcutAction::PanRight => (Modifiers::COMMAND, Key::ArrowRight),
+            ShortcutAction::AddCursor => (Modifiers::NONE, Key::C),
+            ShortcutAction::RemoveSignals => (Modifiers::NONE, Key::Delete),
+            ShortcutAction::ClearSelection => (Modifiers::NONE, Key::Escape),
+            ShortcutAction::FindValue => (Modifiers::COMMAND, Key::F),
+            ShortcutAction::NextEdge => (Modifiers::NONE, Key::ArrowRight),
+            ShortcutAction::PrevEdge => (Modifiers::NONE, Key::ArrowLeft),
+            ShortcutAction::NextRisingEdge => (Modifiers::SHIFT, Key::ArrowRight),
+            ShortcutAction::PrevRisingEdge => (Modifiers::SHIFT, Key::ArrowLeft),
+            ShortcutAction::NextFallingEdge => (Modifiers::ALT, Key::ArrowRight),
+            ShortcutAction::PrevFallingEdge => (Modifiers::ALT, Key::ArrowLeft),
+        };
+        Some(Shortcut { modifiers, key })
+    }
+}
+
+/// Key with modifiers
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Debug, Clone, Copy)]
+pub struct Shortcut {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+impl Shortcut {
+    pub fn to_egui(self) -> KeyboardShortcut {
+        KeyboardShortcut::new(self.modifiers, self.key)
+    }
+    /// Count of pressed modifiers, `Ctrl` and `Cmd` are the same
+    fn modifier_count(&self) -> usize {
+        let m = &self.modifiers;
+        [m.alt, m.shift, m.ctrl || m.command || m.mac_cmd]
+            .into_iter()
+            .filter(|b| *b)
+            .count()
+    }
+    /// Same key with same logical modifiers
+    fn conflicts(&self, other: &Shortcut) -> bool {
+        self.key == other.key
+            && self.modifier_count() == other.modifier_count()
+            && self.modifiers.alt == other.modifiers.alt
+            && self.modifiers.shift == other.modifiers.shift
+    }
+}
+
+/// Keyboard shortcut bindings, saved in app storage
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Shortcuts {
+    /// Bindings changed by user, `None` for unbound, others use default ones
+    pub bindings: BTreeMap<ShortcutAction, Option<Shortcut>>,
+    /// Whether settings window is shown
+    #[serde(skip)]
+    pub open: bool,
+    /// Action waiting for a key press to bind
+    #[serde(skip)]
+    capturing: Option<ShortcutAction>,
+}
+
+impl Shortcuts {
+    pub fn get(&self, action: ShortcutAction) -> Option<Shortcut> {
+        match self.bindings.get(&action) {
+            Some(shortcut) => *shortcut,
+            None => action.default_shortcut(),
+        }
+    }
+    /// Bind `shortcut` to `action`, other actions using the same shortcut are unbound
+    pub fn set(&mut self, action: ShortcutAction, shortcut: Option<Shortcut>) {
+        if let Some(shortcut) = &shortcut {
+            for other in ShortcutAction::ALL {
+                if other != action && self.get(other).is_some_and(|s| s.conflicts(shortcut)) {
+                    self.bindings.insert(other, None);
+                }
+            }
+        }
+        self.bindings.insert(action, shortcut);
+    }
+    pub fn reset(&mut self) {
+        self.bindings.clear();
+    }
+    /// Consume key presses of bound shortcuts, nothing when typing in text fields
+    pub fn take_actions(&self, ctx: &Context) -> Vec<ShortcutAction> {
+        if ctx.wants_keyboard_input() || self.capturing.is_some() {
+            return vec![];
+        }
+        let mut bindings = ShortcutAction::ALL
+            .into_iter()
+            .filter_map(|action| self.get(action).map(|s| (action, s)))
+            .collect::<Vec<_>>();
+        // try `Shift+Right` before `Right`, which also matches `Shift+Right`
+        bindings.sort_by_key(|(_, s)| Reverse(s.modifier_count()));
+        ctx.input_mut(|i| {
+            bindings
+                .into_iter()
+                .filter(|(_, s)| i.consume_shortcut(&s.to_egui()))
+                .map(|(action, _)| action)
+                .collect()
+        })
+    }
+    /// Paint settings window to rebind shortcuts
+    pub fn ui(&mut self, ctx: &Context) {
+        if !self.open {
+            self.capturing = None;
+            return;
+        }
+        if let Some(action) = self.capturing {
+            let pressed = ctx.input(|i| {
+                i.events.iter().find_map(|e| match e {
+                    Event::Key {
+                        key,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } => Some((*key, *modifiers)),
+                    _ => None,
+                })
+            });
+            if let Some((key, modifiers)) = pressed {
+                self.capturing = None;
+                // `Esc` cancels binding
+                if key != Key::Escape {
+                    self.set(action, Some(Shortcut { modifiers, key }));
+                }
+            }
+        }
+        let mut open = self.open;
+        Window::new(t!("shortcut.title"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                Grid::new("shortcut_settings")
+                    .num_columns(4)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for action in ShortcutAction::ALL {
+                            ui.label(action.name());
+                            let text = match (self.capturing == Some(action), self.get(action)) {
+                                (true, _) => t!("shortcut.press_key").to_string(),
+                                (false, Some(s)) => ctx.format_shortcut(&s.to_egui()),
+                                (false, None) => t!("shortcut.none").to_string(),
+                            };
+                            ui.monospace(text);
+                            if ui.button(t!("shortcut.set")).clicked() {
+                                self.capturing = Some(action);
+                            }
+                            if ui.button(t!("shortcut.clear")).clicked() {
+                                self.set(action, None);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                ui.separator();
+                if ui.button(t!("shortcut.reset")).clicked() {
+                    self.reset();
+                }
+            });
+        self.open = open;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shortcut::{Shortcut, ShortcutAction, Shortcuts};
+    use egui::{Key, Modifiers};
+
+    #[test]
+    fn test_shortcuts() -> anyhow::Result<()> {
+        let mut shortcuts = Shortcuts::default();
+        let right = ShortcutAction::NextEdge.default_shortcut();
+        assert_eq!(shortcuts.get(ShortcutAction::NextEdge), right);
+        // binding a used shortcut unbinds the old action
+        shortcuts.set(ShortcutAction::PanRight, right);
+        assert_eq!(shortcuts.get(ShortcutAction::PanRight), right);
+        assert_eq!(shortcuts.get(ShortcutAction::NextEdge), None);
+        assert!(shortcuts.get(ShortcutAction::NextRisingEdge).is_some());
+        let ctrl_g = Shortcut {
+            modifiers: Modifiers::CTRL | Modifiers::COMMAND,
+            key: Key::G,
+        };
+        shortcuts.set(ShortcutAction::ZoomFit, Some(ctrl_g));
+        let text = serde_json::to_string(&shortcuts)?;
+        let loaded: Shortcuts = serde_json::from_str(&text)?;
+        assert_eq!(loaded.get(ShortcutAction::ZoomFit), Some(ctrl_g));
+        assert_eq!(loaded.get(ShortcutAction::NextEdge), None);
+        shortcuts.reset();
+        assert_eq!(shortcuts.get(ShortcutAction::NextEdge), right);
+        Ok(())
+    }
+}
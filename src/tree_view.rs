@@ -29,6 +29,30 @@ pub enum TreeAction {
     AddSignal(WaveTreeNode),
     AddSignals(Vec<WaveTreeNode>),
     SelectScope(Vec<WaveTreeNode>),
+    /// Add signals in nested groups: (scope names from the added scope, signal)
+    AddGroup(Vec<(Vec<String>, WaveTreeNode)>),
+}
+
+/// Signals in scope `tree` and its sub scopes, with scope names from `tree`
+fn scope_group_signals(
+    tree: &Node<WaveTreeNode>,
+    path: &mut Vec<String>,
+) -> Vec<(Vec<String>, WaveTreeNode)> {
+    if let WaveTreeNode::WaveScope(scope) = tree.data() {
+        path.push(scope.name.to_string());
+    }
+    let mut signals = vec![];
+    for node in tree.iter() {
+        match node.data() {
+            WaveTreeNode::WaveVar(_) => signals.push((path.clone(), node.data().clone())),
+            WaveTreeNode::WaveScope(_) => signals.extend(scope_group_signals(node, path)),
+            _ => {}
+        }
+    }
+    if let WaveTreeNode::WaveScope(_) = tree.data() {
+        path.pop();
+    }
+    signals
 }
 
 impl TreeView {
@@ -65,6 +89,7 @@ impl TreeView {
         let handle_scope_response = |response: Response| {
             let mut add_all = false;
             let mut recurse_add_all = false;
+            let mut add_as_group = false;
             response.context_menu(|ui| {
                 if ui.button(t!("sst.signal.add_all")).clicked() {
                     add_all = true;
@@ -74,11 +99,17 @@ impl TreeView {
                     recurse_add_all = true;
                     ui.close_menu();
                 }
+                if ui.button(t!("sst.signal.add_as_group")).clicked() {
+                    add_as_group = true;
+                    ui.close_menu();
+                }
             });
             if add_all {
                 TreeAction::AddSignals(child_signals(tree))
             } else if recurse_add_all {
                 TreeAction::AddSignals(recurse_child_signals(tree))
+            } else if add_as_group {
+                TreeAction::AddGroup(scope_group_signals(tree, &mut vec![]))
             } else {
                 TreeAction::None
             }
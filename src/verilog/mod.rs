@@ -160,6 +160,21 @@ impl VerilogSource {
         (line, offset - offset_now)
     }
 
+    /// Parameters used as `case` labels of signal at `path`, see [VerilogModule::case_params]
+    pub fn search_case_params(&self, path: &Vec<String>) -> Vec<(String, String)> {
+        self.search_path(path)
+            .into_iter()
+            .filter_map(|(path, _)| {
+                let module = self
+                    .modules
+                    .iter()
+                    .find(|m| Some(&m.name) == path.first())?;
+                Some(module.case_params(path.last()?))
+            })
+            .find(|params| !params.is_empty())
+            .unwrap_or_default()
+    }
+
     // pub fn get_code_from_interval(&self, location: &CodeInterval) -> u64 {
     //     let data = &self.source_code.0;
     //     let tf = CommonTokenFactory::default();
@@ -190,6 +205,38 @@ pub struct VerilogModule {
     pub ports: Vec<VerilogPort>,
     pub regs: Vec<VerilogReg>,
     pub wires: Vec<VerilogWire>,
+    pub params: Vec<VerilogParam>,
+    pub cases: Vec<VerilogCase>,
+}
+impl VerilogModule {
+    /// Parameters used as `case` labels of `reg`, as (name, value expression),
+    /// values referring to other parameters are resolved
+    pub fn case_params(&self, reg: &str) -> Vec<(String, String)> {
+        let find = |name: &str| self.params.iter().rev().find(|p| p.name == name);
+        let mut params: Vec<(String, String)> = vec![];
+        for label in self
+            .cases
+            .iter()
+            .filter(|c| c.expression == reg)
+            .flat_map(|c| c.labels.iter())
+        {
+            if params.iter().any(|(name, _)| name == label) {
+                continue;
+            }
+            if let Some(param) = find(label) {
+                let mut value = &param.value;
+                // limit depth in case parameters refer to each other
+                for _ in 0..8 {
+                    match find(value) {
+                        Some(p) => value = &p.value,
+                        None => break,
+                    }
+                }
+                params.push((param.name.clone(), value.clone()));
+            }
+        }
+        params
+    }
 }
 #[derive(Default, Debug, Clone)]
 pub enum VerilogPortType {
@@ -214,6 +261,77 @@ pub struct VerilogWire {
     pub name: String,
     pub location: CodeLocation,
 }
+#[derive(Default, Debug, Clone)]
+pub struct VerilogParam {
+    pub name: String,
+    /// Expression text without spaces, like `2'd1`
+    pub value: String,
+    pub location: CodeLocation,
+}
+/// Labels of a `case` statement
+#[derive(Default, Debug, Clone)]
+pub struct VerilogCase {
+    /// Case expression text without spaces, like `state`
+    pub expression: String,
+    /// Label expressions, `default` is not included
+    pub labels: Vec<String>,
+}
+
+/// Split `text` at `sep` that is not in brackets
+fn split_top_level(text: &str, sep: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            c if c == sep && depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Expression of case statement text like `case(state)IDLE:...endcase`
+fn case_expression(text: &str) -> String {
+    let rest = match text.split_once('(') {
+        Some((_, rest)) => rest,
+        None => return String::new(),
+    };
+    let mut depth = 1;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return rest[..i].to_string();
+                }
+            }
+            _ => {}
+        }
+    }
+    String::new()
+}
+
+/// Labels of case item text like `IDLE,BUSY:begin...end`
+fn case_item_labels(text: &str) -> Vec<String> {
+    let parts = split_top_level(text, ':');
+    if parts.len() < 2 {
+        return vec![];
+    }
+    split_top_level(parts[0], ',')
+        .into_iter()
+        .filter(|label| !label.is_empty() && *label != "default")
+        .map(|label| label.to_string())
+        .collect()
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct MyVerilogListener {
     pub source: VerilogSource,
@@ -221,6 +339,8 @@ pub struct MyVerilogListener {
     pub port: Option<VerilogPort>,
     pub reg: Option<VerilogReg>,
     pub wire: Option<VerilogWire>,
+    /// Case statements being parsed, inner ones last
+    pub cases: Vec<VerilogCase>,
 }
 
 impl MyVerilogListener {
@@ -367,6 +487,38 @@ impl<'i> VerilogParserListener<'i> for MyVerilogListener {
             .push(self.reg.replace(Default::default()).unwrap());
     }
 
+    fn exit_param_assignment(&mut self, ctx: &Param_assignmentContext<'i>) {
+        // identifiers may also appear in value, so split the text
+        if let Some((name, value)) = ctx.get_text().split_once('=') {
+            let start = ctx.start();
+            self.module.as_mut().unwrap().params.push(VerilogParam {
+                name: name.to_string(),
+                value: value.to_string(),
+                location: CodeLocation {
+                    line: start.line,
+                    column: start.column,
+                },
+            });
+        }
+    }
+
+    fn enter_case_statement(&mut self, _ctx: &Case_statementContext<'i>) {
+        self.cases.push(Default::default());
+    }
+
+    fn exit_case_item(&mut self, ctx: &Case_itemContext<'i>) {
+        if let Some(case) = self.cases.last_mut() {
+            case.labels.extend(case_item_labels(&ctx.get_text()));
+        }
+    }
+
+    fn exit_case_statement(&mut self, ctx: &Case_statementContext<'i>) {
+        if let Some(mut case) = self.cases.pop() {
+            case.expression = case_expression(&ctx.get_text());
+            self.module.as_mut().unwrap().cases.push(case);
+        }
+    }
+
     fn exit_module_declaration(&mut self, ctx: &Module_declarationContext<'i>) {
         self.source
             .modules
@@ -469,4 +621,31 @@ mod test {
         let r = parse_verilog_file("data/code-sample/waterfall.v").unwrap();
         println!("data: {:?}", r);
     }
+
+    #[test]
+    fn test_case_params() {
+        let r = parse_verilog_file("data/code-sample/fsm.v").unwrap();
+        let module = &r.modules[0];
+        assert_eq!(module.params.len(), 6);
+        assert_eq!(module.params[1].name, "IDLE");
+        assert_eq!(module.params[1].value, "2'd0");
+        let params = r.search_case_params(&vec!["fsm".to_string(), "state".to_string()]);
+        let expected = [
+            ("IDLE", "2'd0"),
+            ("LOAD", "2'd1"),
+            ("RUN", "2'b10"),
+            ("HALT", "3"),
+        ];
+        assert_eq!(
+            params,
+            expected
+                .iter()
+                .map(|(a, b)| (a.to_string(), b.to_string()))
+                .collect::<Vec<_>>()
+        );
+        // labels of inner case are not parameters
+        assert!(r
+            .search_case_params(&vec!["fsm".to_string(), "done".to_string()])
+            .is_empty());
+    }
 }
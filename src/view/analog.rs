@@ -0,0 +1,369 @@
+use crate::radix::{vector_float, vector_number};
+use crate::view::signal::{AnalogDisplayType, SignalView, SignalViewMode};
+use crate::view::{
+    WaveView, BG_MULTIPLY, BUSY_SIGNAL_WIDTH, LINE_WIDTH, TEXT_BG_MULTIPLY, TEXT_ROUND_OFFSET,
+};
+use crate::wave::derive::value_bits;
+use crate::wave::lod::PaintChange;
+use crate::wave::{WaveDataItem, WaveDataValue, WaveInfo};
+use egui::*;
+use std::ops::RangeInclusive;
+
+/// How bits of analog signal are read as numbers
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Debug, Clone, Copy, Default)]
+pub enum AnalogValueType {
+    #[default]
+    Unsigned,
+    /// Two's complement
+    Signed,
+    /// IEEE-754 float, half, single or double precision by signal width of 16, 32 or 64
+    Real,
+}
+
+/// Value interpretation and vertical scale of analog signal
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Debug, Clone, Default)]
+#[serde(default)]
+pub struct AnalogOptions {
+    pub value_type: AnalogValueType,
+    /// Values at bottom and top of signal row, min and max of visible values when `None`
+    pub range: Option<(f64, f64)>,
+    /// Horizontal grid lines with value labels, evenly spaced from bottom to top
+    pub grid_lines: u32,
+}
+
+impl AnalogOptions {
+    /// Number of `value` of signal in `width` bits, `None` if it has unknown bits
+    /// or it is [AnalogValueType::Real] in other widths than 16, 32 and 64
+    pub fn value(&self, value: &WaveDataValue, width: u64) -> Option<f64> {
+        let value = match value {
+            WaveDataValue::Real(v) => Some(*v),
+            WaveDataValue::Str(_) => None,
+            _ => {
+                let mut bits = value_bits(value, width);
+                bits.reverse();
+                match self.value_type {
+                    AnalogValueType::Unsigned => vector_number(&bits, false),
+                    AnalogValueType::Signed => vector_number(&bits, true),
+                    AnalogValueType::Real => vector_float(&bits, width as usize),
+                }
+            }
+        };
+        value.filter(|v| v.is_finite())
+    }
+    /// Min and max of values merged in busy `change`,
+    /// summaries only know unsigned numbers and reals
+    pub fn busy_range(&self, change: &PaintChange) -> Option<(f64, f64)> {
+        let range = change.busy?.range()?;
+        match (self.value_type, &change.item.value) {
+            (_, WaveDataValue::Real(_)) | (AnalogValueType::Unsigned, _) => Some(range),
+            _ => None,
+        }
+    }
+    /// Range of all values in `width` bits, used as initial manual range
+    pub fn full_range(&self, width: u64) -> (f64, f64) {
+        match self.value_type {
+            AnalogValueType::Unsigned => (0.0, 2f64.powi(width as i32) - 1.0),
+            AnalogValueType::Signed => {
+                let half = 2f64.powi(width as i32 - 1);
+                (-half, half - 1.0)
+            }
+            AnalogValueType::Real => (-1.0, 1.0),
+        }
+    }
+    /// Values at bottom and top, manual range or min and max of `values`
+    pub fn scale(&self, values: impl Iterator<Item = f64>) -> Option<(f64, f64)> {
+        match self.range {
+            Some(range) => Some(range),
+            None => values.fold(None, |scale, v| match scale {
+                Some((min, max)) => Some((f64::min(min, v), f64::max(max, v))),
+                None => Some((v, v)),
+            }),
+        }
+    }
+    /// Values of grid lines in scale
+    pub fn grid_values(&self, (min, max): (f64, f64)) -> Vec<f64> {
+        match self.grid_lines {
+            0 => vec![],
+            1 => vec![(min + max) / 2.0],
+            n => (0..n)
+                .map(|i| min + (max - min) * i as f64 / (n - 1) as f64)
+                .collect(),
+        }
+    }
+}
+
+/// Y of `value` in scale from `bottom` to `top`, values out of scale are clamped
+pub fn analog_value_to_y(value: f64, (min, max): (f64, f64), top: f32, bottom: f32) -> f32 {
+    if max > min {
+        let ratio = ((max - value) / (max - min)).clamp(0.0, 1.0) as f32;
+        top + ratio * (bottom - top)
+    } else {
+        (top + bottom) / 2.0
+    }
+}
+
+/// Short text of value in grid labels
+fn analog_label(value: f64) -> String {
+    let text = format!("{:.3}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" => "0".to_string(),
+        text => text.to_string(),
+    }
+}
+
+/// Paint analog options in signal menu
+pub fn ui_analog_options(ui: &mut Ui, options: &mut AnalogOptions, width: u64) {
+    ui.horizontal(|ui| {
+        for (value_type, text) in [
+            (AnalogValueType::Unsigned, t!("view.analog.unsigned")),
+            (AnalogValueType::Signed, t!("view.analog.signed")),
+            (AnalogValueType::Real, t!("view.analog.real")),
+        ] {
+            let enabled = value_type != AnalogValueType::Real || matches!(width, 16 | 32 | 64);
+            if ui
+                .add_enabled(
+                    enabled,
+                    SelectableLabel::new(options.value_type == value_type, text),
+                )
+                .clicked()
+                && options.value_type != value_type
+            {
+                options.value_type = value_type;
+                options.range = options.range.map(|_| options.full_range(width));
+            }
+        }
+    });
+    let mut auto = options.range.is_none();
+    if ui
+        .checkbox(&mut auto, t!("view.analog.auto_range"))
+        .changed()
+    {
+        options.range = match auto {
+            true => None,
+            false => Some(options.full_range(width)),
+        };
+    }
+    if let Some((min, max)) = options.range.as_mut() {
+        let speed = f64::max((*max - *min).abs() / 100.0, 0.01);
+        ui.horizontal(|ui| {
+            ui.label(t!("view.analog.min"));
+            ui.add(DragValue::new(min).speed(speed));
+            ui.label(t!("view.analog.max"));
+            ui.add(DragValue::new(max).speed(speed));
+        });
+    }
+    ui.horizontal(|ui| {
+        ui.label(t!("view.analog.grid_lines"));
+        ui.add(DragValue::new(&mut options.grid_lines).clamp_range(0..=8));
+    });
+}
+
+impl WaveView {
+    /// Paint analog wave of `changes` with grid lines, and value at pointer on hover
+    pub(crate) fn paint_analog(
+        &self,
+        painter: &Painter,
+        response: &Response,
+        signal: &SignalView,
+        changes: &[PaintChange],
+        info: &WaveInfo,
+        signal_rect: Rect,
+    ) {
+        let options = &signal.analog;
+        let width = signal.s.width;
+        let scale = options.scale(changes.iter().flat_map(|change| {
+            let busy = options.busy_range(change);
+            options
+                .value(&change.item.value, width)
+                .into_iter()
+                .chain(busy.into_iter().flat_map(|(min, max)| [min, max]))
+        }));
+        let scale = match scale {
+            Some(scale) => scale,
+            None => {
+                painter.rect(
+                    Rect::from_x_y_ranges(
+                        RangeInclusive::new(
+                            self.fpos_to_x(self.range.0 as f32),
+                            self.fpos_to_x(self.range.1 as f32),
+                        ),
+                        response.rect.y_range(),
+                    ),
+                    0.0,
+                    Color32::RED.linear_multiply(BG_MULTIPLY),
+                    (LINE_WIDTH, Color32::RED),
+                );
+                return;
+            }
+        };
+        let (top, bottom) = (response.rect.top(), response.rect.bottom());
+        let timestamp_to_x = |timestamp: u64| {
+            signal_rect.left()
+                + signal_rect.width()
+                    * ((timestamp - info.range.0) as f64 / (self.range.1 - self.range.0)) as f32
+        };
+        // grid lines below wave
+        let grid_color = painter.ctx().style().visuals.weak_text_color();
+        let label_font = FontId::monospace(self.signal_font_size * 0.8);
+        for value in options.grid_values(scale) {
+            let y = analog_value_to_y(value, scale, top, bottom);
+            painter.hline(
+                response.rect.x_range(),
+                y,
+                (LINE_WIDTH, grid_color.linear_multiply(TEXT_BG_MULTIPLY)),
+            );
+            painter.text(
+                pos2(response.rect.left() + TEXT_ROUND_OFFSET, y),
+                match y - top < label_font.size {
+                    true => Align2::LEFT_TOP,
+                    false => Align2::LEFT_BOTTOM,
+                },
+                analog_label(value),
+                label_font.clone(),
+                grid_color,
+            );
+        }
+        let step = signal.mode == SignalViewMode::Analog(AnalogDisplayType::Step);
+        let paint_analog = |change: &PaintChange, item_next: &WaveDataItem| {
+            let item_now = &change.item;
+            let (left, right) = (
+                timestamp_to_x(item_now.timestamp),
+                timestamp_to_x(item_next.timestamp),
+            );
+            if let Some(busy) = change.busy {
+                // span of merged values, or full height if they are unknown
+                let y_range = match options.busy_range(change) {
+                    Some((min, max)) => RangeInclusive::new(
+                        analog_value_to_y(max, scale, top, bottom),
+                        analog_value_to_y(min, scale, top, bottom),
+                    ),
+                    None => RangeInclusive::new(top, bottom),
+                };
+                painter.rect_filled(
+                    Rect::from_x_y_ranges(left..=right.max(left + BUSY_SIGNAL_WIDTH), y_range),
+                    0.0,
+                    match busy.has_x {
+                        true => Color32::DARK_RED,
+                        false => signal.color,
+                    },
+                );
+                return;
+            }
+            let value_now = match options.value(&item_now.value, width) {
+                Some(value) => value,
+                None => {
+                    painter.rect(
+                        Rect::from_x_y_ranges(left..=right, top..=bottom),
+                        0.0,
+                        Color32::RED.linear_multiply(BG_MULTIPLY),
+                        (LINE_WIDTH, Color32::RED),
+                    );
+                    return;
+                }
+            };
+            let value_next = options.value(&item_next.value, width).unwrap_or(value_now);
+            let y_now = analog_value_to_y(value_now, scale, top, bottom);
+            let y_next = analog_value_to_y(value_next, scale, top, bottom);
+            let y_right = if step { y_now } else { y_next };
+            painter.line_segment(
+                [pos2(left, y_now), pos2(right, y_right)],
+                (LINE_WIDTH, signal.color),
+            );
+            if step {
+                painter.vline(
+                    right,
+                    RangeInclusive::new(y_now, y_next),
+                    (LINE_WIDTH, signal.color),
+                );
+            }
+        };
+        for pair in changes.windows(2) {
+            paint_analog(&pair[0], &pair[1].item);
+        }
+        // last analog value
+        if let Some(change_last) = changes.last() {
+            paint_analog(
+                change_last,
+                &WaveDataItem {
+                    timestamp: u64::min(info.range.1 + 1, self.range.1 as u64 + 1),
+                    ..WaveDataItem::default()
+                },
+            );
+        }
+        // sampled value at pointer
+        if let Some(pointer) = response.hover_pos() {
+            let timestamp = info.range.0 as f64
+                + (pointer.x - signal_rect.left()) as f64 / signal_rect.width() as f64
+                    * (self.range.1 - self.range.0);
+            let item = changes
+                .iter()
+                .map(|change| &change.item)
+                .take_while(|item| item.timestamp as f64 <= timestamp)
+                .last();
+            if let Some(item) = item {
+                let text = match options.value(&item.value, width) {
+                    Some(value) => value.to_string(),
+                    None => item.value.as_radix_width(self.get_radix(signal), width),
+                };
+                response.clone().on_hover_text_at_pointer(text);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::view::analog::{analog_label, analog_value_to_y, AnalogOptions, AnalogValueType};
+    use crate::wave::lod::{LodBucket, PaintChange};
+    use crate::wave::WireValue::*;
+    use crate::wave::{WaveDataItem, WaveDataValue};
+
+    #[test]
+    fn test_analog_options() {
+        let mut options = AnalogOptions::default();
+        let value = WaveDataValue::Comp(vec![0xfe]);
+        assert_eq!(options.value(&value, 8), Some(254.0));
+        assert_eq!(options.value(&WaveDataValue::Raw(vec![V1, X]), 2), None);
+        assert_eq!(options.value(&WaveDataValue::Real(1.5), 64), Some(1.5));
+        options.value_type = AnalogValueType::Signed;
+        assert_eq!(options.value(&value, 8), Some(-2.0));
+        assert_eq!(options.full_range(8), (-128.0, 127.0));
+        options.value_type = AnalogValueType::Real;
+        // half precision -2.125
+        let value = WaveDataValue::Comp(vec![0x40, 0xc0]);
+        assert_eq!(options.value(&value, 16), Some(-2.125));
+        assert_eq!(options.value(&value, 12), None);
+
+        let values = [3.0, -1.0, 2.0];
+        assert_eq!(options.scale(values.into_iter()), Some((-1.0, 3.0)));
+        assert_eq!(options.scale(std::iter::empty()), None);
+        options.range = Some((0.0, 10.0));
+        assert_eq!(options.scale(values.into_iter()), Some((0.0, 10.0)));
+        options.grid_lines = 3;
+        assert_eq!(options.grid_values((0.0, 10.0)), vec![0.0, 5.0, 10.0]);
+        assert_eq!(analog_value_to_y(5.0, (0.0, 10.0), 0.0, 100.0), 50.0);
+        assert_eq!(analog_value_to_y(20.0, (0.0, 10.0), 0.0, 100.0), 0.0);
+        assert_eq!(analog_value_to_y(1.0, (1.0, 1.0), 0.0, 100.0), 50.0);
+        assert_eq!(analog_label(2.5), "2.5");
+        assert_eq!(analog_label(-0.0001), "0");
+        assert_eq!(analog_label(100.0), "100");
+
+        let mut change = PaintChange {
+            item: WaveDataItem {
+                value: WaveDataValue::Comp(vec![1]),
+                timestamp: 0,
+            },
+            busy: Some(LodBucket {
+                min: 1.0,
+                max: 200.0,
+                has_x: false,
+            }),
+        };
+        assert_eq!(options.busy_range(&change), None);
+        options.value_type = AnalogValueType::Unsigned;
+        assert_eq!(options.busy_range(&change), Some((1.0, 200.0)));
+        change.busy = None;
+        assert_eq!(options.busy_range(&change), None);
+    }
+}
@@ -40,6 +40,15 @@ impl WaveCursor {
 }
 
 impl WaveView {
+    /// Move main marker to `pos`, center it in view if out of range
+    pub fn marker_jump(&mut self, pos: u64) {
+        self.marker.set_pos_valid(pos);
+        let fpos = pos as f64;
+        if fpos < self.range.0 || fpos > self.range.1 {
+            let half = (self.range.1 - self.range.0) / 2.0;
+            self.range = (fpos - half, fpos + half);
+        }
+    }
     /// Find nearest cursor according to panel x position
     /// Will ignore `self.marker_temp` and distance larger than `CURSOR_NEAREST`
     pub fn find_cursor(&self, x: f32) -> Option<i32> {
@@ -0,0 +1,210 @@
+use crate::view::cursor::WaveCursor;
+use crate::view::WaveView;
+use crate::wave::{Wave, WaveTimescaleUnit};
+use egui::{CollapsingHeader, DragValue, Grid, ScrollArea, TextEdit, Ui};
+
+impl WaveView {
+    /// Main marker and cursors that are enabled, sorted by position
+    pub fn cursors_valid(&self) -> Vec<&WaveCursor> {
+        let mut cursors = self
+            .cursors
+            .iter()
+            .chain([&self.marker])
+            .filter(|c| c.valid)
+            .collect::<Vec<_>>();
+        cursors.sort_by_key(|c| c.pos);
+        cursors
+    }
+    /// Stringify signed time from position `a` to `b`
+    pub fn pos_delta_to_time(
+        &self,
+        timescale: &(u64, WaveTimescaleUnit),
+        a: u64,
+        b: u64,
+    ) -> String {
+        match b >= a {
+            true => self.pos_to_time(timescale, b - a),
+            false => format!("-{}", self.pos_to_time(timescale, a - b)),
+        }
+    }
+    /// Center view at `pos` and keep its width
+    pub fn view_jump(&mut self, pos: u64) {
+        let half = (self.range.1 - self.range.0) / 2.0;
+        self.range_set((pos as f64 - half, pos as f64 + half));
+    }
+    /// Paint cursor list with names and times to edit
+    fn ui_cursor_list(&mut self, ui: &mut Ui, timescale: &(u64, WaveTimescaleUnit)) {
+        // take cursors out to format times with `self` while editing them
+        let mut marker = self.marker.clone();
+        let mut cursors = std::mem::take(&mut self.cursors);
+        let speed = f64::max((self.range.1 - self.range.0) / 100.0, 1.0);
+        let mut jump = None;
+        let mut remove = None;
+        Grid::new(format!("cursor_list_{}", self.id))
+            .num_columns(5)
+            .striped(true)
+            .show(ui, |ui| {
+                for cursor in std::iter::once(&mut marker).chain(cursors.iter_mut()) {
+                    ui.checkbox(&mut cursor.valid, "")
+                        .on_hover_text(t!("view.cursors.enable"));
+                    match cursor.id {
+                        -1 => ui.label(&cursor.name),
+                        _ => ui.add(TextEdit::singleline(&mut cursor.name).desired_width(80.0)),
+                    };
+                    ui.add(
+                        DragValue::new(&mut cursor.pos)
+                            .speed(speed)
+                            .custom_formatter(|v, _| self.pos_to_time(timescale, v as u64))
+                            .custom_parser(|text| {
+                                self.time_to_pos(timescale, text).map(|p| p as f64)
+                            }),
+                    );
+                    if ui
+                        .button("⌖")
+                        .on_hover_text(t!("view.cursors.jump"))
+                        .clicked()
+                    {
+                        jump = Some(cursor.pos);
+                    }
+                    if cursor.id >= 0
+                        && ui
+                            .button("✖")
+                            .on_hover_text(t!("view.time.cursor_remove"))
+                            .clicked()
+                    {
+                        remove = Some(cursor.id);
+                    }
+                    ui.end_row();
+                }
+            });
+        self.marker = marker;
+        self.cursors = cursors;
+        if let Some(pos) = jump {
+            self.view_jump(pos);
+        }
+        if let Some(id) = remove {
+            self.cursors.retain(|c| c.id != id);
+            self.spans.retain(|s| s.0 != id && s.1 != id);
+        }
+    }
+    /// Paint cursors panel: cursor list, time between every two cursors
+    /// and values of displayed signals at each cursor
+    pub(crate) fn ui_cursor_panel(&mut self, ui: &mut Ui, wave: &Wave) {
+        let timescale = wave.info.timescale;
+        ScrollArea::both().show(ui, |ui| {
+            CollapsingHeader::new(t!("view.cursors.list"))
+                .default_open(true)
+                .show(ui, |ui| {
+                    self.ui_cursor_list(ui, &timescale);
+                    if ui.button(t!("view.time.cursor_add")).clicked() {
+                        let pos = match self.marker.valid {
+                            true => self.marker.pos,
+                            false => ((self.range.0 + self.range.1) / 2.0).max(0.0) as u64,
+                        };
+                        self.cursors
+                            .push(WaveCursor::new(self.next_cursor_id(), pos));
+                    }
+                });
+            let cursors = self
+                .cursors_valid()
+                .into_iter()
+                .map(|c| (c.name.to_string(), c.pos))
+                .collect::<Vec<_>>();
+            CollapsingHeader::new(t!("view.cursors.deltas"))
+                .default_open(true)
+                .show(ui, |ui| {
+                    if cursors.len() < 2 {
+                        ui.label(t!("view.cursors.deltas_empty"));
+                        return;
+                    }
+                    Grid::new(format!("cursor_deltas_{}", self.id))
+                        .num_columns(cursors.len() + 1)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label("");
+                            for (name, _) in &cursors {
+                                ui.strong(name);
+                            }
+                            ui.end_row();
+                            for (name, a) in &cursors {
+                                ui.strong(name);
+                                for (_, b) in &cursors {
+                                    ui.monospace(match a == b {
+                                        true => String::new(),
+                                        false => self.pos_delta_to_time(&timescale, *a, *b),
+                                    });
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            CollapsingHeader::new(t!("view.cursors.values"))
+                .default_open(true)
+                .show(ui, |ui| {
+                    if cursors.is_empty() || self.signals.is_empty() {
+                        ui.label(t!("view.cursors.values_empty"));
+                        return;
+                    }
+                    Grid::new(format!("cursor_values_{}", self.id))
+                        .num_columns(cursors.len() + 1)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label("");
+                            for (name, _) in &cursors {
+                                ui.strong(name);
+                            }
+                            ui.end_row();
+                            for signal in &self.signals {
+                                ui.label(signal.s.to_string());
+                                for (_, pos) in &cursors {
+                                    let text = self
+                                        .find_value(wave, signal.s.id, *pos)
+                                        .map(|item| {
+                                            item.value.as_radix_width(
+                                                self.get_radix(signal),
+                                                signal.s.width,
+                                            )
+                                        })
+                                        .unwrap_or_default();
+                                    ui.monospace(text);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::view::cursor::WaveCursor;
+    use crate::view::WaveView;
+    use crate::wave::WaveTimescaleUnit;
+
+    #[test]
+    fn test_cursor_times() {
+        let mut view = WaveView::default();
+        let timescale = (10, WaveTimescaleUnit::NS);
+        assert_eq!(view.time_to_pos(&timescale, "100"), Some(10));
+        assert_eq!(view.time_to_pos(&timescale, " 1.5 us"), Some(150));
+        assert_eq!(view.time_to_pos(&timescale, "20000PS"), Some(2));
+        assert_eq!(view.time_to_pos(&timescale, "12 hours"), None);
+        assert_eq!(view.time_to_pos(&timescale, "-5"), None);
+        let text = view.pos_to_time(&timescale, 42);
+        assert_eq!(view.time_to_pos(&timescale, &text), Some(42));
+        assert_eq!(view.pos_delta_to_time(&timescale, 2, 5), "30ns");
+        assert_eq!(view.pos_delta_to_time(&timescale, 5, 2), "-30ns");
+
+        view.cursors.push(WaveCursor::new(0, 30));
+        view.cursors.push(WaveCursor::new(1, 10));
+        view.cursors[0].valid = false;
+        view.marker.set_pos_valid(20);
+        let positions = view
+            .cursors_valid()
+            .iter()
+            .map(|c| c.pos)
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![10, 20]);
+    }
+}
@@ -0,0 +1,500 @@
+use crate::view::signal::SignalView;
+use crate::view::WaveView;
+use crate::wave::derive::{concat_items, parse_concat};
+use crate::wave::expr::{expr_items, parse_expr};
+use crate::wave::store::{SignalData, WaveStore, WaveStoreBuilder};
+use crate::wave::{Wave, WaveDataItem, WaveInfo, WaveSignalInfo};
+use anyhow::{anyhow, Result};
+use egui::{Color32, Id, TextEdit, Ui, Window};
+use egui_toast::ToastKind;
+use tracing::warn;
+
+/// Ids of derived signals start from here, never conflict with id codes in wave files
+pub const DERIVED_ID_BASE: u64 = 1 << 62;
+
+/// How a derived signal is computed from signals in wave
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Debug, Clone)]
+pub enum DerivedKind {
+    /// Virtual bus concatenating signals and bit slices, like `{top.a, b[3:0]}`
+    Concat(String),
+    /// Verilog like expression, like `valid & ready` or `addr[31:12] == 20'hdead0`
+    Expr(String),
+}
+
+impl Default for DerivedKind {
+    fn default() -> Self {
+        Self::Concat(String::new())
+    }
+}
+
+impl DerivedKind {
+    pub fn text(&self) -> &str {
+        match self {
+            DerivedKind::Concat(text) | DerivedKind::Expr(text) => text.as_str(),
+        }
+    }
+}
+
+/// Signal computed from other signals, only its definition is saved
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Debug, Clone)]
+pub struct DerivedSignal {
+    pub id: u64,
+    pub name: String,
+    pub kind: DerivedKind,
+}
+
+impl DerivedSignal {
+    /// Definition text shown in editor
+    pub fn text(&self) -> &str {
+        self.kind.text()
+    }
+    /// Check definition against `info`, return info of this signal
+    pub fn signal_info(&self, info: &WaveInfo) -> Result<WaveSignalInfo> {
+        let width = match &self.kind {
+            DerivedKind::Concat(text) => parse_concat(text, info)?.iter().map(|p| p.width()).sum(),
+            DerivedKind::Expr(text) => parse_expr(text, info)?.width(info),
+        };
+        Ok(WaveSignalInfo {
+            id: self.id,
+            name: self.name.clone(),
+            width,
+            ..Default::default()
+        })
+    }
+    /// Ids of signals this signal is computed from
+    pub fn sources(&self, info: &WaveInfo) -> Vec<u64> {
+        match &self.kind {
+            DerivedKind::Concat(text) => parse_concat(text, info)
+                .map(|parts| parts.iter().map(|p| p.id).collect())
+                .unwrap_or_default(),
+            DerivedKind::Expr(text) => parse_expr(text, info)
+                .map(|expr| expr.sources())
+                .unwrap_or_default(),
+        }
+    }
+    /// Compute value changes at or after `from` from loaded data
+    pub fn evaluate(&self, wave: &Wave, from: u64) -> Result<Vec<WaveDataItem>> {
+        match &self.kind {
+            DerivedKind::Concat(text) => {
+                let parts = parse_concat(text, &wave.info)?;
+                Ok(concat_items(&wave.data, &wave.info, &parts, from))
+            }
+            DerivedKind::Expr(text) => {
+                let expr = parse_expr(text, &wave.info)?;
+                Ok(expr_items(&wave.data, &wave.info, &expr, from))
+            }
+        }
+    }
+}
+
+/// Name of single part like `cpu.pc[15:8]` is `pc[15:8]`, or a general name
+fn derived_default_name(text: &str) -> String {
+    let text = text.trim();
+    let inner = text
+        .strip_prefix('{')
+        .and_then(|t| t.strip_suffix('}'))
+        .unwrap_or(text)
+        .trim();
+    match inner.contains(',') || inner.is_empty() {
+        true => t!("view.derived.new_name").to_string(),
+        false => inner.rsplit('.').next().unwrap_or(inner).to_string(),
+    }
+}
+
+/// State of derived signal editor window
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct DerivedEditor {
+    /// Editing existing signal, or create new one
+    pub id: Option<u64>,
+    pub name: String,
+    pub kind: DerivedKind,
+    pub error: Option<String>,
+    /// New signal is inserted after this signal index
+    pub after: Option<usize>,
+}
+
+impl WaveView {
+    pub fn derived_get(&self, id: u64) -> Option<&DerivedSignal> {
+        self.derived.iter().find(|d| d.id == id)
+    }
+    fn derived_next_id(&self) -> u64 {
+        self.derived
+            .iter()
+            .map(|d| d.id + 1)
+            .max()
+            .unwrap_or(DERIVED_ID_BASE)
+    }
+    /// Add derived signal after signal `after`, or update existing one with the same id
+    pub fn derived_set(
+        &mut self,
+        derived: DerivedSignal,
+        info: &WaveInfo,
+        after: Option<usize>,
+    ) -> Result<()> {
+        let signal_info = derived.signal_info(info)?;
+        match self.derived.iter_mut().find(|d| d.id == derived.id) {
+            Some(d) => {
+                *d = derived;
+                self.signals
+                    .iter_mut()
+                    .filter(|s| s.s.id == signal_info.id)
+                    .for_each(|s| s.s = signal_info.clone());
+            }
+            None => {
+                self.derived.push(derived);
+                let mut signal = SignalView::from_id(signal_info.id, info);
+                signal.s = signal_info;
+                match after.filter(|i| *i < self.signals.len()) {
+                    Some(index) => {
+                        signal.group = self.signals[index].group;
+                        self.signals.insert(index + 1, signal);
+                    }
+                    None => self.signals.push(signal),
+                }
+            }
+        }
+        self.derived_dirty = true;
+        Ok(())
+    }
+    /// Show bits of vector signal at `index` as derived rows below it, most significant first
+    pub fn signal_expand(&mut self, index: usize, info: &WaveInfo) -> Result<()> {
+        let signal = self
+            .signals
+            .get(index)
+            .ok_or_else(|| anyhow!("no signal at {}", index))?
+            .clone();
+        if signal.expanded {
+            return Ok(());
+        }
+        let path = info
+            .code_paths
+            .get(&signal.s.id)
+            .ok_or_else(|| anyhow!("{} is not a signal in wave", signal.s.name))?
+            .join(".");
+        // inserted right after `index`, so the last inserted one is on top
+        for bit in 0..signal.s.width {
+            let derived = DerivedSignal {
+                id: self.derived_next_id(),
+                name: format!("{}[{}]", signal.s.name, bit),
+                kind: DerivedKind::Concat(format!("{{{}[{}]}}", path, bit)),
+            };
+            self.derived_set(derived, info, Some(index))?;
+            let row = &mut self.signals[index + 1];
+            row.parent = Some(signal.s.id);
+            row.color = signal.color;
+        }
+        self.signals[index].expanded = true;
+        Ok(())
+    }
+    /// Remove bit rows of vector signal at `index`
+    pub fn signal_collapse(&mut self, index: usize) {
+        let id = match self.signals.get_mut(index) {
+            Some(signal) => {
+                signal.expanded = false;
+                signal.s.id
+            }
+            None => return,
+        };
+        let rows = self
+            .signals
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.parent == Some(id))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        self.signals_remove(&rows);
+    }
+    /// Remove definitions no longer shown in view
+    pub fn derived_clean(&mut self) {
+        let signals = &self.signals;
+        self.derived
+            .retain(|d| signals.iter().any(|s| s.s.id == d.id));
+        self.derived_dirty = true;
+    }
+    /// Recompute derived signals when definitions or wave data changed,
+    /// signals computed from followed signals are only extended by appended changes
+    pub fn derived_update(&mut self, wave: &Wave) {
+        let appended = std::mem::take(&mut self.derived_appended);
+        let full = self.derived_dirty;
+        if !full && appended.is_empty() {
+            return;
+        }
+        if full {
+            self.derived_dirty = false;
+            self.derived_sources = self
+                .derived
+                .iter()
+                .flat_map(|d| d.sources(&wave.info))
+                .collect();
+            self.derived_data = WaveStore::default();
+        }
+        let until = self.derived_until;
+        self.derived_until = wave.info.range.1;
+        for derived in &self.derived {
+            let extend = !full && self.derived_data.contains_key(&derived.id);
+            if extend
+                && !derived
+                    .sources(&wave.info)
+                    .iter()
+                    .any(|id| appended.contains(id))
+            {
+                continue;
+            }
+            let from = if extend { until } else { 0 };
+            match derived.evaluate(wave, from) {
+                Ok(items) => {
+                    match extend {
+                        true => {
+                            // changes at `until` may be computed without all of them
+                            self.derived_data.truncate(derived.id, until);
+                            let mut builder = WaveStoreBuilder::default();
+                            items.iter().for_each(|item| builder.push(derived.id, item));
+                            self.derived_data.append(builder.finish());
+                        }
+                        false => self.derived_data.insert(derived.id, &items),
+                    }
+                    self.derived_errors.remove(&derived.id);
+                }
+                Err(e) => {
+                    let error = e.to_string();
+                    warn!("derived signal {}: {}", derived.name, error);
+                    // report each error once, not on every data update
+                    if self.derived_errors.get(&derived.id) != Some(&error) {
+                        self.toast(
+                            ToastKind::Error,
+                            t!(
+                                "msg.derived_failed",
+                                name = derived.name.as_str(),
+                                error = error.as_str()
+                            ),
+                        );
+                        self.derived_errors.insert(derived.id, error);
+                    }
+                }
+            }
+        }
+    }
+    /// Value changes of signal in wave or derived signal
+    pub fn signal_data<'a>(&'a self, wave: &'a Wave, id: u64) -> Option<SignalData<'a>> {
+        match id >= DERIVED_ID_BASE {
+            true => self.derived_data.get(&id),
+            false => wave.data.get(&id),
+        }
+    }
+    /// Find *nearest* value of signal in wave or derived signal
+    pub fn find_value(&self, wave: &Wave, id: u64, pos: u64) -> Option<WaveDataItem> {
+        self.signal_data(wave, id).and_then(|data| data.find(pos))
+    }
+    /// Paint derived signal editor window
+    pub(crate) fn ui_derived_editor(&mut self, ui: &mut Ui, info: &WaveInfo) {
+        let mut editor = match self.derived_editor.take() {
+            Some(editor) => editor,
+            None => return,
+        };
+        let mut open = true;
+        let mut submit = false;
+        Window::new(t!("view.derived.title"))
+            .id(Id::new(format!("derived_editor_{}", self.id)))
+            .resizable(false)
+            .collapsible(false)
+            .show(ui.ctx(), |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(t!("view.group.name"));
+                    ui.text_edit_singleline(&mut editor.name);
+                });
+                ui.horizontal(|ui| {
+                    let text = editor.kind.text().to_string();
+                    let concat = matches!(editor.kind, DerivedKind::Concat(_));
+                    if ui.radio(concat, t!("view.derived.concat")).clicked() {
+                        editor.kind = DerivedKind::Concat(text.clone());
+                    }
+                    if ui.radio(!concat, t!("view.derived.expr")).clicked() {
+                        editor.kind = DerivedKind::Expr(text);
+                    }
+                });
+                let (hint, text) = match &mut editor.kind {
+                    DerivedKind::Concat(text) => (t!("view.derived.concat_hint"), text),
+                    DerivedKind::Expr(text) => (t!("view.derived.expr_hint"), text),
+                };
+                ui.label(hint);
+                ui.add(TextEdit::multiline(text).code_editor().desired_rows(2));
+                if let Some(error) = &editor.error {
+                    ui.colored_label(Color32::RED, error);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button(t!("view.derived.ok")).clicked() {
+                        submit = true;
+                    }
+                    if ui.button(t!("view.derived.cancel")).clicked() {
+                        open = false;
+                    }
+                });
+            });
+        if submit {
+            let kind = match &editor.kind {
+                DerivedKind::Concat(text) => DerivedKind::Concat(text.trim().to_string()),
+                DerivedKind::Expr(text) => DerivedKind::Expr(text.trim().to_string()),
+            };
+            let derived = DerivedSignal {
+                id: editor.id.unwrap_or_else(|| self.derived_next_id()),
+                name: match (editor.name.trim(), &kind) {
+                    ("", DerivedKind::Concat(text)) => derived_default_name(text),
+                    ("", DerivedKind::Expr(text)) => text.clone(),
+                    (name, _) => name.to_string(),
+                },
+                kind,
+            };
+            match self.derived_set(derived, info, editor.after) {
+                Ok(_) => open = false,
+                Err(e) => editor.error = Some(e.to_string()),
+            }
+        }
+        if open {
+            self.derived_editor = Some(editor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::view::derived::{derived_default_name, DerivedKind, DerivedSignal, DERIVED_ID_BASE};
+    use crate::view::signal::SignalView;
+    use crate::view::WaveView;
+    use crate::wave::store::{WaveStore, WaveStoreBuilder};
+    use crate::wave::vcd_parser::Vcd;
+    use crate::wave::WaveLoader;
+    use std::fs::File;
+
+    #[test]
+    fn test_derived() -> anyhow::Result<()> {
+        let wave = Vcd::load(&mut File::open("data/cpu_ila_commit.vcd")?, |_, _| {}, None)?;
+        let mut ids = wave.info.code_paths.keys().copied().collect::<Vec<_>>();
+        ids.sort();
+        let (a, b) = (ids[0], ids[1]);
+        let path = |id: u64| wave.info.code_paths[&id].join(".");
+        let mut view = WaveView::default();
+        view.signals.push(SignalView::from_id(a, &wave.info));
+        view.signals.push(SignalView::from_id(b, &wave.info));
+        let mut bus = DerivedSignal {
+            id: DERIVED_ID_BASE,
+            name: "bus".to_string(),
+            kind: DerivedKind::Concat(format!("{{{}, {}[0]}}", path(a), path(b))),
+        };
+        view.derived_set(bus.clone(), &wave.info, Some(0))?;
+        assert_eq!(view.signals[1].s.id, DERIVED_ID_BASE);
+        assert_eq!(
+            view.signals[1].s.width,
+            wave.info.code_signal_info[&a].width + 1
+        );
+        view.derived_update(&wave);
+        let data = view.signal_data(&wave, DERIVED_ID_BASE).unwrap();
+        assert!(!data.is_empty());
+        // invalid definition keeps the old one
+        bus.kind = DerivedKind::Concat("{not_exist}".to_string());
+        assert!(view.derived_set(bus, &wave.info, None).is_err());
+        assert_eq!(view.derived.len(), 1);
+        view.signals_remove(&[1]);
+        assert!(view.derived.is_empty());
+        assert_eq!(derived_default_name("{top.cpu.pc[15:8]}"), "pc[15:8]");
+
+        let expr = DerivedSignal {
+            id: DERIVED_ID_BASE + 1,
+            name: "expr".to_string(),
+            kind: DerivedKind::Expr(format!("{} == {}[0]", path(a), path(b))),
+        };
+        view.derived_set(expr.clone(), &wave.info, None)?;
+        assert_eq!(view.signals.last().unwrap().s.width, 1);
+        assert_eq!(expr.sources(&wave.info), vec![a, b]);
+        view.derived_update(&wave);
+        assert!(view.signal_data(&wave, expr.id).is_some());
+        view.signals_remove(&[view.signals.len() - 1]);
+
+        let vector = *ids
+            .iter()
+            .find(|id| wave.info.code_signal_info[*id].width > 1)
+            .unwrap();
+        let width = wave.info.code_signal_info[&vector].width as usize;
+        view.signals.push(SignalView::from_id(vector, &wave.info));
+        let index = view.signals.len() - 1;
+        view.signal_expand(index, &wave.info)?;
+        assert_eq!(view.signals.len(), index + 1 + width);
+        let top = view.signals[index + 1].clone();
+        assert_eq!(top.parent, Some(vector));
+        assert_eq!(
+            top.s.name,
+            format!("{}[{}]", view.signals[index].s.name, width - 1)
+        );
+        view.derived_update(&wave);
+        assert!(view.signal_data(&wave, top.s.id).is_some());
+        view.signal_collapse(index);
+        assert_eq!(view.signals.len(), index + 1);
+        assert!(view.derived.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_derived_append() -> anyhow::Result<()> {
+        let full = Vcd::load(&mut File::open("data/cpu_ila_commit.vcd")?, |_, _| {}, None)?;
+        let mut wave = Vcd::load(&mut File::open("data/cpu_ila_commit.vcd")?, |_, _| {}, None)?;
+        let mut ids = full.info.code_paths.keys().copied().collect::<Vec<_>>();
+        ids.sort();
+        let (a, b) = (ids[0], ids[1]);
+        let path = |id: u64| full.info.code_paths[&id].join(".");
+        let data = full.data.get(&a).unwrap();
+        let split = data.iter().nth(data.len() / 2).unwrap().timestamp;
+        // changes at `split` are divided into loaded and appended ones
+        let mut loaded = WaveStore::default();
+        let mut appended = WaveStoreBuilder::default();
+        for id in full.data.keys() {
+            let items = full.data.get(id).unwrap().iter().collect::<Vec<_>>();
+            let count = items
+                .iter()
+                .filter(|i| i.timestamp < split || (i.timestamp == split && *id == a))
+                .count();
+            loaded.insert(*id, &items[..count]);
+            items[count..]
+                .iter()
+                .for_each(|item| appended.push(*id, item));
+        }
+        wave.data = loaded;
+        wave.info.range.1 = split;
+        let mut view = WaveView::default();
+        for (id, kind) in [
+            (
+                a,
+                DerivedKind::Concat(format!("{{{}, {}}}", path(b), path(a))),
+            ),
+            (
+                b,
+                DerivedKind::Expr(format!("{} == {}[0]", path(a), path(b))),
+            ),
+        ] {
+            let derived = DerivedSignal {
+                id: DERIVED_ID_BASE + id,
+                name: id.to_string(),
+                kind,
+            };
+            view.derived_set(derived, &wave.info, None)?;
+        }
+        view.derived_update(&wave);
+        let appended = appended.finish();
+        view.derived_appended.extend(appended.keys().copied());
+        wave.data.append(appended);
+        wave.info.range.1 = full.info.range.1;
+        view.derived_update(&wave);
+        assert!(view.derived_appended.is_empty());
+
+        let mut expected = view.clone();
+        expected.derived_dirty = true;
+        expected.derived_update(&full);
+        for derived in &view.derived {
+            let data = view.signal_data(&wave, derived.id).unwrap();
+            let expected = expected.signal_data(&full, derived.id).unwrap();
+            assert!(data
+                .iter()
+                .map(|i| (i.timestamp, i.value.to_string()))
+                .eq(expected.iter().map(|i| (i.timestamp, i.value.to_string()))));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,590 @@
+use crate::view::signal::SignalView;
+use crate::view::{WaveView, LINE_WIDTH};
+use crate::wave::{Wave, WaveInfo};
+use egui::{Align, Label, Layout, Rect, Response, RichText, Sense, Ui};
+use std::collections::{HashMap, HashSet};
+
+/// Indent of nested groups in signal name column
+pub const GROUP_INDENT: f32 = 12.0;
+
+/// Named group of signals in wave panel, groups can be nested
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Debug, Clone, Default)]
+pub struct SignalGroup {
+    pub id: u64,
+    pub name: String,
+    /// Collapsed group is painted as a single summary row
+    pub collapsed: bool,
+    /// Id of parent group, `None` for top level groups
+    pub parent: Option<u64>,
+}
+
+/// Row painted in wave panel
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum SignalRow {
+    /// Group header: (group id, depth)
+    Group(u64, usize),
+    /// Signal: (index in [WaveView::signals], depth)
+    Signal(usize, usize),
+}
+
+impl SignalRow {
+    pub fn depth(&self) -> usize {
+        match self {
+            SignalRow::Group(_, depth) | SignalRow::Signal(_, depth) => *depth,
+        }
+    }
+}
+
+/// Rows dragged in wave panel
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum DragItem {
+    /// Group header with its signals
+    Group(u64),
+    /// Selected signals
+    Signals,
+}
+
+/// Group operations requested from wave panel
+#[derive(PartialEq, Debug, Clone)]
+pub enum GroupAction {
+    /// Create group of signals: (signal indexes)
+    Create(Vec<usize>),
+    Toggle(u64),
+    Rename(u64, String),
+    /// Remove group, keep its signals
+    Ungroup(u64),
+    /// Remove group with its signals
+    Remove(u64),
+    /// Start dragging group header
+    DragStart(u64),
+}
+
+impl WaveView {
+    pub fn group_get(&self, id: u64) -> Option<&SignalGroup> {
+        self.groups.iter().find(|g| g.id == id)
+    }
+    fn group_next_id(&self) -> u64 {
+        self.groups.iter().map(|g| g.id + 1).max().unwrap_or(0)
+    }
+    /// Group ids from top level down to `group`
+    pub fn group_chain(&self, group: Option<u64>) -> Vec<u64> {
+        let mut chain = vec![];
+        let mut current = group;
+        while let Some(id) = current {
+            if chain.contains(&id) {
+                break;
+            }
+            chain.push(id);
+            current = self.group_get(id).and_then(|g| g.parent);
+        }
+        chain.reverse();
+        chain
+    }
+    /// Indexes of signals in group and its sub groups
+    pub fn group_signals(&self, id: u64) -> Vec<usize> {
+        self.signals
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| self.group_chain(s.group).contains(&id))
+            .map(|(i, _)| i)
+            .collect()
+    }
+    /// Rows to paint, signals and sub groups in collapsed groups are hidden
+    pub fn signal_rows(&self) -> Vec<SignalRow> {
+        let collapsed = |id: &u64| self.group_get(*id).map(|g| g.collapsed).unwrap_or(false);
+        let mut rows = vec![];
+        let mut last_chain: Vec<u64> = vec![];
+        for (index, signal) in self.signals.iter().enumerate() {
+            let chain = self.group_chain(signal.group);
+            let common = chain
+                .iter()
+                .zip(last_chain.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            for depth in common..chain.len() {
+                if chain[..depth].iter().any(collapsed) {
+                    break;
+                }
+                rows.push(SignalRow::Group(chain[depth], depth));
+            }
+            if !chain.iter().any(collapsed) {
+                let depth = chain.len() + signal.parent.is_some() as usize;
+                rows.push(SignalRow::Signal(index, depth));
+            }
+            last_chain = chain;
+        }
+        rows
+    }
+    /// Keep signals of each group contiguous, drop empty groups and unknown group ids
+    pub fn groups_normalize(&mut self) {
+        let ids = self.groups.iter().map(|g| g.id).collect::<HashSet<_>>();
+        for group in self.groups.iter_mut() {
+            group.parent = group.parent.filter(|p| ids.contains(p) && *p != group.id);
+        }
+        for signal in self.signals.iter_mut() {
+            signal.group = signal.group.filter(|g| ids.contains(g));
+        }
+        enum Item {
+            Signal(usize),
+            Group(u64),
+        }
+        // children of each group in order of first appearance
+        let mut children: HashMap<Option<u64>, Vec<Item>> = HashMap::new();
+        let mut used = HashSet::new();
+        for (index, signal) in self.signals.iter().enumerate() {
+            let mut parent = None;
+            for id in self.group_chain(signal.group) {
+                if used.insert(id) {
+                    children.entry(parent).or_default().push(Item::Group(id));
+                }
+                parent = Some(id);
+            }
+            children
+                .entry(signal.group)
+                .or_default()
+                .push(Item::Signal(index));
+        }
+        let mut order = vec![];
+        let mut iters = vec![children.remove(&None).unwrap_or_default().into_iter()];
+        while let Some(iter) = iters.last_mut() {
+            match iter.next() {
+                Some(Item::Signal(index)) => order.push(index),
+                Some(Item::Group(id)) => {
+                    iters.push(children.remove(&Some(id)).unwrap_or_default().into_iter());
+                }
+                None => {
+                    iters.pop();
+                }
+            }
+        }
+        self.signals = order.into_iter().map(|i| self.signals[i].clone()).collect();
+        self.groups.retain(|g| used.contains(&g.id));
+    }
+    /// Create group of signals, nested in the deepest group containing all of them
+    pub fn group_create(&mut self, name: &str, indexes: &[usize]) -> Option<u64> {
+        let indexes = self.signals_with_children(indexes);
+        let chains = indexes
+            .iter()
+            .filter_map(|i| self.signals.get(*i))
+            .map(|s| self.group_chain(s.group))
+            .collect::<Vec<_>>();
+        let first = chains.first()?;
+        let common = (0..first.len())
+            .take_while(|depth| chains.iter().all(|c| c.get(*depth) == first.get(*depth)))
+            .count();
+        let id = self.group_next_id();
+        self.groups.push(SignalGroup {
+            id,
+            name: name.to_string(),
+            collapsed: false,
+            parent: common.checked_sub(1).map(|depth| first[depth]),
+        });
+        for index in &indexes {
+            if let Some(signal) = self.signals.get_mut(*index) {
+                signal.group = Some(id);
+            }
+        }
+        self.groups_normalize();
+        Some(id)
+    }
+    /// Append signals in nested groups, `path` of each signal is scope names
+    /// relative to the parent of added scope
+    pub fn group_add_signals(&mut self, signals: Vec<(Vec<String>, u64)>, info: &WaveInfo) {
+        let mut created: HashMap<Vec<String>, u64> = HashMap::new();
+        for (path, id) in signals {
+            let mut parent = None;
+            for depth in 1..=path.len() {
+                let key = path[..depth].to_vec();
+                parent = Some(match created.get(&key) {
+                    Some(group) => *group,
+                    None => {
+                        let group = self.group_next_id();
+                        self.groups.push(SignalGroup {
+                            id: group,
+                            name: path[depth - 1].to_string(),
+                            collapsed: false,
+                            parent,
+                        });
+                        created.insert(key, group);
+                        group
+                    }
+                });
+            }
+            self.signals.push(SignalView {
+                group: parent,
+                ..SignalView::from_id(id, info)
+            });
+        }
+        self.groups_normalize();
+    }
+    /// Remove group, its signals and sub groups are moved to its parent unless `with_signals`
+    pub fn group_remove(&mut self, id: u64, with_signals: bool) {
+        let parent = self.group_get(id).and_then(|g| g.parent);
+        if with_signals {
+            let remove = self.group_signals(id).into_iter().collect::<HashSet<_>>();
+            self.signals = self
+                .signals
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !remove.contains(i))
+                .map(|(_, s)| s.clone())
+                .collect();
+        } else {
+            for signal in self.signals.iter_mut().filter(|s| s.group == Some(id)) {
+                signal.group = parent;
+            }
+            for group in self.groups.iter_mut().filter(|g| g.parent == Some(id)) {
+                group.parent = parent;
+            }
+        }
+        self.groups.retain(|g| g.id != id);
+        self.groups_normalize();
+    }
+    /// Group that items dropped before `target` row belong to, `None` target is the end
+    fn row_parent(&self, target: Option<SignalRow>) -> Option<u64> {
+        match target {
+            Some(SignalRow::Signal(index, _)) => self.signals.get(index).and_then(|s| s.group),
+            Some(SignalRow::Group(id, _)) => self.group_get(id).and_then(|g| g.parent),
+            None => None,
+        }
+    }
+    /// Index in signals to insert before `target` row
+    fn row_signal_index(&self, target: Option<SignalRow>) -> usize {
+        match target {
+            Some(SignalRow::Signal(index, _)) => index,
+            Some(SignalRow::Group(id, _)) => self
+                .group_signals(id)
+                .first()
+                .copied()
+                .unwrap_or(self.signals.len()),
+            None => self.signals.len(),
+        }
+    }
+    /// Move group with its signals before `target` row, `None` to move to the end
+    pub fn group_move(&mut self, id: u64, target: Option<SignalRow>) {
+        let parent = self.row_parent(target);
+        if matches!(target, Some(SignalRow::Group(t, _)) if t == id)
+            || self.group_chain(parent).contains(&id)
+        {
+            return;
+        }
+        let members = self.group_signals(id);
+        self.signals_move_before(&members, target);
+        if let Some(group) = self.groups.iter_mut().find(|g| g.id == id) {
+            group.parent = parent;
+        }
+        self.groups_normalize();
+    }
+    /// Move signals before `target` row into its group, `None` to move to the end
+    pub fn signals_move(&mut self, indexes: &[usize], target: Option<SignalRow>) {
+        let indexes = &self.signals_with_children(indexes);
+        let parent = self.row_parent(target);
+        let start = self.signals_move_before(indexes, target);
+        for signal in self.signals.iter_mut().skip(start).take(indexes.len()) {
+            signal.group = parent;
+        }
+        self.groups_normalize();
+    }
+    /// Move signals to the end of group, `None` to move to the end as top level signals
+    pub fn signals_move_to_group(&mut self, indexes: &[usize], group: Option<u64>) {
+        let indexes = &self.signals_with_children(indexes);
+        let target = group
+            .and_then(|id| {
+                self.group_signals(id)
+                    .into_iter()
+                    .filter(|i| !indexes.contains(i))
+                    .last()
+            })
+            .map(|last| last + 1)
+            .filter(|next| *next < self.signals.len())
+            .map(|next| SignalRow::Signal(next, 0));
+        let start = self.signals_move_before(indexes, target);
+        for signal in self.signals.iter_mut().skip(start).take(indexes.len()) {
+            signal.group = group;
+        }
+        self.groups_normalize();
+    }
+    /// Signals at `indexes` and bit rows of expanded vectors among them, in order of rows
+    fn signals_with_children(&self, indexes: &[usize]) -> Vec<usize> {
+        let ids = indexes
+            .iter()
+            .filter_map(|i| self.signals.get(*i))
+            .map(|s| s.s.id)
+            .collect::<Vec<_>>();
+        self.signals
+            .iter()
+            .enumerate()
+            .filter(|(i, s)| indexes.contains(i) || s.parent.is_some_and(|p| ids.contains(&p)))
+            .map(|(i, _)| i)
+            .collect()
+    }
+    /// Move signals at `members` before `target` row keeping their order, return new index of the first one
+    fn signals_move_before(&mut self, members: &[usize], target: Option<SignalRow>) -> usize {
+        let insert = self.row_signal_index(target);
+        let insert = insert - members.iter().filter(|i| **i < insert).count();
+        let (moving, mut rest): (Vec<_>, Vec<_>) = self
+            .signals
+            .drain(..)
+            .enumerate()
+            .partition(|(i, _)| members.contains(i));
+        let moving = moving.into_iter().map(|(_, s)| s);
+        let insert = insert.min(rest.len());
+        // bit rows stay right after their vector
+        let insert = insert
+            + rest[insert..]
+                .iter()
+                .take_while(|(_, s)| s.parent.is_some())
+                .count();
+        let tail = rest.split_off(insert);
+        self.signals = rest
+            .into_iter()
+            .map(|(_, s)| s)
+            .chain(moving)
+            .chain(tail.into_iter().map(|(_, s)| s))
+            .collect();
+        insert
+    }
+    /// Header text of group row
+    pub fn group_label_text(&self, id: u64) -> String {
+        let group = match self.group_get(id) {
+            Some(group) => group,
+            None => return String::new(),
+        };
+        format!(
+            "{} {} ({})",
+            if group.collapsed { "⏵" } else { "⏷" },
+            group.name,
+            self.group_signals(id).len()
+        )
+    }
+    /// Paint group header in signal name column
+    pub(crate) fn ui_group_label(&self, id: u64, depth: usize, ui: &mut Ui) -> Option<GroupAction> {
+        let group = self.group_get(id)?;
+        let mut action = None;
+        ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
+            ui.add_space(depth as f32 * GROUP_INDENT);
+            let response = ui.add(
+                Label::new(RichText::new(self.group_label_text(id)).strong())
+                    .wrap(false)
+                    .sense(Sense::click_and_drag()),
+            );
+            if response.clicked() {
+                action = Some(GroupAction::Toggle(id));
+            }
+            if response.drag_started() {
+                action = Some(GroupAction::DragStart(id));
+            }
+            response.context_menu(|ui| {
+                ui.horizontal(|ui| {
+                    ui.label(t!("view.group.name"));
+                    let mut name = group.name.clone();
+                    if ui.text_edit_singleline(&mut name).changed() {
+                        action = Some(GroupAction::Rename(id, name));
+                    }
+                });
+                let toggle = match group.collapsed {
+                    true => t!("view.group.expand"),
+                    false => t!("view.group.collapse"),
+                };
+                if ui.button(toggle).clicked() {
+                    action = Some(GroupAction::Toggle(id));
+                    ui.close_menu();
+                }
+                if ui.button(t!("view.group.ungroup")).clicked() {
+                    action = Some(GroupAction::Ungroup(id));
+                    ui.close_menu();
+                }
+                if ui.button(t!("view.group.remove")).clicked() {
+                    action = Some(GroupAction::Remove(id));
+                    ui.close_menu();
+                }
+            });
+        });
+        action
+    }
+    /// Paint group row in wave column, collapsed group shows value changes of all its signals
+    pub(crate) fn ui_group_wave(&self, id: u64, wave: &Wave, ui: &mut Ui) -> Response {
+        let (response, painter) =
+            ui.allocate_painter(ui.available_size_before_wrap(), Sense::click_and_drag());
+        if !self.group_get(id).map(|g| g.collapsed).unwrap_or(false) {
+            return response;
+        }
+        let rect = response.rect;
+        let color = ui.visuals().weak_text_color();
+        painter.hline(rect.x_range(), rect.center().y, (LINE_WIDTH / 2.0, color));
+        let (start, end) = (self.range.0.max(0.0) as u64, self.range.1.max(0.0) as u64);
+        for index in self.group_signals(id) {
+            let signal = &self.signals[index];
+            if let Some(data) = self.signal_data(wave, signal.s.id) {
+                let mut xs = data
+                    .iter_at(data.position(start))
+                    .take_while(|item| item.timestamp <= end)
+                    .map(|item| (rect.left() + self.pos_to_x(item.timestamp)).round() as i32)
+                    .collect::<Vec<_>>();
+                xs.dedup();
+                for x in xs {
+                    painter.vline(
+                        x as f32,
+                        rect.shrink(4.0).y_range(),
+                        (LINE_WIDTH, signal.color),
+                    );
+                }
+            }
+        }
+        response
+    }
+    /// Paint drop position of dragged rows, return (dragged rows, target row) when released
+    pub(crate) fn ui_rows_drag(
+        &mut self,
+        ui: &Ui,
+        rows: &[SignalRow],
+        row_rects: &[(usize, Rect)],
+    ) -> Option<(DragItem, Option<SignalRow>)> {
+        let item = self.dragging?;
+        let (pointer, released) = ui.input(|i| (i.pointer.interact_pos(), !i.pointer.any_down()));
+        if released {
+            self.dragging = None;
+        }
+        let pointer = pointer?;
+        let target = row_rects
+            .iter()
+            .find(|(_, rect)| rect.center().y > pointer.y);
+        let y = match target {
+            Some((_, rect)) => rect.top(),
+            None => row_rects.last()?.1.bottom(),
+        };
+        ui.painter().hline(
+            ui.max_rect().x_range(),
+            y,
+            (LINE_WIDTH, ui.visuals().selection.stroke.color),
+        );
+        match released {
+            true => Some((item, target.and_then(|(row, _)| rows.get(*row).copied()))),
+            false => None,
+        }
+    }
+    /// Move dragged rows before `target` row
+    pub fn rows_drop(&mut self, item: DragItem, target: Option<SignalRow>) {
+        match item {
+            DragItem::Group(id) => self.group_move(id, target),
+            DragItem::Signals => self.signals_move(&self.selected_signals(), target),
+        }
+    }
+    /// Apply actions collected when painting
+    pub fn group_handle_actions(&mut self, actions: Vec<GroupAction>) {
+        for action in actions {
+            match action {
+                GroupAction::Create(indexes) => {
+                    self.group_create(t!("view.group.new_name").as_ref(), &indexes);
+                }
+                GroupAction::Toggle(id) => {
+                    if let Some(group) = self.groups.iter_mut().find(|g| g.id == id) {
+                        group.collapsed = !group.collapsed;
+                    }
+                }
+                GroupAction::Rename(id, name) => {
+                    if let Some(group) = self.groups.iter_mut().find(|g| g.id == id) {
+                        group.name = name;
+                    }
+                }
+                GroupAction::Ungroup(id) => self.group_remove(id, false),
+                GroupAction::Remove(id) => self.group_remove(id, true),
+                GroupAction::DragStart(id) => self.dragging = Some(DragItem::Group(id)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::view::group::SignalRow;
+    use crate::view::signal::SignalView;
+    use crate::view::WaveView;
+    use crate::wave::WaveSignalInfo;
+
+    fn view(n: u64) -> WaveView {
+        let mut view = WaveView::default();
+        for id in 0..n {
+            view.signals.push(SignalView {
+                s: WaveSignalInfo {
+                    id,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+        }
+        view
+    }
+
+    fn ids(view: &WaveView) -> Vec<u64> {
+        view.signals.iter().map(|s| s.s.id).collect()
+    }
+
+    #[test]
+    fn test_signal_groups() {
+        let mut view = view(6);
+        let outer = view.group_create("outer", &[1, 3, 4]).unwrap();
+        // members are moved next to the first one
+        assert_eq!(ids(&view), vec![0, 1, 3, 4, 2, 5]);
+        let inner = view.group_create("inner", &[2, 3]).unwrap();
+        assert_eq!(view.group_get(inner).unwrap().parent, Some(outer));
+        assert_eq!(
+            view.signal_rows(),
+            vec![
+                SignalRow::Signal(0, 0),
+                SignalRow::Group(outer, 0),
+                SignalRow::Signal(1, 1),
+                SignalRow::Group(inner, 1),
+                SignalRow::Signal(2, 2),
+                SignalRow::Signal(3, 2),
+                SignalRow::Signal(4, 0),
+                SignalRow::Signal(5, 0),
+            ]
+        );
+        view.groups[1].collapsed = true;
+        assert_eq!(view.signal_rows().len(), 6);
+        view.groups[0].collapsed = true;
+        assert_eq!(
+            view.signal_rows(),
+            vec![
+                SignalRow::Signal(0, 0),
+                SignalRow::Group(outer, 0),
+                SignalRow::Signal(4, 0),
+                SignalRow::Signal(5, 0),
+            ]
+        );
+        // move outer group to the end
+        view.group_move(outer, None);
+        assert_eq!(ids(&view), vec![0, 2, 5, 1, 3, 4]);
+        // cannot move into itself
+        view.group_move(outer, Some(SignalRow::Signal(4, 2)));
+        assert_eq!(ids(&view), vec![0, 2, 5, 1, 3, 4]);
+        // move before first signal
+        view.group_move(inner, Some(SignalRow::Signal(0, 0)));
+        assert_eq!(ids(&view), vec![3, 4, 0, 2, 5, 1]);
+        assert_eq!(view.group_get(inner).unwrap().parent, None);
+        view.group_remove(inner, false);
+        assert_eq!(view.groups.len(), 1);
+        assert!(view.signals[0].group.is_none());
+        view.group_remove(outer, true);
+        assert_eq!(ids(&view), vec![3, 4, 0, 2, 5]);
+        assert!(view.groups.is_empty());
+    }
+
+    #[test]
+    fn test_move_bit_rows() {
+        let mut view = view(6);
+        // signals 2 and 3 are bit rows of vector 1
+        view.signals[2].parent = Some(1);
+        view.signals[3].parent = Some(1);
+        view.signals_move(&[1], Some(SignalRow::Signal(0, 0)));
+        assert_eq!(ids(&view), vec![1, 2, 3, 0, 4, 5]);
+        // dropping on bit rows places after them
+        view.signals_move(&[4], Some(SignalRow::Signal(2, 0)));
+        assert_eq!(ids(&view), vec![1, 2, 3, 4, 0, 5]);
+        let group = view.group_create("group", &[5]).unwrap();
+        view.signals_move_to_group(&[0], Some(group));
+        assert_eq!(ids(&view), vec![4, 0, 5, 1, 2, 3]);
+        assert!(view.signals[2..].iter().all(|s| s.group == Some(group)));
+    }
+}
@@ -0,0 +1,342 @@
+use crate::radix::Radix;
+use crate::view::cursor::WaveCursor;
+use crate::view::derived::{DerivedKind, DerivedSignal, DERIVED_ID_BASE};
+use crate::view::group::SignalGroup;
+use crate::view::layout::{signal_path_map, LayoutSignal, WaveLayout};
+use crate::view::signal::{AnalogDisplayType, SignalViewMode};
+use crate::view::value_table::ValueTable;
+use crate::wave::WaveInfo;
+use anyhow::Result;
+use egui::Color32;
+use std::collections::HashMap;
+use tracing::warn;
+
+/// Trace flags used in GTKWave save files, see `TR_*` in gtkwave `analyzer.h`
+mod flags {
+    pub const HEX: u64 = 0x2;
+    pub const DEC: u64 = 0x4;
+    pub const BIN: u64 = 0x8;
+    pub const OCT: u64 = 0x10;
+    pub const BLANK: u64 = 0x200;
+    pub const SIGNED: u64 = 0x400;
+    pub const ASCII: u64 = 0x800;
+    pub const ANALOG_STEP: u64 = 0x8000;
+    pub const ANALOG_INTERPOLATED: u64 = 0x10000;
+    pub const CLOSED: u64 = 0x400000;
+    pub const GRP_BEGIN: u64 = 0x800000;
+    pub const GRP_END: u64 = 0x1000000;
+}
+
+/// Window width when `[size]` is not given
+const GTKW_WAVE_WIDTH_DEFAULT: f64 = 1000.0;
+
+fn gtkw_mode(value: u64) -> SignalViewMode {
+    if value & flags::ANALOG_INTERPOLATED != 0 {
+        SignalViewMode::Analog(AnalogDisplayType::Interpolated)
+    } else if value & flags::ANALOG_STEP != 0 {
+        SignalViewMode::Analog(AnalogDisplayType::Step)
+    } else if value & flags::BIN != 0 {
+        SignalViewMode::Number(Radix::Bin)
+    } else if value & flags::OCT != 0 {
+        SignalViewMode::Number(Radix::Oct)
+    } else if value & flags::ASCII != 0 {
+        SignalViewMode::Number(Radix::Ascii)
+    } else if value & flags::SIGNED != 0 {
+        SignalViewMode::Number(Radix::SignedDec)
+    } else if value & flags::DEC != 0 {
+        SignalViewMode::Number(Radix::Dec)
+    } else if value & flags::HEX != 0 {
+        SignalViewMode::Number(Radix::Hex)
+    } else {
+        SignalViewMode::Default
+    }
+}
+
+/// Trace colors of `[color]`, 0 is the default color
+fn gtkw_color(index: usize) -> Option<Color32> {
+    match index {
+        1 => Some(Color32::RED),
+        2 => Some(Color32::from_rgb(255, 165, 0)),
+        3 => Some(Color32::YELLOW),
+        4 => Some(Color32::GREEN),
+        5 => Some(Color32::BLUE),
+        6 => Some(Color32::from_rgb(75, 0, 130)),
+        7 => Some(Color32::from_rgb(238, 130, 238)),
+        _ => None,
+    }
+}
+
+/// `top.data[7:0]` to `top.data` when the bit range covers declared width of signal,
+/// bit ranges are not part of vcd references.
+/// `None` for selects of some bits like `top.data[3]`, which are shown as derived slices.
+fn gtkw_signal_path(name: &str, info: &WaveInfo, paths: &HashMap<String, u64>) -> Option<String> {
+    if paths.contains_key(name) {
+        return Some(name.to_string());
+    }
+    let (path, range) = match name.strip_suffix(']').and_then(|n| n.rsplit_once('[')) {
+        Some((path, range)) if !path.is_empty() => (path, range),
+        _ => return Some(name.to_string()),
+    };
+    let width = paths
+        .get(path)
+        .and_then(|id| info.code_signal_info.get(id))
+        .map(|s| s.width.max(1));
+    let (msb, lsb) = range.split_once(':').unwrap_or((range, range));
+    match (width, msb.trim().parse::<u64>(), lsb.trim().parse::<u64>()) {
+        (Some(width), Ok(msb), Ok(0)) if msb + 1 == width => Some(path.to_string()),
+        _ => None,
+    }
+}
+
+impl WaveLayout {
+    /// Parse GTKWave `.gtkw` save file of wave with `info`. Signal names are kept as paths
+    /// and resolved when layout is applied, traces that cannot be shown (like concatenations)
+    /// are kept as they are so that they are reported as unresolved.
+    /// Gtkwave times are scaled by multiplier of wave timescale.
+    pub fn from_gtkw(text: &str, info: &WaveInfo) -> Self {
+        let timescale = info.timescale.0.max(1) as f64;
+        let paths = signal_path_map(info);
+        let mut layout = WaveLayout::default();
+        let mut trace_flags = 0u64;
+        let mut color = None;
+        let mut filter = None;
+        let mut time_start = 0.0;
+        let mut zoom = None;
+        let mut window_width = None;
+        let mut signals_width = 0.0;
+        let mut sst_width = 0.0;
+        let mut sst_expanded = false;
+        let mut group_stack: Vec<u64> = vec![];
+        for line in text.lines().map(|l| l.trim()) {
+            if line.is_empty() || line.starts_with("[*]") {
+                continue;
+            }
+            if let Some(directive) = line.strip_prefix('[') {
+                let (key, value) = directive.split_once(']').unwrap_or((directive, ""));
+                let mut values = value.split_whitespace();
+                let mut next_f64 = || values.next().and_then(|v| v.parse::<f64>().ok());
+                match key {
+                    "timestart" => time_start = next_f64().unwrap_or_default(),
+                    "size" => window_width = next_f64(),
+                    "signals_width" => signals_width = next_f64().unwrap_or_default(),
+                    "sst_width" => sst_width = next_f64().unwrap_or_default(),
+                    "sst_expanded" => sst_expanded = next_f64() == Some(1.0),
+                    "color" => color = next_f64().and_then(|c| gtkw_color(c as usize)),
+                    _ => {}
+                }
+                continue;
+            }
+            match line.as_bytes()[0] {
+                b'*' => {
+                    let mut values = line[1..].split_whitespace();
+                    zoom = values.next().and_then(|v| v.parse::<f64>().ok());
+                    let mut markers = values.filter_map(|v| v.parse::<i64>().ok());
+                    if let Some(marker) = markers.next().filter(|m| *m >= 0) {
+                        layout
+                            .marker
+                            .set_pos_valid((marker as f64 / timescale) as u64);
+                    }
+                    for (index, marker) in markers.take(26).enumerate() {
+                        if marker >= 0 {
+                            layout.cursors.push(WaveCursor {
+                                id: index as i32,
+                                pos: (marker as f64 / timescale) as u64,
+                                name: ((b'A' + index as u8) as char).to_string(),
+                                valid: true,
+                            });
+                        }
+                    }
+                }
+                b'@' => trace_flags = u64::from_str_radix(&line[1..], 16).unwrap_or_default(),
+                // translate filter file of next trace: `^1 /path/to/filter.txt`,
+                // process and transaction filters are not supported
+                b'^' => {
+                    filter = line[1..]
+                        .split_once(' ')
+                        .filter(|(index, _)| index.parse::<u32>().is_ok())
+                        .map(|(_, path)| path.trim().to_string());
+                }
+                // comments, blank traces and group markers
+                b'-' => {
+                    color = None;
+                    if trace_flags & flags::GRP_BEGIN != 0 {
+                        let id = layout.groups.len() as u64;
+                        layout.groups.push(SignalGroup {
+                            id,
+                            name: line[1..].to_string(),
+                            collapsed: trace_flags & flags::CLOSED != 0,
+                            parent: group_stack.last().copied(),
+                        });
+                        group_stack.push(id);
+                    } else if trace_flags & flags::GRP_END != 0 {
+                        group_stack.pop();
+                    }
+                }
+                _ => {
+                    if trace_flags & flags::BLANK != 0 {
+                        continue;
+                    }
+                    // `+{alias} top.signal`
+                    let name = match line.strip_prefix("+{") {
+                        Some(alias) => alias.split_once("} ").map(|x| x.1).unwrap_or(line),
+                        None => line,
+                    };
+                    let mut signal = LayoutSignal {
+                        path: name.to_string(),
+                        mode: gtkw_mode(trace_flags),
+                        group: group_stack.last().copied(),
+                        ..Default::default()
+                    };
+                    if !name.starts_with("#{") {
+                        match gtkw_signal_path(name, info, &paths) {
+                            Some(path) => signal.path = path,
+                            None => {
+                                // bit select, unresolved when the slice is out of signal
+                                let id = DERIVED_ID_BASE + layout.derived.len() as u64;
+                                layout.derived.push(DerivedSignal {
+                                    id,
+                                    name: name.to_string(),
+                                    kind: DerivedKind::Concat(format!("{{{name}}}")),
+                                });
+                                signal.derived = Some(id);
+                            }
+                        }
+                    }
+                    if let Some(color) = color.take() {
+                        signal.color = color;
+                    }
+                    if let Some(path) = filter.take() {
+                        signal.value_table = ValueTable::load(&path, Radix::Hex)
+                            .map_err(|e| warn!("load filter {}: {}", path, e))
+                            .ok();
+                    }
+                    layout.signals.push(signal);
+                }
+            }
+        }
+        if let Some(zoom) = zoom {
+            let width = window_width
+                .map(|w| w - signals_width - if sst_expanded { sst_width } else { 0.0 })
+                .filter(|w| *w > 0.0)
+                .unwrap_or(GTKW_WAVE_WIDTH_DEFAULT);
+            // time per pixel is 2^(-zoom)
+            let span = 2f64.powf(-zoom) * width;
+            layout.range = (time_start / timescale, (time_start + span) / timescale);
+        }
+        layout
+    }
+    /// Read GTKWave save file of wave with `info`
+    pub fn load_gtkw(path: &str, info: &WaveInfo) -> Result<Self> {
+        Ok(Self::from_gtkw(
+            std::fs::read_to_string(path)?.as_str(),
+            info,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::radix::Radix;
+    use crate::view::layout::WaveLayout;
+    use crate::view::signal::{AnalogDisplayType, SignalViewMode};
+    use crate::view::WaveView;
+    use crate::wave::vcd_parser::Vcd;
+    use crate::wave::WaveLoader;
+    use egui::Color32;
+    use std::fs::File;
+
+    const GTKW: &str = r#"[*]
+[*] GTKWave Analyzer v3.3.104 (w)1999-2020 BSI
+[*]
+[dumpfile] "data/cpu_ila_commit.vcd"
+[timestart] 100
+[size] 1200 600
+[pos] -1 -1
+*-3.000000 204 -1 300 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1
+[treeopen] dut.
+[sst_width] 250
+[signals_width] 150
+[sst_expanded] 1
+@28
+dut.commits_0_w_en
+@22
+[color] 1
+^1 data/not_exist_filter.txt
+dut.commits_0_data[31:0]
+@200
+-Comment
+@c00200
+-Commits
+@8022
+dut.commits_0_addr[4:0]
+#{dut.bus} dut.a dut.b
+@1401200
+-Commits
+@22
+dut.not_exist
+dut.commits_0_data[3]
+dut.commits_0_addr[7:0]
+[pattern_trace] 1
+[pattern_trace] 0
+"#;
+
+    #[test]
+    fn test_gtkw() -> anyhow::Result<()> {
+        let wave = Vcd::load(&mut File::open("data/cpu_ila_commit.vcd")?, |_, _| {}, None)?;
+        let layout = WaveLayout::from_gtkw(GTKW, &wave.info);
+        assert_eq!(layout.signals.len(), 7);
+        assert_eq!(layout.range, (100.0, 6500.0));
+        assert!(layout.marker.valid);
+        assert_eq!(layout.marker.pos, 204);
+        assert_eq!(layout.cursors.len(), 1);
+        assert_eq!(layout.cursors[0].name, "B");
+        assert_eq!(layout.cursors[0].pos, 300);
+        assert_eq!(layout.groups.len(), 1);
+        assert_eq!(layout.groups[0].name, "Commits");
+        assert!(layout.groups[0].collapsed);
+        assert_eq!(layout.signals[2].group, Some(0));
+        assert_eq!(layout.signals[4].group, None);
+        // missing filter file is skipped
+        assert_eq!(layout.signals[1].value_table, None);
+        // whole range is stripped, other bit selects are derived
+        assert_eq!(layout.signals[1].path, "dut.commits_0_data");
+        assert_eq!(layout.signals[1].derived, None);
+        assert_eq!(layout.derived.len(), 2);
+
+        let mut view = WaveView::default();
+        let unresolved = view.apply_layout(&layout, &wave.info);
+        assert_eq!(
+            unresolved,
+            vec![
+                "#{dut.bus} dut.a dut.b",
+                "dut.not_exist",
+                "dut.commits_0_addr[7:0]"
+            ]
+        );
+        assert_eq!(view.signals.len(), 4);
+        assert_eq!(view.signals[0].s.name, "commits_0_w_en");
+        assert_eq!(view.signals[0].mode, SignalViewMode::Number(Radix::Bin));
+        assert_eq!(view.signals[1].mode, SignalViewMode::Number(Radix::Hex));
+        assert_eq!(view.signals[1].color, Color32::RED);
+        assert_ne!(view.signals[2].color, Color32::RED);
+        assert_eq!(
+            view.signals[2].mode,
+            SignalViewMode::Analog(AnalogDisplayType::Step)
+        );
+        assert_eq!(view.marker.pos, 204);
+        assert_eq!(view.groups.len(), 1);
+        assert_eq!(view.signals[2].group, Some(0));
+        assert_eq!(view.signals[3].s.name, "dut.commits_0_data[3]");
+        assert_eq!(view.signals[3].s.width, 1);
+        assert!(view.derived_get(view.signals[3].s.id).is_some());
+        assert_eq!(
+            super::gtkw_mode(0x404),
+            SignalViewMode::Number(Radix::SignedDec)
+        );
+        assert_eq!(
+            super::gtkw_mode(0x800),
+            SignalViewMode::Number(Radix::Ascii)
+        );
+        Ok(())
+    }
+}
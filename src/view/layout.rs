@@ -0,0 +1,247 @@
+use crate::view::analog::AnalogOptions;
+use crate::view::cursor::WaveCursor;
+use crate::view::derived::DerivedSignal;
+use crate::view::group::SignalGroup;
+use crate::view::signal::{SignalView, SignalViewMode, SIGNAL_COLOR_BG_DEFAULT};
+use crate::view::value_table::ValueTable;
+use crate::view::{WaveView, SIGNAL_HEIGHT_DEFAULT};
+use crate::wave::WaveInfo;
+use anyhow::Result;
+use egui::Color32;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+/// Signal in layout file, referenced by hierarchical path like `top.cpu.pc`
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Debug, Clone)]
+#[serde(default)]
+pub struct LayoutSignal {
+    pub path: String,
+    pub height: f32,
+    pub mode: SignalViewMode,
+    pub color: Color32,
+    /// Innermost group id in [WaveLayout::groups]
+    pub group: Option<u64>,
+    /// Id in [WaveLayout::derived] for derived signal, `path` is its name then
+    pub derived: Option<u64>,
+    /// Bit rows are shown, they are not saved in layout
+    pub expanded: bool,
+    pub value_table: Option<ValueTable>,
+    pub analog: AnalogOptions,
+}
+
+impl Default for LayoutSignal {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            height: SIGNAL_HEIGHT_DEFAULT,
+            mode: Default::default(),
+            color: *SIGNAL_COLOR_BG_DEFAULT,
+            group: None,
+            derived: None,
+            expanded: false,
+            value_table: None,
+            analog: Default::default(),
+        }
+    }
+}
+
+/// View layout saved to standalone file, independent of vcd id codes
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Debug, Clone, Default)]
+#[serde(default)]
+pub struct WaveLayout {
+    pub signals: Vec<LayoutSignal>,
+    pub groups: Vec<SignalGroup>,
+    pub derived: Vec<DerivedSignal>,
+    pub cursors: Vec<WaveCursor>,
+    pub marker: WaveCursor,
+    pub spans: Vec<(i32, i32)>,
+    pub range: (f64, f64),
+    pub cursor_panel: bool,
+}
+
+impl WaveLayout {
+    /// Read layout from json file
+    pub fn load(path: &str) -> Result<Self> {
+        Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+    }
+    /// Write layout to json file
+    pub fn save(&self, path: &str) -> Result<()> {
+        serde_json::to_writer_pretty(BufWriter::new(File::create(path)?), self)?;
+        Ok(())
+    }
+}
+
+/// Map `top.cpu.pc` style paths to signal ids
+pub fn signal_path_map(info: &WaveInfo) -> HashMap<String, u64> {
+    info.code_paths
+        .iter()
+        .map(|(id, path)| (path.join("."), *id))
+        .collect()
+}
+
+impl WaveView {
+    /// Collect signals by path and cursors into layout
+    pub fn to_layout(&self, info: &WaveInfo) -> WaveLayout {
+        WaveLayout {
+            signals: self
+                .signals
+                .iter()
+                .filter(|signal| signal.parent.is_none())
+                .filter_map(|signal| {
+                    let (path, derived) = match self.derived_get(signal.s.id) {
+                        Some(derived) => (derived.name.clone(), Some(derived.id)),
+                        None => (info.code_paths.get(&signal.s.id)?.join("."), None),
+                    };
+                    Some(LayoutSignal {
+                        path,
+                        height: signal.height,
+                        mode: signal.mode.clone(),
+                        color: signal.color,
+                        group: signal.group,
+                        derived,
+                        expanded: signal.expanded,
+                        value_table: signal.value_table.clone(),
+                        analog: signal.analog.clone(),
+                    })
+                })
+                .collect(),
+            groups: self.groups.clone(),
+            derived: self
+                .derived
+                .iter()
+                .filter(|d| {
+                    self.signals
+                        .iter()
+                        .any(|s| s.s.id == d.id && s.parent.is_none())
+                })
+                .cloned()
+                .collect(),
+            cursors: self.cursors.clone(),
+            marker: self.marker.clone(),
+            spans: self.spans.clone(),
+            range: self.range,
+            cursor_panel: self.cursor_panel,
+        }
+    }
+    /// Replace signals and cursors with layout, return paths not found in `info`
+    pub fn apply_layout(&mut self, layout: &WaveLayout, info: &WaveInfo) -> Vec<String> {
+        let paths = signal_path_map(info);
+        let mut unresolved = vec![];
+        self.signals = layout
+            .signals
+            .iter()
+            .filter_map(|signal| {
+                let s = match signal.derived {
+                    Some(id) => layout
+                        .derived
+                        .iter()
+                        .find(|d| d.id == id)
+                        .and_then(|d| d.signal_info(info).ok()),
+                    None => paths
+                        .get(&signal.path)
+                        .map(|id| SignalView::from_id(*id, info).s),
+                };
+                match s {
+                    Some(s) => Some(SignalView {
+                        s,
+                        height: signal.height,
+                        mode: signal.mode.clone(),
+                        color: signal.color,
+                        group: signal.group,
+                        selected: false,
+                        expanded: signal.expanded,
+                        parent: None,
+                        value_table: signal.value_table.clone(),
+                        analog: signal.analog.clone(),
+                    }),
+                    None => {
+                        unresolved.push(signal.path.clone());
+                        None
+                    }
+                }
+            })
+            .collect();
+        self.groups = layout.groups.clone();
+        self.groups_normalize();
+        self.derived = layout.derived.clone();
+        self.derived_clean();
+        // expand from the end to keep indexes of signals before
+        for index in (0..self.signals.len()).rev() {
+            if self.signals[index].expanded {
+                self.signals[index].expanded = false;
+                self.signal_expand(index, info).ok();
+            }
+        }
+        self.cursors = layout.cursors.clone();
+        // keep id and name of main marker
+        match layout.marker.valid {
+            true => self.marker.set_pos_valid(layout.marker.pos),
+            false => self.marker.valid = false,
+        }
+        self.spans = layout.spans.clone();
+        self.cursor_panel = layout.cursor_panel;
+        if layout.range.0 < layout.range.1 {
+            self.range = layout.range;
+        }
+        unresolved
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::radix::Radix;
+    use crate::view::derived::{DerivedKind, DerivedSignal, DERIVED_ID_BASE};
+    use crate::view::layout::WaveLayout;
+    use crate::view::signal::{SignalView, SignalViewMode};
+    use crate::view::value_table::ValueTable;
+    use crate::view::WaveView;
+    use crate::wave::vcd_parser::Vcd;
+    use crate::wave::WaveLoader;
+    use std::fs::File;
+
+    #[test]
+    fn test_layout() -> anyhow::Result<()> {
+        let wave = Vcd::load(&mut File::open("data/cpu_ila_commit.vcd")?, |_, _| {}, None)?;
+        let mut view = WaveView::default();
+        let mut ids = wave.info.code_paths.keys().copied().collect::<Vec<_>>();
+        ids.sort();
+        for id in ids.iter().take(3) {
+            view.signals.push(SignalView::from_id(*id, &wave.info));
+        }
+        view.signals[1].mode = SignalViewMode::Number(Radix::Bin);
+        view.signals[2].height = 42.0;
+        view.signals[2].analog.range = Some((-1.0, 1.0));
+        view.signals[2].value_table = Some(ValueTable::parse("0 IDLE\n1 BUSY", Radix::Dec)?);
+        view.group_create("group", &[1, 2]);
+        let bus = DerivedSignal {
+            id: DERIVED_ID_BASE,
+            name: "bus".to_string(),
+            kind: DerivedKind::Concat(format!("{{{}}}", wave.info.code_paths[&ids[0]].join("."))),
+        };
+        view.derived_set(bus, &wave.info, Some(2))?;
+        let vector = (0..view.signals.len())
+            .find(|i| view.signals[*i].s.width > 1)
+            .unwrap();
+        view.signal_expand(vector, &wave.info)?;
+        view.range = (10.0, 200.0);
+        view.cursor_panel = true;
+        let mut layout = view.to_layout(&wave.info);
+        let json = serde_json::to_string(&layout)?;
+        assert!(json.contains("dut."));
+        layout
+            .signals
+            .push(serde_json::from_str(r#"{"path": "dut.not_exist"}"#)?);
+        let mut restored = WaveView::default();
+        let unresolved = restored.apply_layout(&layout, &wave.info);
+        assert_eq!(unresolved, vec!["dut.not_exist".to_string()]);
+        assert_eq!(restored.signals, view.signals);
+        assert_eq!(restored.groups, view.groups);
+        assert_eq!(restored.derived, view.derived);
+        assert_eq!(restored.range, view.range);
+        assert!(restored.cursor_panel);
+        let layout2: WaveLayout = serde_json::from_str(json.as_str())?;
+        assert_eq!(layout2.signals.len(), 4);
+        Ok(())
+    }
+}
@@ -1,16 +1,34 @@
+pub mod analog;
 pub mod cursor;
+pub mod cursor_panel;
+pub mod derived;
+pub mod group;
+pub mod gtkw;
+pub mod layout;
+pub mod search;
+pub mod select;
 pub mod signal;
 pub mod time_bar;
 pub mod ui;
+pub mod value_table;
+pub mod zoom;
 
 use crate::message::RvcdMsg;
 use crate::radix::Radix;
+use crate::rvcd::toast_new;
 use crate::verilog::VerilogSource;
 use crate::view::cursor::WaveCursor;
+use crate::view::derived::{DerivedEditor, DerivedSignal};
+use crate::view::group::{DragItem, SignalGroup};
+use crate::view::search::ValueSearch;
 use crate::view::signal::{SignalView, SignalViewAlign};
 use crate::view::ui::ResponsePointerState;
+use crate::view::zoom::ZoomHistory;
+use crate::wave::store::WaveStore;
 use crate::wave::{WaveInfo, WaveTimescaleUnit};
 use egui::*;
+use egui_toast::ToastKind;
+use std::collections::{HashMap, HashSet};
 use std::sync::mpsc;
 use tracing::*;
 
@@ -19,6 +37,8 @@ pub const TEXT_ROUND_OFFSET: f32 = 4.0;
 // pub const MIN_SIGNAL_WIDTH: f32 = 2.0;
 // pub const MIN_SIGNAL_WIDTH: f32 = 0.8;
 pub const MIN_SIGNAL_WIDTH: f32 = -100.0;
+/// Value changes closer than this width are painted as one busy block
+pub const BUSY_SIGNAL_WIDTH: f32 = 1.0;
 pub const BG_MULTIPLY: f32 = 0.05;
 pub const TEXT_BG_MULTIPLY: f32 = 0.4;
 pub const CURSOR_NEAREST: f32 = 20.0;
@@ -38,6 +58,33 @@ pub struct WaveView {
     pub id: usize,
     /// Signals added to viewer
     pub signals: Vec<SignalView>,
+    /// Signal groups, members of a group are contiguous in `signals`
+    pub groups: Vec<SignalGroup>,
+    /// Definitions of derived signals in `signals`
+    pub derived: Vec<DerivedSignal>,
+    /// Value changes of derived signals, computed from wave data
+    #[serde(skip)]
+    pub derived_data: WaveStore,
+    /// Ids of signals that derived signals are computed from
+    #[serde(skip)]
+    pub derived_sources: Vec<u64>,
+    /// Whether `derived_data` should be computed again
+    #[serde(skip)]
+    pub derived_dirty: bool,
+    /// Signals with value changes appended after `derived_data` is computed
+    #[serde(skip)]
+    pub derived_appended: HashSet<u64>,
+    /// End of wave data when `derived_data` is computed, appended changes are not before it
+    #[serde(skip)]
+    pub derived_until: u64,
+    #[serde(skip)]
+    pub derived_editor: Option<DerivedEditor>,
+    /// Last error of each derived signal, reported once
+    #[serde(skip)]
+    pub derived_errors: HashMap<u64, String>,
+    /// Find value bar, shown when not `None`
+    #[serde(skip)]
+    pub value_search: Option<ValueSearch>,
     /// Viewer range, smaller or bigger than data range
     pub range: (f64, f64),
     /// Text alignment, FIXME: center position error
@@ -57,9 +104,26 @@ pub struct WaveView {
     pub marker_temp: WaveCursor,
     /// Available spans
     pub spans: Vec<(i32, i32)>,
+    /// Whether to show cursors panel
+    pub cursor_panel: bool,
+    /// Position where dragging main marker started
+    #[serde(skip)]
+    pub drag_region_start: Option<u64>,
+    /// Last region dragged with main marker, can be zoomed to
+    #[serde(skip)]
+    pub marker_region: Option<(u64, u64)>,
+    /// Ranges visited before, for zoom back and forward
+    #[serde(skip)]
+    pub zoom_history: ZoomHistory,
     /// Temporally use to store id
     #[serde(skip)]
     pub dragging_cursor_id: Option<i32>,
+    /// Group header or selected signals being dragged
+    #[serde(skip)]
+    pub dragging: Option<DragItem>,
+    /// Signal index where shift-click selection starts
+    #[serde(skip)]
+    pub selection_anchor: Option<usize>,
     /// remember display width to calculate position
     #[serde(skip)]
     pub wave_width: f32,
@@ -90,6 +154,10 @@ pub struct WaveView {
     #[serde(skip)]
     pub sources: Vec<VerilogSource>,
     pub highlight_signals: Vec<u64>,
+    /// Follow value changes appended to loaded file
+    pub follow: bool,
+    /// Scroll to the end when following file
+    pub auto_scroll: bool,
 }
 
 impl Default for WaveView {
@@ -97,6 +165,16 @@ impl Default for WaveView {
         Self {
             id: 0,
             signals: vec![],
+            groups: vec![],
+            derived: vec![],
+            derived_data: Default::default(),
+            derived_sources: vec![],
+            derived_dirty: true,
+            derived_appended: Default::default(),
+            derived_until: 0,
+            derived_editor: None,
+            derived_errors: Default::default(),
+            value_search: None,
             range: (0.0, 0.0),
             align: Default::default(),
             background: true,
@@ -107,7 +185,13 @@ impl Default for WaveView {
             marker: WaveCursor::from_string(-1, t!("view.cursor_main").as_str()),
             marker_temp: WaveCursor::from_string(-2, ""),
             spans: vec![],
+            cursor_panel: false,
+            drag_region_start: None,
+            marker_region: None,
+            zoom_history: Default::default(),
             dragging_cursor_id: None,
+            dragging: None,
+            selection_anchor: None,
             wave_width: 100.0,
             signal_font_size: 12.0,
             right_click_time_bar_pos: None,
@@ -124,6 +208,8 @@ impl Default for WaveView {
             value_width_max: 0.0,
             sources: vec![],
             highlight_signals: vec![],
+            follow: false,
+            auto_scroll: true,
         }
     }
 }
@@ -146,16 +232,30 @@ impl WaveView {
     pub fn set_sources(&mut self, sources: Vec<VerilogSource>) {
         self.sources = sources;
     }
+    /// Show a toast message through parent, like [crate::rvcd::Rvcd::toast]
+    pub(crate) fn toast(&self, kind: ToastKind, text: impl Into<String>) {
+        match &self.tx {
+            Some(tx) => {
+                tx.send(RvcdMsg::Notification(toast_new(kind, text))).ok();
+            }
+            None => warn!("no tx in view!"),
+        }
+    }
     /// Remove signals that not defined in wave info, used in `reload()`
     pub fn signals_clean_unavailable(&mut self, info: &WaveInfo) {
         let signals: Vec<SignalView> = self
             .signals
             .clone()
             .into_iter()
-            .filter(|signal| info.code_signal_info.contains_key(&signal.s.id))
+            .filter(|signal| {
+                info.code_signal_info.contains_key(&signal.s.id)
+                    || self.derived_get(signal.s.id).is_some()
+            })
             .collect();
         debug!("signals: {} => {}", self.signals.len(), signals.len());
         self.signals = signals;
+        self.groups_normalize();
+        self.derived_clean();
     }
     /// Convert paint pos to wave position
     /// * `x`: x position to wave panel
@@ -192,6 +292,29 @@ impl WaveView {
         }
         format!("{v}{u}")
     }
+    /// Parse time like `100`, `12ns` or `1.5 us` to wave position,
+    /// number without unit is in unit of timescale
+    pub fn time_to_pos(&self, timescale: &(u64, WaveTimescaleUnit), text: &str) -> Option<u64> {
+        use WaveTimescaleUnit::*;
+        let units = [S, MS, US, NS, PS, FS].map(|u| u.to_string());
+        let text = text.trim();
+        let split = text
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(text.len());
+        let (number, unit) = text.split_at(split);
+        let number = number.parse::<f64>().ok()?;
+        let unit = match unit.trim().to_ascii_lowercase() {
+            unit if unit.is_empty() => timescale.1.to_string(),
+            unit => unit,
+        };
+        let exponent = units.iter().position(|u| *u == unit)? as i32;
+        let exponent_scale = units.iter().position(|u| *u == timescale.1.to_string())? as i32;
+        let pos = number * 1000f64.powi(exponent_scale - exponent) / timescale.0 as f64;
+        match pos.is_finite() {
+            true => Some(pos.round() as u64),
+            false => None,
+        }
+    }
     /// Get new id for cursor
     fn next_cursor_id(&self) -> i32 {
         self.cursors
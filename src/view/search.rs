@@ -0,0 +1,161 @@
+use crate::radix::Radix;
+use crate::view::WaveView;
+use crate::wave::search::{
+    search_next, search_next_edge, search_prev, search_prev_edge, EdgeKind, ValueQuery,
+};
+use crate::wave::{Wave, WaveDataValue, WaveSignalType};
+use anyhow::{anyhow, Result};
+use egui::{Color32, ComboBox, Key, TextEdit, Ui};
+
+/// State of find value bar
+#[derive(PartialEq, Debug, Clone)]
+pub struct ValueSearch {
+    pub text: String,
+    /// Radix of numbers without prefix
+    pub radix: Radix,
+    pub error: Option<String>,
+}
+
+impl ValueSearch {
+    pub fn new(radix: Radix) -> Self {
+        Self {
+            text: String::new(),
+            radix,
+            error: None,
+        }
+    }
+}
+
+impl WaveView {
+    /// Show or hide find value bar
+    pub fn value_search_toggle(&mut self) {
+        self.value_search = match self.value_search {
+            Some(_) => None,
+            None => Some(ValueSearch::new(self.default_radix.clone())),
+        };
+    }
+    /// Position searching starts from, the main marker or edge of view
+    fn search_origin(&self, forward: bool) -> u64 {
+        match (self.marker.valid, forward) {
+            (true, _) => self.marker.pos,
+            (false, true) => self.range.0.max(0.0) as u64,
+            (false, false) => self.range.1.max(0.0) as u64,
+        }
+    }
+    /// Move main marker to nearest next or previous edge of selected signals
+    pub fn edge_jump(&mut self, wave: &Wave, edge: EdgeKind, forward: bool) -> Option<u64> {
+        let pos = self.search_origin(forward);
+        let found = self
+            .selected_signals()
+            .into_iter()
+            .map(|i| &self.signals[i].s)
+            .filter_map(|s| {
+                let data = self.signal_data(wave, s.id)?;
+                match forward {
+                    true => search_next_edge(&data, pos, edge, s.width),
+                    false => search_prev_edge(&data, pos, edge, s.width),
+                }
+            });
+        let found = match forward {
+            true => found.min(),
+            false => found.max(),
+        };
+        if let Some(pos) = found {
+            self.marker_jump(pos);
+        }
+        found
+    }
+    /// Move main marker to next or previous change of selected signal matching search text
+    pub fn value_search_jump(&mut self, wave: &Wave, forward: bool) -> Result<()> {
+        let search = self
+            .value_search
+            .as_ref()
+            .ok_or_else(|| anyhow!("value search is closed"))?;
+        let signal = self
+            .selected_signals()
+            .first()
+            .map(|i| self.signals[*i].s.clone())
+            .ok_or_else(|| anyhow!(t!("view.search.no_signal").to_string()))?;
+        let bits = !matches!(
+            signal.typ,
+            WaveSignalType::Real
+                | WaveSignalType::RealTime
+                | WaveSignalType::RealParameter
+                | WaveSignalType::ShortReal
+                | WaveSignalType::String
+        );
+        let query = ValueQuery::parse(&search.text, search.radix.clone(), bits, signal.width)?;
+        let data = self
+            .signal_data(wave, signal.id)
+            .ok_or_else(|| anyhow!(t!("view.search.no_data").to_string()))?;
+        let pos = self.search_origin(forward);
+        let matches = |v: &WaveDataValue| query.matches(v, signal.width);
+        let found = match forward {
+            true => search_next(&data, pos, matches),
+            false => search_prev(&data, pos, matches),
+        };
+        match found {
+            Some(pos) => {
+                self.marker_jump(pos);
+                Ok(())
+            }
+            None => Err(anyhow!(t!("view.search.not_found").to_string())),
+        }
+    }
+    /// Paint find value bar below toolbar
+    pub(crate) fn ui_value_search(&mut self, ui: &mut Ui, wave: &Wave) {
+        let mut search = match self.value_search.take() {
+            Some(search) => search,
+            None => return,
+        };
+        let mut open = true;
+        let mut jump = None;
+        ui.horizontal(|ui| {
+            ui.label(t!("view.search.label"));
+            let response = ui.add(
+                TextEdit::singleline(&mut search.text)
+                    .hint_text(t!("view.search.hint"))
+                    .desired_width(160.0),
+            );
+            if response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                jump = Some(!ui.input(|i| i.modifiers.shift));
+                response.request_focus();
+            }
+            // fixed-point radix has parameters, the current one is listed too
+            let fixed = matches!(search.radix, Radix::Fixed { .. }).then(|| search.radix.clone());
+            ComboBox::from_id_source(format!("value_search_radix_{}", self.id))
+                .selected_text(search.radix.to_string())
+                .width(80.0)
+                .show_ui(ui, |ui| {
+                    for radix in Radix::PRESETS.into_iter().chain(fixed) {
+                        let text = radix.to_string();
+                        ui.selectable_value(&mut search.radix, radix, text);
+                    }
+                });
+            if ui.button(t!("view.search.prev")).clicked() {
+                jump = Some(false);
+            }
+            if ui.button(t!("view.search.next")).clicked() {
+                jump = Some(true);
+            }
+            if ui.button("✖").clicked() {
+                open = false;
+            }
+            if let Some(error) = &search.error {
+                ui.colored_label(Color32::RED, error);
+            }
+        });
+        if open {
+            self.value_search = Some(search);
+        }
+        if let Some(forward) = jump {
+            let error = self
+                .value_search_jump(wave, forward)
+                .err()
+                .map(|e| e.to_string());
+            if let Some(search) = &mut self.value_search {
+                search.error = error;
+            }
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use crate::view::analog::AnalogOptions;
+use crate::view::derived::DerivedEditor;
+use crate::view::group::{DragItem, GroupAction};
+use crate::view::signal::{SignalView, SignalViewMode};
+use crate::view::value_table::ValueTable;
+use crate::view::WaveView;
+use crate::wave::WaveInfo;
+use egui::{Color32, Modifiers};
+use std::collections::HashSet;
+use tracing::warn;
+
+/// How clicking a signal changes selection
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum SelectMode {
+    /// Select clicked signal only
+    Single,
+    /// Toggle clicked signal, `Ctrl+Click`
+    Toggle,
+    /// Select signals from last clicked one, `Shift+Click`
+    Range,
+}
+
+impl SelectMode {
+    pub fn from_modifiers(modifiers: &Modifiers) -> Self {
+        if modifiers.shift {
+            SelectMode::Range
+        } else if modifiers.command {
+            SelectMode::Toggle
+        } else {
+            SelectMode::Single
+        }
+    }
+}
+
+/// Signal operations requested from wave panel, applied to signal indexes
+#[derive(PartialEq, Debug, Clone)]
+pub enum SignalAction {
+    Select(usize, SelectMode),
+    /// Start dragging selected signals, clicked signal is selected first
+    DragStart(usize),
+    Remove(Vec<usize>),
+    SetMode(Vec<usize>, SignalViewMode),
+    SetColor(Vec<usize>, Color32),
+    SetHeight(Vec<usize>, f32),
+    /// Move signals into group, `None` for top level
+    MoveToGroup(Vec<usize>, Option<u64>),
+    Group(GroupAction),
+    /// Open editor to create or edit derived signal
+    EditDerived(DerivedEditor),
+    /// Show or hide bit rows of vector signal
+    Expand(usize, bool),
+    /// Attach value names to signals, `None` to remove them
+    SetValueTable(Vec<usize>, Option<ValueTable>),
+    SetAnalog(Vec<usize>, AnalogOptions),
+}
+
+impl WaveView {
+    /// Indexes of selected signals
+    pub fn selected_signals(&self) -> Vec<usize> {
+        self.signals
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.selected)
+            .map(|(i, _)| i)
+            .collect()
+    }
+    /// Signals that actions on signal `index` apply to,
+    /// the whole selection if it contains `index`, or `index` only
+    pub fn action_targets(&self, index: usize) -> Vec<usize> {
+        let selected = self.selected_signals();
+        match selected.contains(&index) {
+            true => selected,
+            false => vec![index],
+        }
+    }
+    pub fn signal_select(&mut self, index: usize, mode: SelectMode) {
+        match mode {
+            SelectMode::Single => {
+                for (i, signal) in self.signals.iter_mut().enumerate() {
+                    signal.selected = i == index;
+                }
+                self.selection_anchor = Some(index);
+            }
+            SelectMode::Toggle => {
+                if let Some(signal) = self.signals.get_mut(index) {
+                    signal.selected = !signal.selected;
+                }
+                self.selection_anchor = Some(index);
+            }
+            SelectMode::Range => {
+                let anchor = self.selection_anchor.unwrap_or(index);
+                let range = anchor.min(index)..=anchor.max(index);
+                for (i, signal) in self.signals.iter_mut().enumerate() {
+                    signal.selected = range.contains(&i);
+                }
+            }
+        }
+    }
+    pub fn selection_clear(&mut self) {
+        self.signals.iter_mut().for_each(|s| s.selected = false);
+        self.selection_anchor = None;
+    }
+    /// Remove signals at `indexes` with their bit rows, empty groups are removed too
+    pub fn signals_remove(&mut self, indexes: &[usize]) {
+        let indexes = indexes.iter().collect::<HashSet<_>>();
+        let expanded = indexes
+            .iter()
+            .filter_map(|i| self.signals.get(**i))
+            .filter(|s| s.expanded)
+            .map(|s| s.s.id)
+            .collect::<HashSet<_>>();
+        self.signals = self
+            .signals
+            .iter()
+            .enumerate()
+            .filter(|(i, s)| {
+                !indexes.contains(i) && !s.parent.map(|p| expanded.contains(&p)).unwrap_or(false)
+            })
+            .map(|(_, s)| s.clone())
+            .collect();
+        self.selection_anchor = None;
+        self.groups_normalize();
+        self.derived_clean();
+    }
+    fn signals_update(&mut self, indexes: &[usize], f: impl Fn(&mut SignalView)) {
+        for index in indexes {
+            if let Some(signal) = self.signals.get_mut(*index) {
+                f(signal);
+            }
+        }
+    }
+    /// Apply actions collected when painting
+    pub fn signal_handle_actions(&mut self, actions: Vec<SignalAction>, info: &WaveInfo) {
+        for action in actions {
+            match action {
+                SignalAction::Select(index, mode) => self.signal_select(index, mode),
+                SignalAction::DragStart(index) => {
+                    if !self.signals.get(index).map(|s| s.selected).unwrap_or(false) {
+                        self.signal_select(index, SelectMode::Single);
+                    }
+                    self.dragging = Some(DragItem::Signals);
+                }
+                SignalAction::Remove(indexes) => self.signals_remove(&indexes),
+                SignalAction::SetMode(indexes, mode) => {
+                    self.signals_update(&indexes, |s| s.mode = mode.clone())
+                }
+                SignalAction::SetColor(indexes, color) => {
+                    self.signals_update(&indexes, |s| s.color = color)
+                }
+                SignalAction::SetHeight(indexes, height) => {
+                    self.signals_update(&indexes, |s| s.height = height)
+                }
+                SignalAction::MoveToGroup(indexes, group) => {
+                    self.signals_move_to_group(&indexes, group)
+                }
+                SignalAction::Group(action) => self.group_handle_actions(vec![action]),
+                SignalAction::EditDerived(editor) => self.derived_editor = Some(editor),
+                SignalAction::Expand(index, true) => {
+                    if let Err(e) = self.signal_expand(index, info) {
+                        warn!("expand signal: {}", e);
+                    }
+                }
+                SignalAction::Expand(index, false) => self.signal_collapse(index),
+                SignalAction::SetValueTable(indexes, table) => {
+                    self.signals_update(&indexes, |s| s.value_table = table.clone())
+                }
+                SignalAction::SetAnalog(indexes, options) => {
+                    self.signals_update(&indexes, |s| s.analog = options.clone())
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::view::group::SignalRow;
+    use crate::view::select::{SelectMode, SignalAction};
+    use crate::view::signal::SignalView;
+    use crate::view::WaveView;
+    use crate::wave::WaveSignalInfo;
+
+    #[test]
+    fn test_select() {
+        let mut view = WaveView::default();
+        for id in 0..6 {
+            view.signals.push(SignalView {
+                s: WaveSignalInfo {
+                    id,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+        }
+        let ids = |view: &WaveView| view.signals.iter().map(|s| s.s.id).collect::<Vec<_>>();
+        view.signal_select(1, SelectMode::Single);
+        view.signal_select(3, SelectMode::Range);
+        assert_eq!(view.selected_signals(), vec![1, 2, 3]);
+        view.signal_select(2, SelectMode::Toggle);
+        assert_eq!(view.selected_signals(), vec![1, 3]);
+        assert_eq!(view.action_targets(3), vec![1, 3]);
+        assert_eq!(view.action_targets(4), vec![4]);
+        // move selection before signal 0
+        view.signals_move(&view.selected_signals(), Some(SignalRow::Signal(0, 0)));
+        assert_eq!(ids(&view), vec![1, 3, 0, 2, 4, 5]);
+        let group = view.group_create("group", &[4, 5]).unwrap();
+        view.signal_handle_actions(
+            vec![
+                SignalAction::SetHeight(vec![0, 1], 42.0),
+                SignalAction::MoveToGroup(vec![0, 1], Some(group)),
+            ],
+            &Default::default(),
+        );
+        assert_eq!(ids(&view), vec![0, 2, 4, 5, 1, 3]);
+        assert_eq!(view.signals[4].height, 42.0);
+        // drop selection at the end moves it out of group
+        view.signals_move(&view.selected_signals(), None);
+        assert_eq!(ids(&view), vec![0, 2, 4, 5, 1, 3]);
+        assert_eq!(view.signals[5].group, None);
+        view.signals_remove(&view.selected_signals());
+        assert_eq!(ids(&view), vec![0, 2, 4, 5]);
+        assert_eq!(view.groups.len(), 1);
+    }
+}
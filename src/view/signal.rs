@@ -2,16 +2,24 @@ use crate::message::RvcdMsg;
 use crate::radix::Radix;
 use crate::utils::{execute, get_text_size};
 use crate::verilog::VerilogGotoSource;
+use crate::view::analog::{ui_analog_options, AnalogOptions};
+use crate::view::derived::{DerivedEditor, DerivedKind};
+use crate::view::group::{GroupAction, GROUP_INDENT};
+use crate::view::select::{SelectMode, SignalAction};
+use crate::view::value_table::ValueTable;
 use crate::view::{
-    WaveView, BG_MULTIPLY, LINE_WIDTH, MIN_SIGNAL_WIDTH, SIGNAL_HEIGHT_DEFAULT, TEXT_ROUND_OFFSET,
+    WaveView, BG_MULTIPLY, BUSY_SIGNAL_WIDTH, LINE_WIDTH, MIN_SIGNAL_WIDTH, SIGNAL_HEIGHT_DEFAULT,
+    TEXT_BG_MULTIPLY, TEXT_ROUND_OFFSET,
 };
+use crate::wave::lod::{paint_changes, LodBucket};
+use crate::wave::store::SignalData;
 use crate::wave::{WaveDataItem, WaveDataValue, WaveInfo, WaveSignalInfo, WireValue};
 use egui::{
-    color_picker, pos2, vec2, Align, Align2, Color32, DragValue, FontId, Label, Layout, Rect,
-    Response, Sense, Ui, Widget,
+    color_picker, pos2, vec2, Align, Align2, Color32, DragValue, FontId, Id, Label, Layout,
+    Rect, Response, Sense, Ui, Widget,
 };
 use num_bigint::BigUint;
-use num_traits::{One, ToPrimitive, Zero};
+use num_traits::{One, Zero};
 use once_cell::sync::Lazy;
 use std::fmt::{Display, Formatter};
 use std::ops::RangeInclusive;
@@ -47,6 +55,41 @@ impl Display for SignalViewMode {
     }
 }
 
+/// Paint radix buttons in a menu, return the clicked one
+pub fn ui_radix_menu(ui: &mut Ui) -> Option<Radix> {
+    let mut selected = None;
+    for radix in Radix::PRESETS {
+        if ui.button(radix.to_string()).clicked() {
+            selected = Some(radix);
+        }
+    }
+    ui.menu_button(t!("view.mode.fixed"), |ui| {
+        let id = Id::new("radix_menu_fixed");
+        let (mut int, mut frac, mut signed) =
+            ui.data_mut(|d| *d.get_temp_mut_or(id, (None::<u32>, 8u32, true)));
+        ui.horizontal(|ui| {
+            let mut limited = int.is_some();
+            ui.checkbox(&mut limited, t!("view.mode.fixed_int"));
+            let mut bits = int.unwrap_or(8);
+            ui.add_enabled(limited, DragValue::new(&mut bits).clamp_range(0..=64));
+            int = limited.then_some(bits);
+        });
+        ui.horizontal(|ui| {
+            ui.label(t!("view.mode.fixed_frac"));
+            ui.add(DragValue::new(&mut frac).clamp_range(0..=64));
+        });
+        ui.checkbox(&mut signed, t!("view.mode.fixed_signed"));
+        ui.data_mut(|d| d.insert_temp(id, (int, frac, signed)));
+        if ui.button(t!("view.mode.fixed_apply")).clicked() {
+            selected = Some(Radix::Fixed { int, frac, signed });
+        }
+    });
+    if selected.is_some() {
+        ui.close_menu();
+    }
+    selected
+}
+
 #[derive(serde::Deserialize, serde::Serialize, PartialEq, Default, Debug, Clone)]
 pub enum SignalViewAlign {
     #[default]
@@ -55,7 +98,7 @@ pub enum SignalViewAlign {
     Right,
 }
 
-static SIGNAL_COLOR_BG_DEFAULT: Lazy<Color32> =
+pub(crate) static SIGNAL_COLOR_BG_DEFAULT: Lazy<Color32> =
     // Lazy::new(|| Color32::GREEN.linear_multiply(BG_MULTIPLY));
     Lazy::new(|| Color32::GREEN);
 
@@ -65,6 +108,24 @@ pub struct SignalView {
     pub height: f32,
     pub mode: SignalViewMode,
     pub color: Color32,
+    /// Innermost group containing this signal
+    #[serde(default)]
+    pub group: Option<u64>,
+    /// Selected in wave panel
+    #[serde(skip)]
+    pub selected: bool,
+    /// Bits of this vector signal are shown as rows below it
+    #[serde(default)]
+    pub expanded: bool,
+    /// Id of vector signal this bit row is expanded from
+    #[serde(default)]
+    pub parent: Option<u64>,
+    /// Names shown instead of values
+    #[serde(default)]
+    pub value_table: Option<ValueTable>,
+    /// Options used in analog mode
+    #[serde(default)]
+    pub analog: AnalogOptions,
 }
 impl SignalView {
     pub fn from_id(id: u64, info: &WaveInfo) -> Self {
@@ -75,6 +136,12 @@ impl SignalView {
             height: SIGNAL_HEIGHT_DEFAULT,
             mode: Default::default(),
             color: SIGNAL_COLOR_BG_DEFAULT.clone(),
+            group: None,
+            selected: false,
+            expanded: false,
+            parent: None,
+            value_table: None,
+            analog: Default::default(),
         }
     }
 }
@@ -84,28 +151,14 @@ impl WaveView {
     pub(crate) fn ui_signal_wave(
         &self,
         signal: &SignalView,
-        wave_data: &[WaveDataItem],
+        wave_data: &SignalData,
         info: &WaveInfo,
         ui: &mut Ui,
     ) -> Response {
         let (response, painter) =
             ui.allocate_painter(ui.available_size_before_wrap(), Sense::click_and_drag());
-        // let items = wave_data.iter().filter(|i| i.id == signal.s.id);
-        let items = wave_data.iter();
         // let start_pos = self.range.0.ceil() as u64;
         let start_pos = self.range.0 as u64;
-        let start_index = wave_data.binary_search_by_key(&start_pos, |x| x.timestamp);
-        let start_items = match start_index {
-            Ok(index) | Err(index) => {
-                // FIXME: skip less items failed
-                // if index >= 1 {
-                //     items.skip(index - 1)
-                // } else {
-                //     items.skip(0)
-                // }
-                items.skip(index.min(wave_data.len() - 1))
-            }
-        };
         let text_color = ui.visuals().strong_text_color();
         let signal_rect_raw = response.rect;
         // strange but works...
@@ -134,17 +187,15 @@ impl WaveView {
         //     response.rect.y_range(),
         //     (LINE_WIDTH, Color32::RED),
         // );
-        let mut item_last: Option<&WaveDataItem> = None;
         let mut ignore_x_start = -1.0;
         let mut ignore_has_x = false;
-        let mut paint_signal = |item_now: &WaveDataItem, item_next: &WaveDataItem| {
+        let mut paint_signal = |item_now: &WaveDataItem,
+                                item_next: &WaveDataItem,
+                                busy: Option<LodBucket>| {
             let single: bool = match &item_now.value {
-                WaveDataValue::Comp(_) => {
-                    let d = Default::default();
-                    let s = info.code_signal_info.get(&signal.s.id).unwrap_or(&d);
-                    s.width == 1
-                }
+                WaveDataValue::Comp(_) => signal.s.width == 1,
                 WaveDataValue::Raw(v) => v.len() == 1,
+                WaveDataValue::Real(_) | WaveDataValue::Str(_) => false,
             };
             let width = signal_rect.width();
             let height = signal_rect.height();
@@ -172,7 +223,33 @@ impl WaveView {
                 }
                 _ => {}
             };
-            let text = item_now.value.as_radix(self.get_radix(signal));
+            if let Some(busy) = busy {
+                // too many changes to paint one by one
+                painter.rect_filled(
+                    Rect::from_x_y_ranges(
+                        RangeInclusive::new(
+                            rect.left(),
+                            rect.right().max(rect.left() + BUSY_SIGNAL_WIDTH),
+                        ),
+                        rect.y_range(),
+                    ),
+                    0.0,
+                    if busy.has_x {
+                        Color32::DARK_RED
+                    } else {
+                        signal.color
+                    },
+                );
+                return rect;
+            }
+            let text = item_now
+                .value
+                .as_radix_width(self.get_radix(signal), signal.s.width);
+            let is_bits = item_now.value.is_bits();
+            let named = signal
+                .value_table
+                .as_ref()
+                .and_then(|table| table.get(&item_now.value, signal.s.width));
             if rect.width() > MIN_SIGNAL_WIDTH {
                 if ignore_x_start >= 0.0 {
                     // paint a rect as ignored data
@@ -213,6 +290,7 @@ impl WaveView {
                             false => WireValue::V0,
                         },
                         WaveDataValue::Raw(v) => v[0],
+                        _ => WireValue::X,
                     };
                     match value {
                         WireValue::V0 => {
@@ -244,13 +322,13 @@ impl WaveView {
                     };
                 } else {
                     let number: Option<BigUint> = (&item_now.value).into();
-                    if text.contains('x') {
+                    if named.is_none() && is_bits && text.contains('x') {
                         paint_x();
-                    } else if text.contains('z') {
+                    } else if named.is_none() && is_bits && text.contains('z') {
                         paint_z();
                     } else {
                         match number {
-                            Some(n) if n.is_zero() => {
+                            Some(n) if n.is_zero() && named.is_none() => {
                                 painter.hline(
                                     rect.x_range(),
                                     rect.bottom(),
@@ -261,16 +339,22 @@ impl WaveView {
                                 painter.rect(
                                     rect,
                                     0.0,
-                                    if self.background {
-                                        signal.color.linear_multiply(BG_MULTIPLY)
-                                    } else {
-                                        Color32::TRANSPARENT
+                                    match named.and_then(|n| n.color) {
+                                        Some(color) => color.linear_multiply(TEXT_BG_MULTIPLY),
+                                        None if self.background => {
+                                            signal.color.linear_multiply(BG_MULTIPLY)
+                                        }
+                                        None => Color32::TRANSPARENT,
                                     },
                                     (LINE_WIDTH, signal.color.clone()),
                                 );
                             }
                         }
                     }
+                    let text = match named {
+                        Some(named) => named.name.clone(),
+                        None => text,
+                    };
                     let value_font = FontId::monospace(self.signal_font_size);
                     let text_min_size = get_text_size(ui, "+", value_font.clone());
                     if self.show_text && rect.width() >= text_min_size.x + TEXT_ROUND_OFFSET {
@@ -288,16 +372,20 @@ impl WaveView {
                         let paint_text = if rect.width() >= text_size.x + TEXT_ROUND_OFFSET {
                             text
                         } else {
-                            let text_mono_width = text_size.x / text.len() as f32;
-                            let text_len = text.len();
-                            let remains = &text[0..(text_len
-                                - ((text_size.x + TEXT_ROUND_OFFSET - rect.width())
-                                    / text_mono_width) as usize)];
-                            if remains.len() <= 1 {
+                            // string values may contain non-ascii chars, so count chars here
+                            let text_len = text.chars().count();
+                            let text_mono_width = text_size.x / text_len as f32;
+                            let remains_len = text_len.saturating_sub(
+                                ((text_size.x + TEXT_ROUND_OFFSET - rect.width())
+                                    / text_mono_width) as usize,
+                            );
+                            if remains_len <= 1 {
                                 "+".to_string()
                             } else {
-                                let len = remains.len();
-                                format!("{}+", &remains[0..(len - 2)])
+                                format!(
+                                    "{}+",
+                                    text.chars().take(remains_len - 2).collect::<String>()
+                                )
                             }
                         };
                         // let text_font = FontId::monospace(self.signal_font_size);
@@ -334,69 +422,32 @@ impl WaveView {
                 if ignore_x_start < 0.0 {
                     ignore_x_start = rect.left();
                 }
-                if text.contains('x') || text.contains('z') {
+                if is_bits && (text.contains('x') || text.contains('z')) {
                     ignore_has_x = true;
                 }
             }
             rect
         };
-        // TODO: Reduce horizontal value painting
-        // let mut done_early = false;
-        let mut paint_items = vec![];
-        let is_analog = match signal.mode {
-            SignalViewMode::Analog(_) => true,
-            _ => false,
-        };
-        for item in start_items {
-            // let mut done = false;
-            if is_analog {
-                paint_items.push(item);
-            }
-            if let Some(item_last) = item_last {
-                let value_rect = paint_signal(item_last, item);
-                if value_rect == Rect::NOTHING || value_rect.left() > response.rect.right() {
-                    break;
-                }
-                // if !ui.is_rect_visible(_value_rect) {
-                //     painter.rect_filled(_value_rect, 0.0, Color32::RED);
-                //     done = true;
-                //     done_early = true;
-                // }
+        // dense changes are merged by summaries of signal, to paint by view width
+        let min_span = (self.range.1 - self.range.0) / signal_rect.width() as f64
+            * BUSY_SIGNAL_WIDTH as f64;
+        let changes = paint_changes(wave_data, start_pos, self.range.1 as u64, min_span);
+        for pair in changes.windows(2) {
+            let value_rect = paint_signal(&pair[0].item, &pair[1].item, pair[0].busy);
+            if value_rect == Rect::NOTHING || value_rect.left() > response.rect.right() {
+                break;
             }
-            item_last = Some(item);
-            // if done {
-            //     break;
-            // }
         }
-        // if done_early {
-        //     let mut paint_it = || {
-        //         if let Some(item) = it.next() {
-        //             let r = if let Some(item_last) = item_last {
-        //                 let _ = paint_signal(item_last, item);
-        //                 true
-        //             } else {
-        //                 false
-        //             };
-        //             item_last = Some(item);
-        //             r
-        //         } else {
-        //             false
-        //         }
-        //     };
-        //     paint_it();
-        //     paint_it();
-        //     paint_it();
-        // } else {
-        if let Some(item_last) = item_last {
+        if let Some(change_last) = changes.last() {
             let _ = paint_signal(
-                item_last,
+                &change_last.item,
                 &WaveDataItem {
                     timestamp: u64::min(info.range.1 + 1, self.range.1 as u64 + 1),
                     ..WaveDataItem::default()
                 },
+                change_last.busy,
             );
         }
-        // }
         // draw last
         if ignore_x_start >= 0.0 {
             let right_pos = (self.x_to_pos(signal_rect.right()) + 1)
@@ -414,174 +465,162 @@ impl WaveView {
                 },
             );
         }
-        if is_analog {
-            // draw analog
-            let mut item_last_analog: Option<&WaveDataItem> = None;
-            let mut min_value = None;
-            let mut max_value = None;
-            let mut analog_no_value = true;
-            for item in &paint_items {
-                match &item.value {
-                    WaveDataValue::Comp(v) => {
-                        analog_no_value = false;
-                        let value = BigUint::from_bytes_le(&v);
-                        let handle = |min_max_value: Option<Vec<u8>>, is_less: bool| {
-                            if min_max_value.is_none() {
-                                Some(v.clone())
-                            } else {
-                                let min_max_v =
-                                    BigUint::from_bytes_le(&min_max_value.as_ref().unwrap());
-                                if if is_less {
-                                    value < min_max_v
-                                } else {
-                                    value > min_max_v
-                                } {
-                                    Some(v.clone())
-                                } else {
-                                    min_max_value
-                                }
-                            }
-                        };
-                        min_value = handle(min_value, true);
-                        max_value = handle(max_value, false);
-                    }
-                    WaveDataValue::Raw(_) => {}
-                }
-            }
-            if analog_no_value {
-                painter.rect(
-                    Rect::from_x_y_ranges(
-                        RangeInclusive::new(
-                            self.fpos_to_x(self.range.0 as f32),
-                            self.fpos_to_x(self.range.1 as f32),
-                        ),
-                        signal_rect_raw.y_range(),
-                    ),
-                    0.0,
-                    Color32::RED.linear_multiply(BG_MULTIPLY),
-                    (LINE_WIDTH, Color32::RED),
-                );
-            } else {
-                let paint_analog = |item_now: &WaveDataItem, item_next: &WaveDataItem| {
-                    let width = signal_rect.width();
-                    let height = signal_rect.height();
-                    let percent_rect_left = ((item_now.timestamp - info.range.0) as f64
-                        / (self.range.1 - self.range.0))
-                        as f32;
-                    let percent_rect_right = ((item_next.timestamp - info.range.0) as f64
-                        / (self.range.1 - self.range.0))
-                        as f32;
-                    let rect = Rect::from_min_max(
-                        pos2(
-                            signal_rect.left() + width * percent_rect_left,
-                            signal_rect.top(),
-                        ),
-                        pos2(
-                            signal_rect.left() + width * percent_rect_right,
-                            signal_rect.top() + height,
-                        ),
-                    );
-                    if !(min_value.is_some() && max_value.is_some()) {
-                        painter.rect(
-                            rect,
-                            0.0,
-                            Color32::RED.linear_multiply(BG_MULTIPLY),
-                            (LINE_WIDTH, Color32::RED),
-                        );
-                    } else {
-                        let min_value = BigUint::from_bytes_le(min_value.as_ref().unwrap());
-                        let max_value = BigUint::from_bytes_le(max_value.as_ref().unwrap());
-                        let value_now = match &item_now.value {
-                            WaveDataValue::Comp(v) => BigUint::from_bytes_le(v),
-                            WaveDataValue::Raw(_) => BigUint::zero(),
-                        };
-                        let value_next = match &item_next.value {
-                            WaveDataValue::Comp(v) => BigUint::from_bytes_le(v),
-                            WaveDataValue::Raw(_) => BigUint::zero(),
-                        };
-                        let rate = (rect.bottom() - rect.top())
-                            / (max_value.clone() - min_value.clone()).to_f32().unwrap();
-                        let y_now =
-                            rate * (max_value.clone() - value_now).to_f32().unwrap() + rect.top();
-                        let y_next =
-                            rate * (max_value.clone() - value_next).to_f32().unwrap() + rect.top();
-                        let step = match &signal.mode {
-                            SignalViewMode::Analog(i) => match i {
-                                AnalogDisplayType::Interpolated => false,
-                                AnalogDisplayType::Step => true,
-                            },
-                            _ => false,
-                        };
-                        let y_right = if step { y_now } else { y_next };
-                        painter.line_segment(
-                            [pos2(rect.left(), y_now), pos2(rect.right(), y_right)],
-                            (LINE_WIDTH, signal.color.clone()),
-                        );
-                        if step {
-                            painter.vline(
-                                rect.right(),
-                                RangeInclusive::new(y_now, y_next),
-                                (LINE_WIDTH, signal.color.clone()),
-                            );
-                        }
-                    }
-                };
-                for item in paint_items {
-                    if let Some(item_now) = item_last_analog {
-                        let item_next = item;
-                        paint_analog(item_now, item_next);
-                    }
-                    item_last_analog = Some(item)
-                }
-                // last analog value
-                if let Some(item_last_analog) = item_last_analog {
-                    paint_analog(
-                        item_last_analog,
-                        &WaveDataItem {
-                            timestamp: u64::min(info.range.1 + 1, self.range.1 as u64 + 1),
-                            ..WaveDataItem::default()
-                        },
-                    );
-                }
-            }
+        if let SignalViewMode::Analog(_) = signal.mode {
+            self.paint_analog(&painter, &response, signal, &changes, info, signal_rect);
         }
         response
     }
-    /// Paint signal label
+    /// Paint signal label, changes are applied to selected signals when `index` is selected
     pub(crate) fn ui_signal_label(
         &self,
-        signal: &SignalView,
         index: usize,
+        depth: usize,
         ui: &mut Ui,
         info: &WaveInfo,
         highlight: bool,
-    ) -> Option<(SignalView, usize, bool)> {
+        actions: &mut Vec<SignalAction>,
+    ) {
+        let signal = &self.signals[index];
+        let targets = self.action_targets(index);
         let mut signal_new = signal.clone();
         let text = signal.s.to_string();
         let mut to_remove = false;
         ui.scope(|ui| {
             ui.set_height(signal.height);
             ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                let rect = ui.available_rect_before_wrap();
                 if highlight {
                     ui.painter().rect_filled(
-                        ui.available_rect_before_wrap(),
+                        rect,
                         0.0,
                         Color32::YELLOW.linear_multiply(BG_MULTIPLY),
                     );
                 }
+                if signal.selected {
+                    ui.painter()
+                        .rect_filled(rect, 0.0, ui.visuals().selection.bg_fill);
+                }
+                // guide lines of groups
+                for d in 0..depth {
+                    ui.painter().vline(
+                        rect.left() + (d as f32 + 0.5) * GROUP_INDENT,
+                        rect.y_range(),
+                        (LINE_WIDTH / 2.0, ui.visuals().weak_text_color()),
+                    );
+                }
                 let response = ui.add(Label::new(text).wrap(false).sense(Sense::click_and_drag()));
-                // TODO: drag signal order
+                if signal.s.width > 1 && info.code_paths.contains_key(&signal.s.id) {
+                    let arrow = if signal.expanded { "⏷" } else { "⏵" };
+                    if ui.add(Label::new(arrow).sense(Sense::click())).clicked() {
+                        actions.push(SignalAction::Expand(index, !signal.expanded));
+                    }
+                }
+                if response.clicked() {
+                    let mode = ui.input(|i| SelectMode::from_modifiers(&i.modifiers));
+                    actions.push(SignalAction::Select(index, mode));
+                }
+                if response.drag_started() {
+                    actions.push(SignalAction::DragStart(index));
+                }
                 response.context_menu(|ui| {
+                    if targets.len() > 1 {
+                        ui.label(t!(
+                            "view.selected",
+                            count = targets.len().to_string().as_str()
+                        ));
+                        ui.separator();
+                    }
                     if ui.button(t!("view.remove")).clicked() {
                         to_remove = true;
                         ui.close_menu();
                     }
+                    if ui.button(t!("view.group.create")).clicked() {
+                        actions.push(SignalAction::Group(GroupAction::Create(targets.clone())));
+                        ui.close_menu();
+                    }
+                    if ui.button(t!("view.derived.create")).clicked() {
+                        let paths = targets
+                            .iter()
+                            .filter_map(|i| info.code_paths.get(&self.signals[*i].s.id))
+                            .map(|path| path.join("."))
+                            .collect::<Vec<_>>();
+                        actions.push(SignalAction::EditDerived(DerivedEditor {
+                            kind: DerivedKind::Concat(format!("{{{}}}", paths.join(", "))),
+                            after: targets.last().copied(),
+                            ..Default::default()
+                        }));
+                        ui.close_menu();
+                    }
+                    if ui.button(t!("view.derived.create_expr")).clicked() {
+                        let paths = targets
+                            .iter()
+                            .filter_map(|i| info.code_paths.get(&self.signals[*i].s.id))
+                            .map(|path| path.join("."))
+                            .collect::<Vec<_>>();
+                        actions.push(SignalAction::EditDerived(DerivedEditor {
+                            kind: DerivedKind::Expr(paths.join(" & ")),
+                            after: targets.last().copied(),
+                            ..Default::default()
+                        }));
+                        ui.close_menu();
+                    }
+                    if let Some(path) = info
+                        .code_paths
+                        .get(&signal.s.id)
+                        .filter(|_| signal.s.width > 1)
+                    {
+                        if ui.button(t!("view.derived.slice")).clicked() {
+                            actions.push(SignalAction::EditDerived(DerivedEditor {
+                                kind: DerivedKind::Concat(format!(
+                                    "{}[{}:0]",
+                                    path.join("."),
+                                    signal.s.width - 1
+                                )),
+                                after: Some(index),
+                                ..Default::default()
+                            }));
+                            ui.close_menu();
+                        }
+                    }
+                    if let Some(derived) = self.derived_get(signal.s.id) {
+                        if ui.button(t!("view.derived.edit")).clicked() {
+                            actions.push(SignalAction::EditDerived(DerivedEditor {
+                                id: Some(derived.id),
+                                name: derived.name.clone(),
+                                kind: derived.kind.clone(),
+                                ..Default::default()
+                            }));
+                            ui.close_menu();
+                        }
+                    }
+                    if !self.groups.is_empty() {
+                        ui.menu_button(t!("view.group.move_to"), |ui| {
+                            if ui.button(t!("view.group.top_level")).clicked() {
+                                actions.push(SignalAction::MoveToGroup(targets.clone(), None));
+                                ui.close_menu();
+                            }
+                            for group in &self.groups {
+                                let depth = self.group_chain(Some(group.id)).len() - 1;
+                                let text = format!("{}{}", "  ".repeat(depth), group.name);
+                                if ui.button(text).clicked() {
+                                    actions.push(SignalAction::MoveToGroup(
+                                        targets.clone(),
+                                        Some(group.id),
+                                    ));
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                    }
+                    // analog waves can be taller to show values in finer scale
+                    let height_max = match signal.mode {
+                        SignalViewMode::Analog(_) => SIGNAL_HEIGHT_DEFAULT * 10.0,
+                        _ => SIGNAL_HEIGHT_DEFAULT * 4.0,
+                    };
                     ui.horizontal(|ui| {
                         ui.label(t!("view.height"));
                         DragValue::new(&mut signal_new.height)
-                            .clamp_range(
-                                (SIGNAL_HEIGHT_DEFAULT / 2.0)..=(SIGNAL_HEIGHT_DEFAULT * 4.0),
-                            )
+                            .clamp_range((SIGNAL_HEIGHT_DEFAULT / 2.0)..=height_max)
                             .speed(1.0)
                             .suffix("px")
                             .ui(ui);
@@ -601,14 +640,9 @@ impl WaveView {
                                 ui.close_menu();
                             }
                             ui.menu_button(t!("view.mode.number"), |ui| {
-                                use Radix::*;
-                                let data = [Hex, Oct, Dec, Bin];
-                                data.into_iter().for_each(|r| {
-                                    if ui.button(format!("{r:?}")).clicked() {
-                                        signal_new.mode = SignalViewMode::Number(r);
-                                        ui.close_menu();
-                                    }
-                                });
+                                if let Some(r) = ui_radix_menu(ui) {
+                                    signal_new.mode = SignalViewMode::Number(r);
+                                }
                             });
                             ui.menu_button(t!("view.mode.analog"), |ui| {
                                 let v = [AnalogDisplayType::Interpolated, AnalogDisplayType::Step];
@@ -621,9 +655,12 @@ impl WaveView {
                                         ui.close_menu();
                                     }
                                 }
+                                ui.separator();
+                                ui_analog_options(ui, &mut signal_new.analog, signal.s.width);
                             });
                         },
                     );
+                    self.ui_value_table_menu(ui, signal, &targets, info, actions);
                     if !self.sources.is_empty() {
                         if ui.button(t!("view.to_source")).clicked() {
                             let id = signal.s.id;
@@ -638,10 +675,21 @@ impl WaveView {
                 });
             });
         });
-        if to_remove || signal_new != *signal {
-            Some((signal_new, index, to_remove))
-        } else {
-            None
+        if to_remove {
+            actions.push(SignalAction::Remove(targets));
+            return;
+        }
+        if signal_new.height != signal.height {
+            actions.push(SignalAction::SetHeight(targets.clone(), signal_new.height));
+        }
+        if signal_new.color != signal.color {
+            actions.push(SignalAction::SetColor(targets.clone(), signal_new.color));
+        }
+        if signal_new.analog != signal.analog {
+            actions.push(SignalAction::SetAnalog(targets.clone(), signal_new.analog));
+        }
+        if signal_new.mode != signal.mode {
+            actions.push(SignalAction::SetMode(targets, signal_new.mode));
         }
     }
     pub fn get_radix(&self, signal: &SignalView) -> Radix {
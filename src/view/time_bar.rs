@@ -224,6 +224,34 @@ impl WaveView {
                     if let Some(span) = span_to_add {
                         self.spans.push(span);
                     }
+                    let pos = match id {
+                        -1 => Some(self.marker.pos),
+                        _ => self.cursors_get(id).map(|c| c.pos),
+                    };
+                    let mut others = self
+                        .cursors
+                        .iter()
+                        .chain(std::iter::once(&self.marker))
+                        .filter(|c| c.valid && c.id != id)
+                        .map(|c| (c.name.to_string(), c.pos))
+                        .collect::<Vec<_>>();
+                    others.sort_by_key(|(_, pos)| *pos);
+                    if let Some(pos) = pos {
+                        let mut zoom_to = None;
+                        ui.add_enabled_ui(!others.is_empty(), |ui| {
+                            ui.menu_button(t!("view.time.zoom_to"), |ui| {
+                                for (name, other) in others {
+                                    if ui.button(name).clicked() {
+                                        zoom_to = Some(other);
+                                        ui.close_menu();
+                                    }
+                                }
+                            });
+                        });
+                        if let Some(other) = zoom_to {
+                            self.zoom_to(pos, other);
+                        }
+                    }
                 }
             }
             if ui.button(t!("view.time.cursor_remove_all")).clicked() {
@@ -1,15 +1,19 @@
 use crate::message::RvcdMsg;
-use crate::radix::Radix;
+use crate::shortcut::ShortcutAction;
 use crate::utils::get_text_size;
 use crate::view::cursor::WaveCursor;
+use crate::view::group::{SignalRow, GROUP_INDENT};
+use crate::view::select::SignalAction;
+use crate::view::signal::ui_radix_menu;
 use crate::view::{
     WaveView, BG_MULTIPLY, LINE_WIDTH, SIGNAL_HEIGHT_DEFAULT, UI_WIDTH_OFFSET, WAVE_MARGIN_TOP,
     WAVE_MARGIN_TOP2, ZOOM_SIZE_MAX_SCALE, ZOOM_SIZE_MIN,
 };
+use crate::wave::search::EdgeKind;
 use crate::wave::{Wave, WaveInfo};
 use egui::{
-    pos2, vec2, Align, Align2, CentralPanel, Color32, Direction, DragValue, Event, FontId, Layout,
-    PointerButton, Pos2, Rect, Response, Sense, TopBottomPanel, Ui, Widget,
+    pos2, vec2, Align, Align2, Button, CentralPanel, Color32, Direction, DragValue, Event, FontId,
+    Layout, PointerButton, Pos2, Rect, Response, Sense, SidePanel, TopBottomPanel, Ui, Widget,
 };
 use egui_extras::{Column, TableBuilder};
 use num_traits::Float;
@@ -56,6 +60,8 @@ impl ResponsePointerState {
 pub struct ResponseHandleState {
     pub pointer: ResponsePointerState,
     pub new_range: (f64, f64),
+    /// Range changed by zooming, not scrolling
+    pub zoomed: bool,
 }
 impl ResponseHandleState {
     pub fn new(old_range: (f64, f64)) -> Self {
@@ -71,16 +77,11 @@ impl WaveView {
     pub fn menu(&mut self, ui: &mut Ui) {
         ui.menu_button(t!("menu.view"), |ui| {
             ui.menu_button(
-                format!("{}: {:?}", t!("view.default_radix"), self.default_radix),
+                format!("{}: {}", t!("view.default_radix"), self.default_radix),
                 |ui| {
-                    use Radix::*;
-                    let data = [Hex, Oct, Dec, Bin];
-                    data.into_iter().for_each(|r| {
-                        if ui.button(format!("{r:?}")).clicked() {
-                            self.default_radix = r;
-                            ui.close_menu();
-                        }
-                    });
+                    if let Some(r) = ui_radix_menu(ui) {
+                        self.default_radix = r;
+                    }
                 },
             );
             ui.menu_button(format!("{}: {:?}", t!("view.align"), self.align), |ui| {
@@ -141,10 +142,47 @@ impl WaveView {
         ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
             if ui.button(t!("view.toolbar.clear")).clicked() {
                 self.signals.clear();
+                self.groups.clear();
+                self.derived.clear();
             }
             if ui.button(t!("view.toolbar.reset")).clicked() {
-                self.range = (info.range.0 as f64, info.range.1 as f64);
+                self.range_set((info.range.0 as f64, info.range.1 as f64));
+            }
+            if ui
+                .add_enabled(self.zoom_history.can_back(), Button::new("⏴"))
+                .on_hover_text(t!("view.toolbar.zoom_back"))
+                .clicked()
+            {
+                self.zoom_back();
+            }
+            if ui
+                .add_enabled(self.zoom_history.can_forward(), Button::new("⏵"))
+                .on_hover_text(t!("view.toolbar.zoom_forward"))
+                .clicked()
+            {
+                self.zoom_forward();
+            }
+            if let Some((a, b)) = self.marker_region {
+                if ui
+                    .button(t!("view.toolbar.zoom_region"))
+                    .on_hover_text(format!(
+                        "{} ~ {}",
+                        self.pos_to_time(&info.timescale, a),
+                        self.pos_to_time(&info.timescale, b)
+                    ))
+                    .clicked()
+                {
+                    self.zoom_to(a, b);
+                    self.marker_region = None;
+                }
+            }
+            if ui
+                .selectable_label(self.value_search.is_some(), t!("view.toolbar.find_value"))
+                .clicked()
+            {
+                self.value_search_toggle();
             }
+            ui.toggle_value(&mut self.cursor_panel, t!("view.toolbar.cursors"));
             if ui.button(t!("view.toolbar.reload")).clicked() {
                 if let Some(tx) = &self.tx {
                     debug!("reload msg sent");
@@ -153,6 +191,20 @@ impl WaveView {
                     warn!("no tx in view!");
                 }
             }
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                if ui
+                    .checkbox(&mut self.follow, t!("view.toolbar.follow"))
+                    .changed()
+                {
+                    if let Some(tx) = &self.tx {
+                        tx.send(RvcdMsg::SetFollow(self.follow)).unwrap();
+                    }
+                }
+                if self.follow {
+                    ui.checkbox(&mut self.auto_scroll, t!("view.toolbar.auto_scroll"));
+                }
+            }
             // const EDIT_WIDTH: f32 = 100.0;
             ui.label(t!("view.toolbar.from"));
             let speed_min = 0.1;
@@ -245,6 +297,7 @@ impl WaveView {
                         {
                             tracing::debug!("zoom: {:?} -> {:?}", state.new_range, new_range_check);
                             state.new_range = new_range_check;
+                            state.zoomed = true;
                         }
                     }
                 }
@@ -260,6 +313,7 @@ impl WaveView {
     /// Paint wave panel
     pub fn panel(&mut self, ui: &mut Ui, wave: &Wave) {
         let info: &WaveInfo = &wave.info;
+        self.derived_update(wave);
         if self.range.0 == 0.0 && self.range.1 == 0.0 {
             self.range = (info.range.0 as f64, info.range.1 as f64);
         }
@@ -267,15 +321,34 @@ impl WaveView {
             .resizable(false)
             .show_inside(ui, |ui| {
                 self.toolbar(ui, &wave.info);
+                self.ui_value_search(ui, wave);
             });
+        if self.cursor_panel {
+            SidePanel::right(format!("wave_cursors_{}", self.id))
+                .resizable(true)
+                .show_inside(ui, |ui| {
+                    self.ui_cursor_panel(ui, wave);
+                });
+        }
         CentralPanel::default().show_inside(ui, |ui| {
             // bugs by: https://github.com/emilk/egui/issues/2430
             let use_rect = ui.max_rect();
             const DEFAULT_MIN_SIGNAL_WIDTH: f32 = 150.0;
+            let rows = self.signal_rows();
             let fixed_name_width = f32::max(
-                self.signals
-                    .iter()
-                    .map(|x| get_text_size(ui, x.s.to_string().as_str(), Default::default()).x)
+                rows.iter()
+                    .map(|row| {
+                        let text = match row {
+                            SignalRow::Group(id, _) => self.group_label_text(*id),
+                            SignalRow::Signal(index, _) => match &self.signals[*index].s {
+                                // leave space for expand arrow
+                                s if s.width > 1 => format!("⏵ {s}"),
+                                s => s.to_string(),
+                            },
+                        };
+                        get_text_size(ui, text.as_str(), Default::default()).x
+                            + row.depth() as f32 * GROUP_INDENT
+                    })
                     .reduce(f32::max)
                     .unwrap_or(0.0),
                 DEFAULT_MIN_SIGNAL_WIDTH,
@@ -298,8 +371,8 @@ impl WaveView {
                 self.signals
                     .iter()
                     .map(|s| {
-                        wave.find_value(s.s.id, marker_value_pos)
-                            .map(|v| v.value.as_radix(self.get_radix(s)))
+                        self.find_value(wave, s.s.id, marker_value_pos)
+                            .map(|v| v.value.as_radix_width(self.get_radix(s), s.s.width))
                             .unwrap_or("".to_string())
                     })
                     .collect::<Vec<_>>()
@@ -320,7 +393,8 @@ impl WaveView {
             self.wave_width = use_rect.width() - fixed_name_width - fixed_value_width;
             let mut wave_left: f32 =
                 fixed_name_width + fixed_value_width + use_rect.left() + UI_WIDTH_OFFSET;
-            let mut new_signals = vec![];
+            let mut signal_actions = vec![];
+            let mut row_rects = vec![];
             let mut last_paint_row_index = None;
             let mut dragging_pos = None;
             let mut pointer_state = ResponsePointerState::default();
@@ -367,22 +441,49 @@ impl WaveView {
                     })
                     .body(|body| {
                         body.heterogeneous_rows(
-                            self.signals.iter().map(|x| x.height),
+                            rows.iter().map(|row| match row {
+                                SignalRow::Group(..) => SIGNAL_HEIGHT_DEFAULT,
+                                SignalRow::Signal(index, _) => self.signals[*index].height,
+                            }),
                             |mut row| {
                                 let row_index = row.index();
-                                let signal = self.signals.get(row_index);
                                 last_paint_row_index = Some(row_index);
-                                if let Some(signal) = signal {
-                                    let highlight = self.highlight_signals.contains(&signal.s.id);
+                                if let Some(SignalRow::Group(id, depth)) = rows.get(row_index) {
                                     row.col(|ui| {
-                                        if let Some(signal_new) = self.ui_signal_label(
-                                            signal, row_index, ui, &wave.info, highlight,
-                                        ) {
-                                            new_signals.push(signal_new);
+                                        row_rects.push((row_index, ui.max_rect()));
+                                        if let Some(action) = self.ui_group_label(*id, *depth, ui) {
+                                            signal_actions.push(SignalAction::Group(action));
                                         }
                                     });
+                                    row.col(|_| {});
                                     row.col(|ui| {
-                                        if let Some(value) = signal_values_text.get(row_index) {
+                                        let response = self.ui_group_wave(*id, wave, ui);
+                                        if let Some(pos) = response.interact_pointer_pos() {
+                                            dragging_pos = Some(pos - vec2(wave_left, 0.0));
+                                        }
+                                        wave_left = ui.available_rect_before_wrap().left();
+                                        pointer_state.handle_pointer_response(&response, wave_left);
+                                    });
+                                }
+                                let (signal_index, depth) = match rows.get(row_index) {
+                                    Some(SignalRow::Signal(index, depth)) => (*index, *depth),
+                                    _ => return,
+                                };
+                                if let Some(signal) = self.signals.get(signal_index) {
+                                    let highlight = self.highlight_signals.contains(&signal.s.id);
+                                    row.col(|ui| {
+                                        row_rects.push((row_index, ui.max_rect()));
+                                        self.ui_signal_label(
+                                            signal_index,
+                                            depth,
+                                            ui,
+                                            &wave.info,
+                                            highlight,
+                                            &mut signal_actions,
+                                        );
+                                    });
+                                    row.col(|ui| {
+                                        if let Some(value) = signal_values_text.get(signal_index) {
                                             let (response, painter) = ui.allocate_painter(
                                                 ui.max_rect().size(),
                                                 Sense::click_and_drag(),
@@ -407,9 +508,9 @@ impl WaveView {
                                         }
                                     });
                                     row.col(|ui| {
-                                        if let Some(data) = wave.data.get(&signal.s.id) {
+                                        if let Some(data) = self.signal_data(wave, signal.s.id) {
                                             let response =
-                                                self.ui_signal_wave(signal, data, info, ui);
+                                                self.ui_signal_wave(signal, &data, info, ui);
                                             if let Some(pos) = response.interact_pointer_pos() {
                                                 dragging_pos = Some(pos - vec2(wave_left, 0.0));
                                             }
@@ -435,20 +536,18 @@ impl WaveView {
             let state =
                 self.handle_response(ui, &global_response, wave_left, &wave.info, self.range);
             // update signal information
-            let signals_updated = self
-                .signals
-                .iter()
-                .cloned()
-                .enumerate()
-                .filter_map(|x| match new_signals.iter().find(|c| c.1 == x.0) {
-                    None => Some(x.1),
-                    Some(c) => match c.2 {
-                        true => None,
-                        false => Some(c.0.clone()),
-                    },
-                })
-                .collect();
-            self.signals = signals_updated;
+            let rows_drop = self.ui_rows_drag(ui, &rows, &row_rects);
+            if signal_actions.is_empty() {
+                if let Some((item, target)) = rows_drop {
+                    self.rows_drop(item, target);
+                }
+            }
+            self.signal_handle_actions(signal_actions, info);
+            self.ui_derived_editor(ui, info);
+            if state.zoomed {
+                let time = ui.input(|i| i.time);
+                self.zoom_history.push_continuous(self.range, time);
+            }
             self.range = state.new_range;
             // info!("fix_width = {}, ui left = {}, wave_left = {}", fix_width, ui.max_rect().left(), wave_left);
             // info!("(fix_width + ui left) - wave_left = {}", fix_width + ui.max_rect().left() - wave_left);
@@ -469,6 +568,9 @@ impl WaveView {
                         fpos as u64
                     }
                     .clamp(self.range.0 as u64, self.range.1 as u64);
+                    if pointer_state.drag_by_primary && !self.marker_temp.valid {
+                        self.drag_region_start = Some(p);
+                    }
                     self.marker_temp.set_pos_valid(p);
                     if pointer_state.drag_by_secondary && !self.range_seek_started {
                         self.marker.set_pos_valid(p);
@@ -503,8 +605,7 @@ impl WaveView {
                                 self.move_drag_start_pos = Some(move_drag_pos);
                             }
                             if dy > SIGNAL_HEIGHT_DEFAULT {
-                                let index =
-                                    usize::min(last_paint_row_index + 1, self.signals.len() - 1);
+                                let index = usize::min(last_paint_row_index + 1, rows.len() - 1);
                                 debug!("to next signal: {}", index);
                                 self.scrolling_next_index = Some(index);
                                 self.move_drag_start_pos = Some(move_drag_pos);
@@ -521,16 +622,13 @@ impl WaveView {
                 if pointer_state.drag_release && self.marker_temp.valid {
                     // scale to range
                     if self.last_pointer_state.drag_by_secondary {
-                        let (a, b) = if self.marker.pos < self.marker_temp.pos {
-                            (&self.marker, &self.marker_temp)
-                        } else {
-                            (&self.marker_temp, &self.marker)
-                        };
-                        let range_new = (a.pos as f64, b.pos as f64);
-                        debug!("range_new: {:?}", range_new);
-                        if range_new.1 - range_new.0 > 1.0 {
-                            self.range = range_new;
-                        }
+                        let (a, b) = (self.marker.pos, self.marker_temp.pos);
+                        debug!("range_new: {:?}", (a.min(b), a.max(b)));
+                        self.zoom_to(a, b);
+                    } else if let Some(start) = self.drag_region_start.take() {
+                        let end = self.marker_temp.pos;
+                        self.marker_region =
+                            Some((start.min(end), start.max(end))).filter(|(a, b)| b > a);
                     }
                     self.marker.set_pos_valid(
                         self.marker_temp
@@ -579,6 +677,16 @@ impl WaveView {
             self.last_pointer_state = pointer_state;
         });
     }
+    /// Move view range to the end of wave, keeping its width
+    pub fn scroll_to_end(&mut self, info: &WaveInfo) {
+        let width = self.range.1 - self.range.0;
+        let end = info.range.1 as f64;
+        self.range = if self.limit_range_left && end < width {
+            (0.0, width)
+        } else {
+            (end - width, end)
+        };
+    }
     pub fn move_horizontal(&self, dx: f32, info: &WaveInfo) -> (f64, f64) {
         let pos_delta = self.x_to_fpos(dx) as f64 - self.range.0;
         let new_range_check = (self.range.0 + pos_delta, self.range.1 + pos_delta);
@@ -600,6 +708,81 @@ impl WaveView {
             }
         }
     }
+    /// Zoom view by `scale` around main marker in view, or center of view
+    pub fn zoom(&mut self, scale: f64, info: &WaveInfo) {
+        let center = match self.marker.valid {
+            true if (self.range.0..=self.range.1).contains(&(self.marker.pos as f64)) => {
+                self.marker.pos as f64
+            }
+            _ => (self.range.0 + self.range.1) / 2.0,
+        };
+        let range = (
+            center - (center - self.range.0) * scale,
+            center + (self.range.1 - center) * scale,
+        );
+        let d = range.1 - range.0;
+        if d > ZOOM_SIZE_MIN as f64
+            && d < ZOOM_SIZE_MAX_SCALE as f64 * (info.range.1 - info.range.0) as f64
+        {
+            self.range_set(match self.limit_range_left && range.0 < 0.0 {
+                true => (0.0, d),
+                false => range,
+            });
+        }
+    }
+    /// Run view action of keyboard shortcut
+    pub fn handle_shortcut(&mut self, action: ShortcutAction, wave: &Wave) {
+        let info = &wave.info;
+        match action {
+            ShortcutAction::ZoomIn => self.zoom(0.5, info),
+            ShortcutAction::ZoomOut => self.zoom(2.0, info),
+            ShortcutAction::ZoomFit => {
+                self.range_set((info.range.0 as f64, info.range.1 as f64));
+            }
+            ShortcutAction::ZoomBack => self.zoom_back(),
+            ShortcutAction::ZoomForward => self.zoom_forward(),
+            ShortcutAction::PanLeft => {
+                self.range = self.move_horizontal(-self.wave_width / 4.0, info);
+            }
+            ShortcutAction::PanRight => {
+                self.range = self.move_horizontal(self.wave_width / 4.0, info);
+            }
+            ShortcutAction::AddCursor => {
+                let pos = match self.marker.valid {
+                    true => self.marker.pos,
+                    false => ((self.range.0 + self.range.1) / 2.0).max(0.0) as u64,
+                };
+                self.cursors
+                    .push(WaveCursor::new(self.next_cursor_id(), pos));
+            }
+            ShortcutAction::RemoveSignals => {
+                let selected = self.selected_signals();
+                self.signal_handle_actions(vec![SignalAction::Remove(selected)], info);
+            }
+            ShortcutAction::ClearSelection => self.selection_clear(),
+            ShortcutAction::FindValue => self.value_search_toggle(),
+            ShortcutAction::NextEdge => {
+                self.edge_jump(wave, EdgeKind::Any, true);
+            }
+            ShortcutAction::PrevEdge => {
+                self.edge_jump(wave, EdgeKind::Any, false);
+            }
+            ShortcutAction::NextRisingEdge => {
+                self.edge_jump(wave, EdgeKind::Rising, true);
+            }
+            ShortcutAction::PrevRisingEdge => {
+                self.edge_jump(wave, EdgeKind::Rising, false);
+            }
+            ShortcutAction::NextFallingEdge => {
+                self.edge_jump(wave, EdgeKind::Falling, true);
+            }
+            ShortcutAction::PrevFallingEdge => {
+                self.edge_jump(wave, EdgeKind::Falling, false);
+            }
+            // handled by app
+            ShortcutAction::OpenFile | ShortcutAction::Reload => {}
+        }
+    }
     /// Paint span between two cursors
     pub fn paint_span(
         &self,
@@ -0,0 +1,287 @@
+use crate::radix::Radix;
+use crate::view::select::SignalAction;
+use crate::view::signal::SignalView;
+use crate::view::WaveView;
+use crate::wave::derive::value_bits;
+use crate::wave::search::parse_query_number;
+use crate::wave::{WaveDataValue, WaveInfo, WireValue};
+use anyhow::{anyhow, Result};
+use egui::{Color32, Ui};
+use egui_toast::ToastKind;
+
+/// Name of a value in [ValueTable]
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Debug, Clone)]
+pub struct ValueName {
+    /// Bits most significant first without leading zeros, like `101` or `1x`
+    pub bits: String,
+    pub name: String,
+    /// Background of value box
+    pub color: Option<Color32>,
+}
+
+/// Translation from signal values to names, like states of state machines
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ValueTable {
+    /// File or source the table comes from
+    pub source: String,
+    pub names: Vec<ValueName>,
+}
+
+/// Key of bits most significant first, leading zeros are removed
+fn bits_key(bits: impl Iterator<Item = WireValue>) -> String {
+    let key = bits.map(|b| b.to_string()).collect::<String>();
+    match key.trim_start_matches('0') {
+        "" => "0".to_string(),
+        key => key.to_string(),
+    }
+}
+
+/// Color like `#ff8000` or a common color name
+fn parse_color(text: &str) -> Option<Color32> {
+    if let Some(hex) = text.strip_prefix('#') {
+        let rgb = u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.len() == 6)?;
+        return Some(Color32::from_rgb(
+            (rgb >> 16) as u8,
+            (rgb >> 8) as u8,
+            rgb as u8,
+        ));
+    }
+    match text.to_ascii_lowercase().as_str() {
+        "red" => Some(Color32::RED),
+        "orange" => Some(Color32::from_rgb(255, 165, 0)),
+        "yellow" => Some(Color32::YELLOW),
+        "green" => Some(Color32::GREEN),
+        "blue" => Some(Color32::BLUE),
+        "cyan" => Some(Color32::from_rgb(0, 255, 255)),
+        "magenta" => Some(Color32::from_rgb(255, 0, 255)),
+        "purple" | "violet" => Some(Color32::from_rgb(238, 130, 238)),
+        "gray" | "grey" => Some(Color32::GRAY),
+        "white" => Some(Color32::WHITE),
+        "black" => Some(Color32::BLACK),
+        _ => None,
+    }
+}
+
+impl ValueTable {
+    /// Parse lines of value and name like `3 IDLE` or `0x1f ?red?BUSY` in GTKWave filter style,
+    /// numbers without prefix are in `radix`, lines starting with `#` are comments
+    pub fn parse(text: &str, radix: Radix) -> Result<Self> {
+        // width of signals is unknown here, other radixes use decimal
+        let radix = match radix.to_number() {
+            Some(_) => radix,
+            None => Radix::Dec,
+        };
+        let mut names = vec![];
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (value, name) = line
+                .split_once(char::is_whitespace)
+                .map(|(value, name)| (value, name.trim()))
+                .ok_or_else(|| anyhow!("line {}: no name for {}", line_no + 1, line))?;
+            let bits = parse_query_number(value, radix.clone(), None)
+                .map_err(|e| anyhow!("line {}: {}", line_no + 1, e))?;
+            // unknown color names are ignored
+            let (name, color) = match name.strip_prefix('?').and_then(|rest| rest.split_once('?')) {
+                Some((color, name)) => (name, parse_color(color)),
+                None => (name, None),
+            };
+            names.push(ValueName {
+                bits: bits_key(bits.to_msb().into_iter()),
+                name: name.to_string(),
+                color,
+            });
+        }
+        Ok(Self {
+            source: String::new(),
+            names,
+        })
+    }
+    /// Read table from file, see [ValueTable::parse]
+    pub fn load(path: &str, radix: Radix) -> Result<Self> {
+        let table = Self::parse(std::fs::read_to_string(path)?.as_str(), radix)?;
+        Ok(Self {
+            source: path.to_string(),
+            ..table
+        })
+    }
+    /// Table of verilog parameters as (name, value expression),
+    /// parameters with values that are not numbers are skipped
+    pub fn from_params(source: &str, params: &[(String, String)]) -> Self {
+        let mut names: Vec<ValueName> = vec![];
+        for (name, value) in params {
+            if let Ok(bits) = parse_query_number(value, Radix::Dec, None) {
+                let bits = bits_key(bits.to_msb().into_iter());
+                if !names.iter().any(|n| n.bits == bits) {
+                    names.push(ValueName {
+                        bits,
+                        name: name.to_string(),
+                        color: None,
+                    });
+                }
+            }
+        }
+        Self {
+            source: source.to_string(),
+            names,
+        }
+    }
+    /// Name of `value` of signal in `width` bits
+    pub fn get(&self, value: &WaveDataValue, width: u64) -> Option<&ValueName> {
+        if !value.is_bits() {
+            return None;
+        }
+        let key = bits_key(value_bits(value, width).into_iter());
+        self.names.iter().find(|n| n.bits == key)
+    }
+}
+
+impl WaveView {
+    /// Attach value table to signals with `ids`, or remove their tables
+    pub fn value_table_set(&mut self, ids: &[u64], table: Option<ValueTable>) {
+        for signal in self.signals.iter_mut().filter(|s| ids.contains(&s.s.id)) {
+            signal.value_table = table.clone();
+        }
+    }
+    /// Value table of signal from parameters used as `case` labels of it in sources
+    pub fn value_table_from_sources(
+        &self,
+        signal: &SignalView,
+        info: &WaveInfo,
+    ) -> Option<ValueTable> {
+        let mut path = info.code_paths.get(&signal.s.id)?.clone();
+        path.push(signal.s.name.to_string());
+        self.sources.iter().find_map(|source| {
+            let params = source.search_case_params(&path);
+            let table = ValueTable::from_params(&source.source_path, &params);
+            Some(table).filter(|t| !t.names.is_empty())
+        })
+    }
+    /// Paint value names menu of signal, tables are applied to `targets`
+    pub(crate) fn ui_value_table_menu(
+        &self,
+        ui: &mut Ui,
+        signal: &SignalView,
+        targets: &[usize],
+        info: &WaveInfo,
+        actions: &mut Vec<SignalAction>,
+    ) {
+        ui.menu_button(t!("view.value_table.title"), |ui| {
+            if let Some(table) = &signal.value_table {
+                ui.label(t!(
+                    "view.value_table.current",
+                    source = table.source.as_str(),
+                    count = table.names.len().to_string().as_str()
+                ));
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let ids = targets
+                    .iter()
+                    .map(|i| self.signals[*i].s.id)
+                    .collect::<Vec<_>>();
+                for (text, radix) in [
+                    (t!("view.value_table.load"), Radix::Dec),
+                    (t!("view.value_table.load_gtkw"), Radix::Hex),
+                ] {
+                    if ui.button(text).clicked() {
+                        let task = rfd::AsyncFileDialog::new().pick_file();
+                        let tx = self.tx.clone();
+                        let ids = ids.clone();
+                        crate::utils::execute(async move {
+                            if let (Some(file), Some(tx)) = (task.await, tx) {
+                                let path = file.path().to_str().unwrap().to_string();
+                                tx.send(crate::message::RvcdMsg::LoadValueTable(ids, path, radix))
+                                    .ok();
+                            }
+                        });
+                        ui.close_menu();
+                    }
+                }
+            }
+            if !self.sources.is_empty() && ui.button(t!("view.value_table.from_source")).clicked() {
+                let mut found = false;
+                for index in targets {
+                    if let Some(table) = self.value_table_from_sources(&self.signals[*index], info)
+                    {
+                        actions.push(SignalAction::SetValueTable(vec![*index], Some(table)));
+                        found = true;
+                    }
+                }
+                if !found {
+                    self.toast(ToastKind::Warning, t!("msg.value_table_not_found"));
+                }
+                ui.close_menu();
+            }
+            if signal.value_table.is_some() && ui.button(t!("view.value_table.clear")).clicked() {
+                actions.push(SignalAction::SetValueTable(targets.to_vec(), None));
+                ui.close_menu();
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::radix::Radix;
+    use crate::view::value_table::ValueTable;
+    use crate::wave::WaveDataValue;
+    use crate::wave::WireValue::*;
+    use egui::Color32;
+
+    #[test]
+    fn test_value_table() -> anyhow::Result<()> {
+        let text = r#"
+# states of fsm
+0 IDLE
+1 ?red?BUSY
+0x2 ?#00ff00?DONE
+4'b1x00 ?unknown_color?PARTIAL
+"#;
+        let table = ValueTable::parse(text, Radix::Dec)?;
+        assert_eq!(table.names.len(), 4);
+        let name = |value: &WaveDataValue| table.get(value, 4).map(|n| n.name.as_str());
+        assert_eq!(name(&WaveDataValue::Comp(vec![0])), Some("IDLE"));
+        assert_eq!(name(&WaveDataValue::Comp(vec![2])), Some("DONE"));
+        assert_eq!(name(&WaveDataValue::Comp(vec![3])), None);
+        assert_eq!(
+            name(&WaveDataValue::Raw(vec![V1, X, V0, V0])),
+            Some("PARTIAL")
+        );
+        assert_eq!(name(&WaveDataValue::Str("1".to_string())), None);
+        assert_eq!(table.names[1].color, Some(Color32::RED));
+        assert_eq!(table.names[2].color, Some(Color32::GREEN));
+        assert_eq!(table.names[3].color, None);
+        // gtkwave filter values are hex
+        let table = ValueTable::parse("0A ?blue?TEN\n10 SIXTEEN", Radix::Hex)?;
+        assert_eq!(
+            table.get(&WaveDataValue::Comp(vec![10]), 8).unwrap().name,
+            "TEN"
+        );
+        assert_eq!(
+            table.get(&WaveDataValue::Comp(vec![16]), 8).unwrap().name,
+            "SIXTEEN"
+        );
+        let params = [
+            ("IDLE", "2'd0"),
+            ("RUN", "2'b10"),
+            ("HALT", "3"),
+            ("WIDTH", "N-1"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        let table = ValueTable::from_params("fsm.v", &params);
+        assert_eq!(table.names.len(), 3);
+        assert_eq!(
+            table.get(&WaveDataValue::Comp(vec![2]), 2).unwrap().name,
+            "RUN"
+        );
+        assert!(ValueTable::parse("5", Radix::Dec).is_err());
+        assert!(ValueTable::parse("g NAME", Radix::Dec).is_err());
+        Ok(())
+    }
+}
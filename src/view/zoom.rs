@@ -0,0 +1,125 @@
+use crate::view::WaveView;
+
+/// Zoom changes within this time are recorded as one history entry
+pub const ZOOM_HISTORY_MERGE_SECONDS: f64 = 0.5;
+pub const ZOOM_HISTORY_MAX_LEN: usize = 100;
+
+/// Visited view ranges for back and forward navigation
+#[derive(Debug, Clone, Default)]
+pub struct ZoomHistory {
+    back: Vec<(f64, f64)>,
+    forward: Vec<(f64, f64)>,
+    /// Time of last continuous zooming, like mouse wheel
+    last_time: Option<f64>,
+}
+
+impl ZoomHistory {
+    /// Record `range` left by zooming
+    pub fn push(&mut self, range: (f64, f64)) {
+        if self.back.last() != Some(&range) {
+            self.back.push(range);
+            if self.back.len() > ZOOM_HISTORY_MAX_LEN {
+                self.back.remove(0);
+            }
+        }
+        self.forward.clear();
+        self.last_time = None;
+    }
+    /// Record `range` left by continuous zooming at `time` in seconds
+    pub fn push_continuous(&mut self, range: (f64, f64), time: f64) {
+        if self
+            .last_time
+            .map(|t| time - t > ZOOM_HISTORY_MERGE_SECONDS)
+            .unwrap_or(true)
+        {
+            self.push(range);
+        }
+        self.last_time = Some(time);
+    }
+    /// Go back from `current` range
+    pub fn back(&mut self, current: (f64, f64)) -> Option<(f64, f64)> {
+        let range = self.back.pop()?;
+        self.forward.push(current);
+        self.last_time = None;
+        Some(range)
+    }
+    /// Go forward from `current` range
+    pub fn forward(&mut self, current: (f64, f64)) -> Option<(f64, f64)> {
+        let range = self.forward.pop()?;
+        self.back.push(current);
+        self.last_time = None;
+        Some(range)
+    }
+    pub fn can_back(&self) -> bool {
+        !self.back.is_empty()
+    }
+    pub fn can_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+}
+
+impl WaveView {
+    /// Change view range and record the old one in zoom history
+    pub fn range_set(&mut self, range: (f64, f64)) {
+        if range != self.range {
+            self.zoom_history.push(self.range);
+            self.range = range;
+        }
+    }
+    /// Zoom to wave positions between `a` and `b`
+    pub fn zoom_to(&mut self, a: u64, b: u64) {
+        let range = (a.min(b) as f64, a.max(b) as f64);
+        if range.1 - range.0 > 1.0 {
+            self.range_set(range);
+        }
+    }
+    pub fn zoom_back(&mut self) {
+        if let Some(range) = self.zoom_history.back(self.range) {
+            self.range = range;
+        }
+    }
+    pub fn zoom_forward(&mut self) {
+        if let Some(range) = self.zoom_history.forward(self.range) {
+            self.range = range;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::view::zoom::ZoomHistory;
+    use crate::view::WaveView;
+
+    #[test]
+    fn test_zoom_history() {
+        let mut history = ZoomHistory::default();
+        // wheel zooming in short time is one entry
+        history.push_continuous((0.0, 100.0), 1.0);
+        history.push_continuous((10.0, 90.0), 1.2);
+        history.push_continuous((20.0, 80.0), 2.0);
+        assert_eq!(history.back((30.0, 70.0)), Some((20.0, 80.0)));
+        assert_eq!(history.back((20.0, 80.0)), Some((0.0, 100.0)));
+        assert_eq!(history.back((0.0, 100.0)), None);
+        assert_eq!(history.forward((0.0, 100.0)), Some((20.0, 80.0)));
+
+        let mut view = WaveView {
+            range: (0.0, 100.0),
+            ..Default::default()
+        };
+        view.zoom_to(60, 40);
+        assert_eq!(view.range, (40.0, 60.0));
+        // too small to zoom
+        view.zoom_to(50, 51);
+        assert_eq!(view.range, (40.0, 60.0));
+        view.range_set((0.0, 10.0));
+        view.zoom_back();
+        view.zoom_back();
+        assert_eq!(view.range, (0.0, 100.0));
+        assert!(!view.zoom_history.can_back());
+        view.zoom_forward();
+        assert_eq!(view.range, (40.0, 60.0));
+        // new zooming drops forward history
+        view.range_set((45.0, 55.0));
+        assert!(!view.zoom_history.can_forward());
+    }
+}
@@ -0,0 +1,251 @@
+use crate::wave::store::WaveStore;
+use crate::wave::{WaveDataItem, WaveDataValue, WaveInfo, WireValue};
+use anyhow::{anyhow, Result};
+use num_bigint::BigUint;
+
+/// Bits `[msb:lsb]` of a signal in virtual bus, bit 0 is the least significant one
+#[derive(Debug, Clone, PartialEq)]
+pub struct BusPart {
+    pub id: u64,
+    pub msb: u64,
+    pub lsb: u64,
+}
+
+impl BusPart {
+    pub fn width(&self) -> u64 {
+        self.msb - self.lsb + 1
+    }
+}
+
+pub(crate) fn signal_width(info: &WaveInfo, id: u64) -> u64 {
+    info.code_signal_info
+        .get(&id)
+        .map(|s| s.width)
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// Find signal by full path like `top.cpu.pc` or by unique path suffix like `cpu.pc`
+pub fn resolve_path(info: &WaveInfo, path: &str) -> Result<u64> {
+    let matches = info.match_paths(path);
+    match matches.as_slice() {
+        [id] => Ok(*id),
+        [] => Err(anyhow!("no signal matches {}", path)),
+        _ => matches
+            .iter()
+            .find(|id| info.code_paths[id].join(".") == path)
+            .copied()
+            .ok_or_else(|| anyhow!("{} matches {} signals", path, matches.len())),
+    }
+}
+
+/// Parse verilog style concatenation like `{top.a, b[3:0], c[2]}`,
+/// first part is the most significant one
+pub fn parse_concat(text: &str, info: &WaveInfo) -> Result<Vec<BusPart>> {
+    let text = text.trim();
+    let inner = text
+        .strip_prefix('{')
+        .and_then(|t| t.strip_suffix('}'))
+        .unwrap_or(text);
+    inner
+        .split(',')
+        .map(|part| {
+            let part = part.trim();
+            if part.is_empty() {
+                return Err(anyhow!("empty part in {}", text));
+            }
+            let (path, range) = match part.strip_suffix(']').and_then(|p| p.rsplit_once('[')) {
+                Some((path, range)) => (path.trim(), Some(range)),
+                None => (part, None),
+            };
+            let id = resolve_path(info, path)?;
+            let width = signal_width(info, id);
+            let (msb, lsb) = match range {
+                None => (width - 1, 0),
+                Some(range) => {
+                    let parse = |s: &str| {
+                        s.trim()
+                            .parse::<u64>()
+                            .map_err(|_| anyhow!("invalid bit range {}", part))
+                    };
+                    match range.split_once(':') {
+                        Some((msb, lsb)) => (parse(msb)?, parse(lsb)?),
+                        None => (parse(range)?, parse(range)?),
+                    }
+                }
+            };
+            if msb < lsb || msb >= width {
+                return Err(anyhow!("{} is out of {} bits", part, width));
+            }
+            Ok(BusPart { id, msb, lsb })
+        })
+        .collect()
+}
+
+/// Bits of value in `width`, most significant first, extended like vcd vectors
+pub(crate) fn value_bits(value: &WaveDataValue, width: u64) -> Vec<WireValue> {
+    let width = width as usize;
+    let mut bits = match value {
+        WaveDataValue::Comp(v) => {
+            let value = BigUint::from_bytes_le(v);
+            return (0..width as u64)
+                .rev()
+                .map(|i| match value.bit(i) {
+                    true => WireValue::V1,
+                    false => WireValue::V0,
+                })
+                .collect();
+        }
+        WaveDataValue::Raw(v) => v.clone(),
+        WaveDataValue::Real(_) | WaveDataValue::Str(_) => vec![WireValue::X; width],
+    };
+    if bits.len() > width {
+        bits.drain(..(bits.len() - width));
+    } else if bits.len() < width {
+        let pad = match bits.first() {
+            Some(WireValue::X) => WireValue::X,
+            Some(WireValue::Z) => WireValue::Z,
+            _ => WireValue::V0,
+        };
+        bits.splice(0..0, vec![pad; width - bits.len()]);
+    }
+    bits
+}
+
+/// Value changes of concatenated signals at or after `from`, parts without value are `x`
+pub fn concat_items(
+    store: &WaveStore,
+    info: &WaveInfo,
+    parts: &[BusPart],
+    from: u64,
+) -> Vec<WaveDataItem> {
+    let mut changes = vec![];
+    for (index, part) in parts.iter().enumerate() {
+        if let Some(data) = store.get(&part.id) {
+            // including the value before `from`
+            let start = data.position(from).saturating_sub(1);
+            changes.extend(
+                data.iter_at(start)
+                    .map(|item| (item.timestamp, index, item.value)),
+            );
+        }
+    }
+    changes.sort_by_key(|(timestamp, index, _)| (*timestamp, *index));
+    let mut current = parts
+        .iter()
+        .map(|p| vec![WireValue::X; p.width() as usize])
+        .collect::<Vec<_>>();
+    let mut last = None;
+    let mut items = vec![];
+    let mut changes = changes.into_iter().peekable();
+    while let Some((timestamp, index, value)) = changes.next() {
+        let part = &parts[index];
+        let width = signal_width(info, part.id);
+        let bits = value_bits(&value, width);
+        current[index] =
+            bits[((width - 1 - part.msb) as usize)..=((width - 1 - part.lsb) as usize)].to_vec();
+        if changes.peek().map(|c| c.0) == Some(timestamp) {
+            continue;
+        }
+        let bits = current.concat();
+        if last.as_ref() == Some(&bits) {
+            continue;
+        }
+        last = Some(bits.clone());
+        if timestamp < from {
+            continue;
+        }
+        let item = WaveDataItem {
+            value: WaveDataValue::Raw(bits),
+            timestamp,
+        };
+        if let Ok(item) = item.compress() {
+            items.push(item);
+        }
+    }
+    items
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use crate::radix::Radix;
+    use crate::wave::derive::{concat_items, parse_concat, BusPart};
+    use crate::wave::store::WaveStoreBuilder;
+    use crate::wave::WireValue::*;
+    use crate::wave::{WaveDataItem, WaveDataValue, WaveInfo, WaveSignalInfo};
+
+    /// Wave info with `(id, name, width)` signals in scope `top`
+    pub(crate) fn test_info(signals: &[(u64, &str, u64)]) -> WaveInfo {
+        let mut info = WaveInfo::default();
+        for (id, name, width) in signals.iter().copied() {
+            info.code_signal_info.insert(
+                id,
+                WaveSignalInfo {
+                    id,
+                    name: name.to_string(),
+                    width,
+                    ..Default::default()
+                },
+            );
+            info.code_paths
+                .insert(id, vec!["top".to_string(), name.to_string()]);
+        }
+        info
+    }
+
+    #[test]
+    fn test_concat() -> anyhow::Result<()> {
+        let mut info = test_info(&[(0, "a", 1), (1, "b", 8), (2, "c", 2)]);
+        info.range = (0, 100);
+        let parts = parse_concat("{top.a, b[3:0], c[1]}", &info)?;
+        assert_eq!(
+            parts[1],
+            BusPart {
+                id: 1,
+                msb: 3,
+                lsb: 0
+            }
+        );
+        assert_eq!(parts[2].width(), 1);
+        assert!(parse_concat("{a, d}", &info).is_err());
+        assert!(parse_concat("{a, b[8:0]}", &info).is_err());
+
+        let mut builder = WaveStoreBuilder::default();
+        let mut push = |id, timestamp, value| {
+            builder.push(id, &WaveDataItem { value, timestamp });
+        };
+        push(0, 0, WaveDataValue::Raw(vec![V0]));
+        push(1, 0, WaveDataValue::Comp(vec![0xa5]));
+        push(2, 0, WaveDataValue::Raw(vec![X]));
+        push(0, 10, WaveDataValue::Raw(vec![V1]));
+        push(2, 10, WaveDataValue::Comp(vec![0b10]));
+        // upper bits of b changed only
+        push(1, 20, WaveDataValue::Comp(vec![0x15]));
+        push(2, 30, WaveDataValue::Comp(vec![0b01]));
+        let store = builder.finish();
+        let items = concat_items(&store, &info, &parts, 0);
+        // value with x is kept raw, most significant bit first
+        assert!(
+            matches!(&items[0].value, WaveDataValue::Raw(v) if v == &vec![V0, V0, V1, V0, V1, X])
+        );
+        let values = items
+            .iter()
+            .skip(1)
+            .map(|i| (i.timestamp, i.value.as_radix(Radix::Bin)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![(10, "101011".to_string()), (30, "101010".to_string())]
+        );
+        // changes after a timestamp are the same as the ones computed at once
+        for from in [5, 10, 20, 25] {
+            let after = concat_items(&store, &info, &parts, from);
+            let expected = items.iter().filter(|i| i.timestamp >= from);
+            assert!(after
+                .iter()
+                .map(|i| (i.timestamp, i.value.to_string()))
+                .eq(expected.map(|i| (i.timestamp, i.value.to_string()))));
+        }
+        Ok(())
+    }
+}
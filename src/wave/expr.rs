@@ -0,0 +1,721 @@
+use crate::wave::derive::{resolve_path, signal_width, value_bits};
+use crate::wave::store::WaveStore;
+use crate::wave::WireValue::*;
+use crate::wave::{WaveDataItem, WaveDataValue, WaveInfo, WireValue};
+use anyhow::{anyhow, bail, Result};
+use num_bigint::BigUint;
+use num_traits::{One, ToPrimitive, Zero};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// Four-state vector value, bit 0 is the least significant one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bits(pub Vec<WireValue>);
+
+impl Bits {
+    /// Value from bits most significant first, like [WaveDataValue::Raw]
+    pub fn from_msb(bits: Vec<WireValue>) -> Self {
+        Self(bits.into_iter().rev().collect())
+    }
+    pub fn to_msb(&self) -> Vec<WireValue> {
+        self.0.iter().rev().copied().collect()
+    }
+    pub fn from_big(value: &BigUint, width: u64) -> Self {
+        Self(
+            (0..width)
+                .map(|i| if value.bit(i) { V1 } else { V0 })
+                .collect(),
+        )
+    }
+    fn from_bool(value: bool) -> Self {
+        Self(vec![if value { V1 } else { V0 }])
+    }
+    pub fn unknown(width: u64) -> Self {
+        Self(vec![X; width as usize])
+    }
+    pub fn width(&self) -> u64 {
+        self.0.len() as u64
+    }
+    /// Number value, `None` if any bit is `x` or `z`
+    pub fn to_big(&self) -> Option<BigUint> {
+        let mut value = BigUint::zero();
+        for (i, bit) in self.0.iter().enumerate() {
+            match bit {
+                V0 => {}
+                V1 => value.set_bit(i as u64, true),
+                X | Z => return None,
+            }
+        }
+        Some(value)
+    }
+    /// Zero extend or truncate to `width`
+    fn resize(mut self, width: u64) -> Self {
+        self.0.resize(width as usize, V0);
+        self
+    }
+    /// Logic value used in conditions, `x` if unknown
+    fn truth(&self) -> WireValue {
+        if self.0.contains(&V1) {
+            V1
+        } else if self.0.iter().all(|b| *b == V0) {
+            V0
+        } else {
+            X
+        }
+    }
+}
+
+impl Display for Bits {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}'b", self.width())?;
+        self.0.iter().rev().try_for_each(|b| write!(f, "{}", b))
+    }
+}
+
+fn bit_not(a: WireValue) -> WireValue {
+    match a {
+        V0 => V1,
+        V1 => V0,
+        _ => X,
+    }
+}
+
+fn bit_and(a: WireValue, b: WireValue) -> WireValue {
+    match (a, b) {
+        (V0, _) | (_, V0) => V0,
+        (V1, V1) => V1,
+        _ => X,
+    }
+}
+
+fn bit_or(a: WireValue, b: WireValue) -> WireValue {
+    match (a, b) {
+        (V1, _) | (_, V1) => V1,
+        (V0, V0) => V0,
+        _ => X,
+    }
+}
+
+fn bit_xor(a: WireValue, b: WireValue) -> WireValue {
+    match (a, b) {
+        (V0 | V1, V0 | V1) => match a == b {
+            true => V0,
+            false => V1,
+        },
+        _ => X,
+    }
+}
+
+/// `==` of verilog, `x` if equality depends on unknown bits
+fn logic_eq(a: &Bits, b: &Bits) -> WireValue {
+    let mut unknown = false;
+    for pair in a.0.iter().zip(b.0.iter()) {
+        match pair {
+            (V0, V1) | (V1, V0) => return V0,
+            (V0, V0) | (V1, V1) => {}
+            _ => unknown = true,
+        }
+    }
+    match unknown {
+        true => X,
+        false => V1,
+    }
+}
+
+/// Binary operators and precedences, larger binds tighter
+const BINARY_OPS: [(&str, u8); 20] = [
+    ("||", 1),
+    ("&&", 2),
+    ("|", 3),
+    ("^", 4),
+    ("&", 5),
+    ("==", 6),
+    ("!=", 6),
+    ("===", 6),
+    ("!==", 6),
+    ("<", 7),
+    ("<=", 7),
+    (">", 7),
+    (">=", 7),
+    ("<<", 8),
+    (">>", 8),
+    ("+", 9),
+    ("-", 9),
+    ("*", 10),
+    ("/", 10),
+    ("%", 10),
+];
+
+/// Unary operators, `&`, `|` and `^` are reductions
+const UNARY_OPS: [&str; 6] = ["~", "!", "-", "&", "|", "^"];
+
+/// Operator tokens, longer ones first
+const TOKEN_OPS: [&str; 31] = [
+    "===", "!==", "==", "!=", "<=", ">=", "<<", ">>", "&&", "||", "+", "-", "*", "/", "%", "&",
+    "|", "^", "~", "!", "<", ">", "?", ":", "(", ")", "[", "]", "{", "}", ",",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Num(Bits),
+    Op(&'static str),
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Ident(name) => write!(f, "{}", name),
+            Token::Num(value) => write!(f, "{}", value),
+            Token::Op(op) => write!(f, "{}", op),
+        }
+    }
+}
+
+/// Parse verilog number like `42`, `8'hff`, `4'b10x1` or `'d7`
+pub(crate) fn parse_number(text: &str) -> Result<Bits> {
+    let text = text.replace('_', "");
+    let (size, based) = match text.split_once('\'') {
+        Some((size, based)) => (size, based),
+        None => {
+            let value = text
+                .parse::<BigUint>()
+                .map_err(|_| anyhow!("invalid number {}", text))?;
+            // unsized numbers are at least 32 bits
+            return Ok(Bits::from_big(&value, value.bits().max(32)));
+        }
+    };
+    let based = based
+        .strip_prefix(|c: char| c == 's' || c == 'S')
+        .unwrap_or(based);
+    let mut chars = based.chars();
+    let base = chars.next().map(|c| c.to_ascii_lowercase());
+    let digits = chars.as_str();
+    if digits.is_empty() {
+        bail!("invalid number {}", text);
+    }
+    let digit_bits = match base {
+        Some('b') => 1,
+        Some('o') => 3,
+        Some('h') => 4,
+        Some('d') => 0,
+        _ => bail!("invalid base in {}", text),
+    };
+    let mut bits = vec![];
+    if digit_bits == 0 {
+        match digits.to_ascii_lowercase().as_str() {
+            "x" => bits.push(X),
+            "z" | "?" => bits.push(Z),
+            _ => {
+                let value = digits
+                    .parse::<BigUint>()
+                    .map_err(|_| anyhow!("invalid number {}", text))?;
+                bits = Bits::from_big(&value, value.bits().max(1)).to_msb();
+            }
+        }
+    } else {
+        for c in digits.chars() {
+            match c.to_ascii_lowercase() {
+                'x' => bits.extend(vec![X; digit_bits]),
+                'z' | '?' => bits.extend(vec![Z; digit_bits]),
+                c => {
+                    let digit = c
+                        .to_digit(1 << digit_bits)
+                        .ok_or_else(|| anyhow!("invalid digit {} in {}", c, text))?;
+                    bits.extend((0..digit_bits).rev().map(|i| match (digit >> i) & 1 {
+                        1 => V1,
+                        _ => V0,
+                    }));
+                }
+            }
+        }
+    }
+    let width = match size {
+        "" => bits.len().max(32),
+        size => match size.parse::<usize>() {
+            Ok(width) if width > 0 => width,
+            _ => bail!("invalid size in {}", text),
+        },
+    };
+    if bits.len() > width {
+        bits.drain(..(bits.len() - width));
+    } else {
+        // extend with `x` or `z` if the leftmost digit is
+        let pad = match bits[0] {
+            X | Z => bits[0],
+            _ => V0,
+        };
+        bits.splice(0..0, vec![pad; width - bits.len()]);
+    }
+    Ok(Bits::from_msb(bits))
+}
+
+fn lex(text: &str) -> Result<Vec<Token>> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '\'' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || "_'?".contains(chars[i]))
+            {
+                i += 1;
+            }
+            let number = chars[start..i].iter().collect::<String>();
+            tokens.push(Token::Num(parse_number(number.as_str())?));
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || "_$.".contains(chars[i])) {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            let rest = chars[i..].iter().take(3).collect::<String>();
+            let op = TOKEN_OPS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .copied()
+                .ok_or_else(|| anyhow!("unexpected {} at {}", c, i))?;
+            i += op.len();
+            tokens.push(Token::Op(op));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Syntax tree of expression, signals are resolved to ids
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Signal(u64),
+    Const(Bits),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    /// Bits `[msb:lsb]` of value
+    Slice(Box<Expr>, u64, u64),
+    /// Concatenation, first part is the most significant one
+    Concat(Vec<Expr>),
+    /// `cond ? a : b`
+    Cond(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    info: &'a WaveInfo,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+    fn eat(&mut self, op: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Op(o)) if *o == op);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+    fn expect(&mut self, op: &str) -> Result<()> {
+        match self.eat(op) {
+            true => Ok(()),
+            false => match self.peek() {
+                Some(token) => bail!("expect {}, found {}", op, token),
+                None => bail!("expect {} at the end", op),
+            },
+        }
+    }
+    fn parse_expr(&mut self) -> Result<Expr> {
+        let cond = self.parse_binary(1)?;
+        if !self.eat("?") {
+            return Ok(cond);
+        }
+        let a = self.parse_expr()?;
+        self.expect(":")?;
+        let b = self.parse_expr()?;
+        Ok(Expr::Cond(Box::new(cond), Box::new(a), Box::new(b)))
+    }
+    fn parse_binary(&mut self, min_prec: u8) -> Result<Expr> {
+        let mut lhs = self.parse_unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op(op)) => BINARY_OPS.iter().find(|(o, _)| o == op).copied(),
+                _ => None,
+            };
+            match op {
+                Some((op, prec)) if prec >= min_prec => {
+                    self.pos += 1;
+                    let rhs = self.parse_binary(prec + 1)?;
+                    lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+                }
+                _ => return Ok(lhs),
+            }
+        }
+    }
+    fn parse_unary(&mut self) -> Result<Expr> {
+        match self.peek() {
+            Some(Token::Op(op)) if UNARY_OPS.contains(op) => {
+                let op = *op;
+                self.pos += 1;
+                Ok(Expr::Unary(op, Box::new(self.parse_unary()?)))
+            }
+            _ => self.parse_postfix(),
+        }
+    }
+    fn parse_index(&mut self) -> Result<u64> {
+        match self.next() {
+            Some(Token::Num(value)) => value
+                .to_big()
+                .and_then(|v| v.to_u64())
+                .ok_or_else(|| anyhow!("invalid bit index {}", value)),
+            Some(token) => bail!("expect bit index, found {}", token),
+            None => bail!("expect bit index at the end"),
+        }
+    }
+    fn parse_postfix(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_primary()?;
+        while self.eat("[") {
+            let msb = self.parse_index()?;
+            let lsb = match self.eat(":") {
+                true => self.parse_index()?,
+                false => msb,
+            };
+            self.expect("]")?;
+            let width = expr.width(self.info);
+            if msb < lsb || msb >= width {
+                bail!("[{}:{}] is out of {} bits", msb, lsb, width);
+            }
+            expr = Expr::Slice(Box::new(expr), msb, lsb);
+        }
+        Ok(expr)
+    }
+    fn parse_primary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Ident(path)) => Ok(Expr::Signal(resolve_path(self.info, &path)?)),
+            Some(Token::Num(value)) => Ok(Expr::Const(value)),
+            Some(Token::Op("(")) => {
+                let expr = self.parse_expr()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Some(Token::Op("{")) => {
+                let mut parts = vec![self.parse_expr()?];
+                while self.eat(",") {
+                    parts.push(self.parse_expr()?);
+                }
+                self.expect("}")?;
+                Ok(Expr::Concat(parts))
+            }
+            Some(token) => bail!("unexpected {}", token),
+            None => bail!("unexpected end of expression"),
+        }
+    }
+}
+
+/// Parse expression like `valid & ready` or `addr[31:12] == 20'hdead0`,
+/// signal paths are resolved like [resolve_path]
+pub fn parse_expr(text: &str, info: &WaveInfo) -> Result<Expr> {
+    let mut parser = Parser {
+        tokens: lex(text)?,
+        pos: 0,
+        info,
+    };
+    let expr = parser.parse_expr()?;
+    match parser.peek() {
+        Some(token) => bail!("unexpected {}", token),
+        None => Ok(expr),
+    }
+}
+
+fn eval_binary(op: &str, a: Bits, b: Bits) -> Bits {
+    let width = a.width().max(b.width());
+    let bitwise = |f: fn(WireValue, WireValue) -> WireValue| {
+        let (a, b) = (a.clone().resize(width), b.clone().resize(width));
+        Bits(a.0.iter().zip(b.0.iter()).map(|(x, y)| f(*x, *y)).collect())
+    };
+    match op {
+        "&" => return bitwise(bit_and),
+        "|" => return bitwise(bit_or),
+        "^" => return bitwise(bit_xor),
+        "&&" => return Bits(vec![bit_and(a.truth(), b.truth())]),
+        "||" => return Bits(vec![bit_or(a.truth(), b.truth())]),
+        "==" | "!=" => {
+            let eq = logic_eq(&a.resize(width), &b.resize(width));
+            return Bits(vec![if op == "==" { eq } else { bit_not(eq) }]);
+        }
+        "===" => return Bits::from_bool(a.resize(width) == b.resize(width)),
+        "!==" => return Bits::from_bool(a.resize(width) != b.resize(width)),
+        "<<" | ">>" => {
+            let width = a.width() as usize;
+            return match b.to_big().and_then(|n| n.to_usize()) {
+                Some(n) => Bits(
+                    (0..width)
+                        .map(|i| match op {
+                            "<<" => i.checked_sub(n).map(|i| a.0[i]).unwrap_or(V0),
+                            _ => a.0.get(i + n).copied().unwrap_or(V0),
+                        })
+                        .collect(),
+                ),
+                None => Bits::unknown(width as u64),
+            };
+        }
+        _ => {}
+    }
+    let compare = matches!(op, "<" | "<=" | ">" | ">=");
+    let (x, y) = match (a.to_big(), b.to_big()) {
+        (Some(x), Some(y)) => (x, y),
+        _ => return Bits::unknown(if compare { 1 } else { width }),
+    };
+    match op {
+        "<" => Bits::from_bool(x < y),
+        "<=" => Bits::from_bool(x <= y),
+        ">" => Bits::from_bool(x > y),
+        ">=" => Bits::from_bool(x >= y),
+        "+" => Bits::from_big(&(x + y), width),
+        // both are less than 2^width
+        "-" => Bits::from_big(&((x + (BigUint::one() << width)) - y), width),
+        "*" => Bits::from_big(&(x * y), width),
+        "/" | "%" if y.is_zero() => Bits::unknown(width),
+        "/" => Bits::from_big(&(x / y), width),
+        _ => Bits::from_big(&(x % y), width),
+    }
+}
+
+impl Expr {
+    /// Result width in bits
+    pub fn width(&self, info: &WaveInfo) -> u64 {
+        match self {
+            Expr::Signal(id) => signal_width(info, *id),
+            Expr::Const(value) => value.width(),
+            Expr::Unary("~" | "-", e) => e.width(info),
+            Expr::Unary(..) => 1,
+            Expr::Binary("<<" | ">>", a, _) => a.width(info),
+            Expr::Binary(
+                "&&" | "||" | "==" | "!=" | "===" | "!==" | "<" | "<=" | ">" | ">=",
+                ..,
+            ) => 1,
+            Expr::Binary(_, a, b) => a.width(info).max(b.width(info)),
+            Expr::Slice(_, msb, lsb) => msb - lsb + 1,
+            Expr::Concat(parts) => parts.iter().map(|p| p.width(info)).sum(),
+            Expr::Cond(_, a, b) => a.width(info).max(b.width(info)),
+        }
+    }
+    /// Ids of referenced signals, sorted
+    pub fn sources(&self) -> Vec<u64> {
+        fn collect(expr: &Expr, ids: &mut Vec<u64>) {
+            match expr {
+                Expr::Signal(id) => ids.push(*id),
+                Expr::Const(_) => {}
+                Expr::Unary(_, e) | Expr::Slice(e, ..) => collect(e, ids),
+                Expr::Binary(_, a, b) => {
+                    collect(a, ids);
+                    collect(b, ids);
+                }
+                Expr::Concat(parts) => parts.iter().for_each(|p| collect(p, ids)),
+                Expr::Cond(c, a, b) => {
+                    collect(c, ids);
+                    collect(a, ids);
+                    collect(b, ids);
+                }
+            }
+        }
+        let mut ids = vec![];
+        collect(self, &mut ids);
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+    /// Evaluate with current values of signals, missing signals are `x`
+    pub fn eval(&self, values: &HashMap<u64, Bits>, info: &WaveInfo) -> Bits {
+        match self {
+            Expr::Signal(id) => values
+                .get(id)
+                .cloned()
+                .unwrap_or_else(|| Bits::unknown(signal_width(info, *id))),
+            Expr::Const(value) => value.clone(),
+            Expr::Unary(op, e) => {
+                let v = e.eval(values, info);
+                match *op {
+                    "~" => Bits(v.0.iter().map(|b| bit_not(*b)).collect()),
+                    "!" => Bits(vec![bit_not(v.truth())]),
+                    "-" => eval_binary("-", Bits::from_big(&BigUint::zero(), v.width()), v),
+                    "&" => Bits(vec![v.0.iter().copied().fold(V1, bit_and)]),
+                    "|" => Bits(vec![v.0.iter().copied().fold(V0, bit_or)]),
+                    _ => Bits(vec![v.0.iter().copied().fold(V0, bit_xor)]),
+                }
+            }
+            Expr::Binary(op, a, b) => eval_binary(op, a.eval(values, info), b.eval(values, info)),
+            Expr::Slice(e, msb, lsb) => {
+                let v = e.eval(values, info);
+                match v.0.get((*lsb as usize)..=(*msb as usize)) {
+                    Some(bits) => Bits(bits.to_vec()),
+                    None => Bits::unknown(msb - lsb + 1),
+                }
+            }
+            Expr::Concat(parts) => Bits(
+                parts
+                    .iter()
+                    .rev()
+                    .flat_map(|p| p.eval(values, info).0)
+                    .collect(),
+            ),
+            Expr::Cond(c, a, b) => {
+                let width = a.width(info).max(b.width(info));
+                let a = a.eval(values, info).resize(width);
+                let b = b.eval(values, info).resize(width);
+                match c.eval(values, info).truth() {
+                    V1 => a,
+                    V0 => b,
+                    _ => Bits(
+                        a.0.iter()
+                            .zip(b.0.iter())
+                            .map(|(x, y)| match x == y && matches!(x, V0 | V1) {
+                                true => *x,
+                                false => X,
+                            })
+                            .collect(),
+                    ),
+                }
+            }
+        }
+    }
+}
+
+fn push_item(items: &mut Vec<WaveDataItem>, timestamp: u64, value: &Bits) {
+    let item = WaveDataItem {
+        value: WaveDataValue::Raw(value.to_msb()),
+        timestamp,
+    };
+    if let Ok(item) = item.compress() {
+        items.push(item);
+    }
+}
+
+/// Value changes of expression at or after `from`, over merged changes of referenced signals
+pub fn expr_items(store: &WaveStore, info: &WaveInfo, expr: &Expr, from: u64) -> Vec<WaveDataItem> {
+    let sources = expr.sources();
+    let mut items = vec![];
+    if sources.is_empty() {
+        if info.range.0 >= from {
+            push_item(&mut items, info.range.0, &expr.eval(&HashMap::new(), info));
+        }
+        return items;
+    }
+    let mut changes = vec![];
+    for id in &sources {
+        if let Some(data) = store.get(id) {
+            // including the value before `from`
+            let start = data.position(from).saturating_sub(1);
+            changes.extend(
+                data.iter_at(start)
+                    .map(|item| (item.timestamp, *id, item.value)),
+            );
+        }
+    }
+    // stable, changes of the same signal keep order
+    changes.sort_by_key(|(timestamp, _, _)| *timestamp);
+    let mut values = HashMap::new();
+    let mut last = None;
+    let mut changes = changes.into_iter().peekable();
+    while let Some((timestamp, id, value)) = changes.next() {
+        let width = signal_width(info, id);
+        values.insert(id, Bits::from_msb(value_bits(&value, width)));
+        if changes.peek().map(|c| c.0) == Some(timestamp) {
+            continue;
+        }
+        let value = expr.eval(&values, info);
+        if last.as_ref() != Some(&value) {
+            if timestamp >= from {
+                push_item(&mut items, timestamp, &value);
+            }
+            last = Some(value);
+        }
+    }
+    items
+}
+
+#[cfg(test)]
+mod test {
+    use crate::radix::Radix;
+    use crate::wave::derive::test::test_info;
+    use crate::wave::expr::{expr_items, parse_expr, Bits};
+    use crate::wave::store::WaveStoreBuilder;
+    use crate::wave::WireValue::*;
+    use crate::wave::{WaveDataItem, WaveDataValue};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_expr() -> anyhow::Result<()> {
+        let info = test_info(&[(0, "valid", 1), (1, "ready", 1), (2, "addr", 32)]);
+        let eval = |text: &str| -> anyhow::Result<Bits> {
+            Ok(parse_expr(text, &info)?.eval(&HashMap::new(), &info))
+        };
+        assert_eq!(eval("4'b10x1 == 4'b1001")?, Bits(vec![X]));
+        assert_eq!(eval("4'b10x1 == 4'b0001")?, Bits(vec![V0]));
+        assert_eq!(eval("4'b10x1 === 4'b10x1")?, Bits(vec![V1]));
+        assert_eq!(eval("1'b0 & 1'bx")?, Bits(vec![V0]));
+        assert_eq!(
+            eval("{2'b10, 3'd5}")?,
+            Bits::from_msb(vec![V1, V0, V1, V0, V1])
+        );
+        assert_eq!(eval("(8'd3 - 8'd5) >> 4")?.to_big(), Some(0xfu32.into()));
+        assert_eq!(
+            eval("1 + 2 * 3 == 7 ? 4'hf : 4'h0")?.to_big(),
+            Some(0xfu32.into())
+        );
+        assert_eq!(eval("8'hx")?, Bits::unknown(8));
+        assert!(eval("valid &").is_err());
+        assert!(eval("addr[32]").is_err());
+        assert!(eval("unknown_signal").is_err());
+
+        let expr = parse_expr("valid & ready && addr[31:12] == 20'hdead0", &info)?;
+        assert_eq!(expr.sources(), vec![0, 1, 2]);
+        let mut builder = WaveStoreBuilder::default();
+        let mut push = |id, timestamp, value| {
+            builder.push(id, &WaveDataItem { value, timestamp });
+        };
+        push(0, 0, WaveDataValue::Raw(vec![V1]));
+        push(1, 0, WaveDataValue::Raw(vec![X]));
+        push(
+            2,
+            0,
+            WaveDataValue::Comp(0xdead0123u32.to_le_bytes().to_vec()),
+        );
+        push(1, 10, WaveDataValue::Raw(vec![V1]));
+        push(
+            2,
+            20,
+            WaveDataValue::Comp(0xdead0fffu32.to_le_bytes().to_vec()),
+        );
+        push(
+            2,
+            30,
+            WaveDataValue::Comp(0x12345678u32.to_le_bytes().to_vec()),
+        );
+        let store = builder.finish();
+        let values = expr_items(&store, &info, &expr, 0)
+            .iter()
+            .map(|i| (i.timestamp, i.value.as_radix(Radix::Bin)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![
+                (0, "x".to_string()),
+                (10, "1".to_string()),
+                (30, "0".to_string()),
+            ]
+        );
+        let values = expr_items(&store, &info, &expr, 20)
+            .iter()
+            .map(|i| (i.timestamp, i.value.as_radix(Radix::Bin)))
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![(30, "0".to_string())]);
+        Ok(())
+    }
+}
@@ -0,0 +1,325 @@
+use crate::wave::store::WaveStoreBuilder;
+use crate::wave::vcd_parser::scope_hash;
+use crate::wave::WaveDataValue::{Raw, Real, Str};
+use crate::wave::WaveTreeNode::WaveRoot;
+use crate::wave::{
+    Wave, WaveDataItem, WaveInfo, WaveLoader, WaveScopeInfo, WaveScopeType, WaveSignalInfo,
+    WaveSignalType, WaveTimescaleUnit, WaveTreeNode, WireValue,
+};
+use anyhow::{anyhow, Result};
+use fst_reader::{
+    FstFilter, FstHierarchyEntry, FstReader, FstScopeType, FstSignalValue, FstVarType,
+};
+use regex::Regex;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use tracing::info;
+use trees::Tree;
+
+pub struct Fst;
+
+impl From<FstScopeType> for WaveScopeType {
+    fn from(value: FstScopeType) -> Self {
+        match value {
+            FstScopeType::Module | FstScopeType::VhdlArchitecture => Self::Module,
+            FstScopeType::Task | FstScopeType::VhdlProcedure => Self::Task,
+            FstScopeType::Function | FstScopeType::VhdlFunction => Self::Function,
+            FstScopeType::Fork => Self::Fork,
+            FstScopeType::Generate
+            | FstScopeType::VhdlForGenerate
+            | FstScopeType::VhdlIfGenerate
+            | FstScopeType::VhdlGenerate => Self::Generate,
+            FstScopeType::Struct | FstScopeType::VhdlRecord | FstScopeType::SvArray => Self::Struct,
+            FstScopeType::Union => Self::Union,
+            FstScopeType::Class => Self::Class,
+            FstScopeType::Interface => Self::Interface,
+            FstScopeType::Package | FstScopeType::VhdlPackage => Self::Package,
+            FstScopeType::Program => Self::Program,
+            _ => Self::Begin,
+        }
+    }
+}
+
+impl From<FstVarType> for WaveSignalType {
+    fn from(value: FstVarType) -> Self {
+        match value {
+            FstVarType::Event => Self::Event,
+            FstVarType::Integer => Self::Integer,
+            FstVarType::Parameter => Self::Parameter,
+            FstVarType::Real => Self::Real,
+            FstVarType::RealParameter => Self::RealParameter,
+            FstVarType::Reg => Self::Reg,
+            FstVarType::Supply0 => Self::Supply0,
+            FstVarType::Supply1 => Self::Supply1,
+            FstVarType::Time => Self::Time,
+            FstVarType::Tri => Self::Tri,
+            FstVarType::TriAnd => Self::TriAnd,
+            FstVarType::TriOr => Self::TriOr,
+            FstVarType::TriReg => Self::TriReg,
+            FstVarType::Tri0 => Self::Tri0,
+            FstVarType::Tri1 => Self::Tri1,
+            FstVarType::Wand => Self::WAnd,
+            FstVarType::Wire => Self::Wire,
+            FstVarType::Wor => Self::WOr,
+            FstVarType::Port => Self::Port,
+            FstVarType::SparseArray => Self::SparseArray,
+            FstVarType::RealTime => Self::RealTime,
+            FstVarType::GenericString => Self::String,
+            FstVarType::Bit => Self::Bit,
+            FstVarType::Logic => Self::Logic,
+            FstVarType::Int => Self::Int,
+            FstVarType::ShortInt => Self::ShortInt,
+            FstVarType::LongInt => Self::LongInt,
+            FstVarType::Byte => Self::Byte,
+            FstVarType::Enum => Self::Enum,
+            FstVarType::ShortReal => Self::ShortReal,
+        }
+    }
+}
+
+/// Convert fst timescale exponent, e.g. `-9` to `(1, ns)`, `-10` to `(100, ps)`
+pub fn fst_timescale(exponent: i8) -> (u64, WaveTimescaleUnit) {
+    use WaveTimescaleUnit::*;
+    let exponent = exponent.clamp(-15, 2) as i32;
+    let unit_exponent = exponent.div_euclid(3) * 3;
+    let unit = match unit_exponent {
+        e if e >= 0 => S,
+        -3 => MS,
+        -6 => US,
+        -9 => NS,
+        -12 => PS,
+        _ => FS,
+    };
+    let unit_exponent = unit_exponent.min(0);
+    (u64::pow(10, (exponent - unit_exponent) as u32), unit)
+}
+
+/// Convert fst value char to [WireValue], 9-state values are folded into 4 states
+fn fst_wire_value(c: u8) -> WireValue {
+    match c {
+        b'0' | b'l' | b'L' => WireValue::V0,
+        b'1' | b'h' | b'H' => WireValue::V1,
+        b'z' | b'Z' => WireValue::Z,
+        _ => WireValue::X,
+    }
+}
+
+/// Some dumpers append bit range to var name, like `data [7:0]`,
+/// strip it only when the range matches var width, and return it as `(msb, lsb)`
+fn fst_var_name(name: &str, width: u32, re: &Regex) -> (String, Option<(i64, i64)>) {
+    if let Some(cap) = re.captures(name) {
+        let msb = cap.get(2).and_then(|m| m.as_str().parse::<i64>().ok());
+        let lsb = cap.get(3).and_then(|m| m.as_str().parse::<i64>().ok());
+        if let (Some(base), Some(msb), Some(lsb)) = (cap.get(1), msb, lsb) {
+            if (msb - lsb).abs() + 1 == width as i64 {
+                return (base.as_str().to_string(), Some((msb, lsb)));
+            }
+        }
+    }
+    (name.to_string(), None)
+}
+
+/// Scope being built while walking fst hierarchy
+struct FstScopeBuilder {
+    id: u64,
+    tree: Tree<WaveTreeNode>,
+    vars: Vec<Tree<WaveTreeNode>>,
+}
+
+impl FstScopeBuilder {
+    fn new(id: u64, node: WaveTreeNode) -> Self {
+        Self {
+            id,
+            tree: Tree::new(node),
+            vars: vec![],
+        }
+    }
+    /// Append collected vars after scopes, same order as vcd tree
+    fn finish(mut self) -> Tree<WaveTreeNode> {
+        for v in self.vars {
+            self.tree.push_back(v);
+        }
+        self.tree
+    }
+}
+
+impl WaveLoader for Fst {
+    fn load<F>(
+        reader: &mut dyn Read,
+        progress_handler: F,
+        _last_timestamp: Option<u64>,
+    ) -> Result<Wave>
+    where
+        F: Fn(f32, u64),
+    {
+        info!("start parsing fst file");
+        #[cfg(not(target_arch = "wasm32"))]
+        let perf_start = std::time::Instant::now();
+        // fst is block based, needs seeking
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+        let mut parser = FstReader::open(Cursor::new(data))?;
+        let header = parser.get_header();
+        let mut headers: HashMap<String, String> = HashMap::new();
+        if !header.date.is_empty() {
+            headers.insert("date".to_string(), header.date.to_string());
+        }
+        if !header.version.is_empty() {
+            headers.insert("version".to_string(), header.version.to_string());
+        }
+        let timescale = fst_timescale(header.timescale_exponent);
+
+        let re_range = Regex::new("^(.+?)\\s*\\[([0-9]+):([0-9]+)\\]$").unwrap();
+        let mut code_info: HashMap<u64, WaveSignalInfo> = HashMap::new();
+        let mut code_paths: HashMap<u64, Vec<String>> = HashMap::new();
+        let mut code_types: HashMap<u64, FstVarType> = HashMap::new();
+        let mut path: Vec<String> = vec![];
+        let mut scopes: Vec<FstScopeBuilder> = vec![FstScopeBuilder::new(0, WaveRoot)];
+        parser.read_hierarchy(|entry| match entry {
+            FstHierarchyEntry::Scope { tpe, name, .. } => {
+                // same hash as vcd parser, seeded by id of parent scope
+                let seed = match scopes.last() {
+                    Some(s) if scopes.len() > 1 => s.id.wrapping_add(1),
+                    _ => 0,
+                };
+                let id = scope_hash(&name, seed);
+                path.push(name.to_string());
+                scopes.push(FstScopeBuilder::new(
+                    id,
+                    WaveTreeNode::WaveScope(WaveScopeInfo {
+                        id,
+                        name,
+                        typ: tpe.into(),
+                    }),
+                ));
+            }
+            FstHierarchyEntry::UpScope => {
+                if scopes.len() > 1 {
+                    let scope = scopes.pop().unwrap().finish();
+                    if let Some(parent) = scopes.last_mut() {
+                        parent.tree.push_back(scope);
+                    }
+                    path.pop();
+                }
+            }
+            FstHierarchyEntry::Var {
+                tpe,
+                name,
+                length,
+                handle,
+                ..
+            } => {
+                let id = handle.get_index() as u64;
+                let (name, range) = fst_var_name(name.as_str(), length, &re_range);
+                let info = WaveSignalInfo {
+                    id,
+                    name: name.to_string(),
+                    width: if tpe.is_real() { 64 } else { length as u64 },
+                    typ: tpe.into(),
+                    range,
+                };
+                let mut var_path = path.clone();
+                var_path.push(name);
+                code_paths.insert(id, var_path);
+                code_info.insert(id, info.clone());
+                code_types.insert(id, tpe);
+                if let Some(scope) = scopes.last_mut() {
+                    scope.vars.push(Tree::new(WaveTreeNode::WaveVar(info)));
+                }
+            }
+            _ => {}
+        })?;
+        // close scopes not terminated by upscope
+        while scopes.len() > 1 {
+            let scope = scopes.pop().unwrap().finish();
+            if let Some(parent) = scopes.last_mut() {
+                parent.tree.push_back(scope);
+            }
+        }
+        let tree = scopes
+            .pop()
+            .map(|s| s.finish())
+            .ok_or(anyhow!("empty fst hierarchy"))?;
+
+        let mut data = WaveStoreBuilder::default();
+        let (time_start, time_stop) = (header.start_time, header.end_time);
+        let timestamp_skip = time_stop.saturating_sub(time_start) / 1000;
+        let mut timestamp_notified = time_start;
+        parser.read_signals(&FstFilter::all(), |timestamp, handle, value| {
+            let id = handle.get_index() as u64;
+            let value = match value {
+                FstSignalValue::String(v) => match code_types.get(&id) {
+                    Some(FstVarType::GenericString) => {
+                        Some(Str(String::from_utf8_lossy(v).to_string()))
+                    }
+                    _ => Some(Raw(v.iter().map(|c| fst_wire_value(*c)).collect())),
+                },
+                FstSignalValue::Real(v) => Some(Real(v)),
+            };
+            if let Some(value) = value {
+                if let Ok(item) = (WaveDataItem { value, timestamp }).compress() {
+                    data.push(id, &item);
+                }
+            }
+            if timestamp_skip > 0 && timestamp_notified + timestamp_skip < timestamp {
+                let progress = (timestamp - time_start) as f32 / (time_stop - time_start) as f32;
+                progress_handler(progress, timestamp);
+                timestamp_notified = timestamp;
+            }
+        })?;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let perf_stop = std::time::Instant::now();
+            info!("parse fst use time: {:?}", perf_stop - perf_start);
+        }
+        Ok(Wave {
+            info: WaveInfo {
+                timescale,
+                range: (time_start, time_stop),
+                headers,
+                code_signal_info: code_info,
+                code_paths,
+                tree,
+            },
+            data: data.finish(),
+            lazy: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::wave::fst_parser::{fst_timescale, fst_var_name};
+    use crate::wave::WaveTimescaleUnit;
+    use regex::Regex;
+
+    #[test]
+    fn test_fst_timescale() {
+        let check = |exponent: i8, value: u64, unit: &str| {
+            let (v, u) = fst_timescale(exponent);
+            assert_eq!(v, value, "exponent {exponent}");
+            assert_eq!(u.to_string(), unit, "exponent {exponent}");
+        };
+        check(0, 1, "s");
+        check(-3, 1, "ms");
+        check(-9, 1, "ns");
+        check(-10, 100, "ps");
+        check(-11, 10, "ps");
+        check(-12, 1, "ps");
+        check(-15, 1, "fs");
+        check(1, 10, "s");
+        assert!(matches!(fst_timescale(-20).1, WaveTimescaleUnit::FS));
+    }
+
+    #[test]
+    fn test_fst_var_name() {
+        let re = Regex::new("^(.+?)\\s*\\[([0-9]+):([0-9]+)\\]$").unwrap();
+        let name = |name: &str, width: u32| fst_var_name(name, width, &re);
+        assert_eq!(name("data [7:0]", 8), ("data".to_string(), Some((7, 0))));
+        assert_eq!(name("data[31:0]", 32), ("data".to_string(), Some((31, 0))));
+        assert_eq!(name("data[15:8]", 8), ("data".to_string(), Some((15, 8))));
+        assert_eq!(name("data[15:8]", 32), ("data[15:8]".to_string(), None));
+        assert_eq!(name("mem[3]", 8), ("mem[3]".to_string(), None));
+        assert_eq!(name("clk", 1), ("clk".to_string(), None));
+    }
+}
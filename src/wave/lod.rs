@@ -0,0 +1,305 @@
+use crate::wave::store::{SignalData, STORE_CHECKPOINT_INTERVAL};
+use crate::wave::{WaveDataItem, WaveDataValue, WireValue};
+
+/// Summary of a run of value changes: range of values as numbers and unknown bits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LodBucket {
+    pub min: f32,
+    pub max: f32,
+    /// Some value has `x` or `z` bits
+    pub has_x: bool,
+}
+
+impl Default for LodBucket {
+    fn default() -> Self {
+        Self {
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+            has_x: false,
+        }
+    }
+}
+
+impl LodBucket {
+    /// Add a value, vectors are read as unsigned numbers
+    pub fn push(&mut self, value: &WaveDataValue) {
+        let number = match value {
+            WaveDataValue::Comp(v) => Some(v.iter().rev().fold(0.0, |n, b| n * 256.0 + *b as f64)),
+            WaveDataValue::Raw(v) => {
+                match v.iter().any(|b| matches!(b, WireValue::X | WireValue::Z)) {
+                    true => {
+                        self.has_x = true;
+                        None
+                    }
+                    false => Some(v.iter().fold(0.0, |n, b| {
+                        n * 2.0
+                            + match b {
+                                WireValue::V1 => 1.0,
+                                _ => 0.0,
+                            }
+                    })),
+                }
+            }
+            WaveDataValue::Real(v) => Some(*v),
+            WaveDataValue::Str(_) => None,
+        };
+        if let Some(number) = number {
+            self.min = self.min.min(number as f32);
+            self.max = self.max.max(number as f32);
+        }
+    }
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+            has_x: self.has_x || other.has_x,
+        }
+    }
+    /// Min and max of values, `None` if no value is a number
+    pub fn range(&self) -> Option<(f64, f64)> {
+        match self.min <= self.max {
+            true => Some((self.min as f64, self.max as f64)),
+            false => None,
+        }
+    }
+}
+
+/// Multi-resolution summary of value changes of one signal.
+///
+/// Level 0 has a bucket for every [STORE_CHECKPOINT_INTERVAL] changes,
+/// and each higher level merges every two buckets of the level below.
+#[derive(Debug, Clone, Default)]
+pub struct SignalLod {
+    levels: Vec<Vec<LodBucket>>,
+}
+
+impl SignalLod {
+    /// Build higher levels from level 0 buckets
+    pub fn new(mut buckets: Vec<LodBucket>) -> Self {
+        buckets.shrink_to_fit();
+        let mut levels = vec![buckets];
+        while levels[levels.len() - 1].len() > 1 {
+            let level = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| pair.iter().fold(LodBucket::default(), |a, b| a.merge(*b)))
+                .collect();
+            levels.push(level);
+        }
+        Self { levels }
+    }
+    /// Level 0 bucket at `index`
+    pub fn get(&self, index: usize) -> Option<LodBucket> {
+        self.levels.first().and_then(|l| l.get(index)).copied()
+    }
+    /// Replace level 0 bucket at `index`, or push it when `index` is the count of buckets,
+    /// then update buckets above it
+    pub fn set(&mut self, index: usize, bucket: LodBucket) {
+        if self.levels.is_empty() {
+            self.levels.push(vec![]);
+        }
+        let mut index = index;
+        let mut bucket = bucket;
+        let mut level = 0;
+        loop {
+            if level == self.levels.len() {
+                self.levels.push(vec![]);
+            }
+            let buckets = &mut self.levels[level];
+            match index < buckets.len() {
+                true => buckets[index] = bucket,
+                false => buckets.push(bucket),
+            }
+            if buckets.len() <= 1 {
+                break;
+            }
+            let pair = (index & !1)..((index & !1) + 2).min(buckets.len());
+            bucket = buckets[pair]
+                .iter()
+                .fold(LodBucket::default(), |a, b| a.merge(*b));
+            index /= 2;
+            level += 1;
+        }
+    }
+    /// Keep first `len` level 0 buckets, then update buckets above them
+    pub fn truncate(&mut self, len: usize) {
+        let mut level_len = len;
+        for level in self.levels.iter_mut() {
+            level.truncate(level_len);
+            level_len = (level_len + 1) / 2;
+        }
+        while self.levels.len() > 1 && self.levels[self.levels.len() - 2].len() <= 1 {
+            self.levels.pop();
+        }
+        if let Some(bucket) = len.checked_sub(1).and_then(|index| self.get(index)) {
+            self.set(len - 1, bucket);
+        }
+    }
+    /// Summary of changes from index `start` to `end`, in whole level 0 buckets,
+    /// so nearby changes outside may be included
+    pub fn summary(&self, start: usize, end: usize) -> LodBucket {
+        let mut summary = LodBucket::default();
+        if end <= start {
+            return summary;
+        }
+        let mut bucket = start / STORE_CHECKPOINT_INTERVAL;
+        let end = ((end - 1) / STORE_CHECKPOINT_INTERVAL + 1)
+            .min(self.levels.first().map_or(0, |l| l.len()));
+        while bucket < end {
+            // largest aligned bucket inside range
+            let mut level = 0;
+            while level + 1 < self.levels.len()
+                && bucket % (2 << level) == 0
+                && bucket + (2 << level) <= end
+            {
+                level += 1;
+            }
+            summary = summary.merge(self.levels[level][bucket >> level]);
+            bucket += 1 << level;
+        }
+        summary
+    }
+    /// Approximate heap memory used in bytes
+    pub fn memory_size(&self) -> usize {
+        self.levels
+            .iter()
+            .map(|l| l.capacity() * std::mem::size_of::<LodBucket>())
+            .sum()
+    }
+}
+
+/// A value change to paint
+#[derive(Debug, Clone)]
+pub struct PaintChange {
+    pub item: WaveDataItem,
+    /// Summary of changes until next painted change,
+    /// when they are too dense to paint one by one
+    pub busy: Option<LodBucket>,
+}
+
+/// Prepare value changes of signal to paint in view from `start` to `end`.
+///
+/// Changes closer than `min_span` to the previous one are merged into a busy change
+/// spanning `min_span`, so painting costs by view width instead of count of changes.
+pub fn paint_changes(data: &SignalData, start: u64, end: u64, min_span: f64) -> Vec<PaintChange> {
+    let mut changes: Vec<PaintChange> = vec![];
+    if data.is_empty() {
+        return changes;
+    }
+    let mut index = data.position(start).min(data.len() - 1);
+    let mut iter = data.iter_at(index);
+    let mut last_timestamp = None;
+    while let Some(item) = iter.next() {
+        let item_index = index;
+        index += 1;
+        let timestamp = item.timestamp;
+        let dense = match last_timestamp {
+            Some(last) => ((timestamp - last) as f64) < min_span,
+            None => false,
+        };
+        changes.push(PaintChange { item, busy: None });
+        if timestamp > end {
+            break;
+        }
+        if dense {
+            // jump to last change in span, skipping changes between
+            let span_end = data.position((timestamp as f64 + min_span).ceil() as u64);
+            if span_end > index + 1 {
+                if let Some(change) = changes.last_mut() {
+                    change.busy = Some(data.summary(item_index, span_end));
+                }
+                index = span_end - 1;
+                iter = data.iter_at(index);
+            }
+        }
+        last_timestamp = Some(timestamp);
+    }
+    changes
+}
+
+#[cfg(test)]
+mod test {
+    use crate::wave::lod::{paint_changes, LodBucket, SignalLod};
+    use crate::wave::store::{WaveStoreBuilder, STORE_CHECKPOINT_INTERVAL};
+    use crate::wave::{WaveDataItem, WaveDataValue, WireValue};
+
+    #[test]
+    fn test_lod() {
+        let mut bucket = LodBucket::default();
+        assert_eq!(bucket.range(), None);
+        bucket.push(&WaveDataValue::Comp(vec![0x00, 0x01]));
+        bucket.push(&WaveDataValue::Raw(vec![WireValue::V1, WireValue::V0]));
+        assert_eq!(bucket.range(), Some((2.0, 256.0)));
+        assert!(!bucket.has_x);
+        bucket.push(&WaveDataValue::Raw(vec![WireValue::Z]));
+        assert!(bucket.has_x);
+
+        let buckets = (0..5)
+            .map(|i| LodBucket {
+                min: i as f32,
+                max: i as f32 + 0.5,
+                has_x: i == 3,
+            })
+            .collect::<Vec<_>>();
+        let lod = SignalLod::new(buckets);
+        let n = STORE_CHECKPOINT_INTERVAL;
+        assert_eq!(lod.summary(0, 5 * n).range(), Some((0.0, 4.5)));
+        assert_eq!(lod.summary(n, 3 * n).range(), Some((1.0, 2.5)));
+        assert!(!lod.summary(n, 3 * n).has_x);
+        assert!(lod.summary(3 * n + 1, 3 * n + 2).has_x);
+        assert_eq!(lod.summary(n + 1, 2 * n + 1).range(), Some((1.0, 2.5)));
+        assert_eq!(lod.summary(2 * n, 2 * n), LodBucket::default());
+        assert_eq!(lod.summary(4 * n, 100 * n).range(), Some((4.0, 4.5)));
+        // pushing and replacing buckets one by one
+        let mut pushed = SignalLod::default();
+        for (index, bucket) in lod.levels[0].iter().enumerate() {
+            pushed.set(index, LodBucket::default());
+            pushed.set(index, *bucket);
+        }
+        assert_eq!(pushed.levels, lod.levels);
+        // truncating to buckets built at once
+        for len in (0..5).rev() {
+            let mut truncated = lod.clone();
+            truncated.truncate(len);
+            assert_eq!(
+                truncated.levels,
+                SignalLod::new(lod.levels[0][..len].to_vec()).levels
+            );
+        }
+    }
+
+    #[test]
+    fn test_paint_changes() {
+        // dense changes from 1000 to 2000, sparse after
+        let mut builder = WaveStoreBuilder::default();
+        let timestamps = (0..1000)
+            .map(|i| 1000 + i)
+            .chain((0..10).map(|i| 3000 + i * 100));
+        for (i, timestamp) in timestamps.enumerate() {
+            let value = match i {
+                500 => WaveDataValue::Raw(vec![WireValue::X]),
+                _ => WaveDataValue::Comp(vec![(i % 2) as u8]),
+            };
+            builder.push(0, &WaveDataItem { value, timestamp });
+        }
+        let store = builder.finish();
+        let data = store.get(&0).unwrap();
+        let changes = paint_changes(&data, 0, 4000, 0.0);
+        assert_eq!(changes.len(), data.len());
+        assert!(changes.iter().all(|c| c.busy.is_none()));
+        let changes = paint_changes(&data, 0, 4000, 10.0);
+        assert!(changes.len() < 150);
+        let busy = changes.iter().filter_map(|c| c.busy).collect::<Vec<_>>();
+        assert!(busy.iter().any(|b| b.has_x));
+        assert!(busy.iter().all(|b| b.range() == Some((0.0, 1.0))));
+        // painted changes keep order and the last dense value
+        assert!(changes
+            .windows(2)
+            .all(|w| w[0].item.timestamp < w[1].item.timestamp));
+        assert!(changes.iter().any(|c| c.item.timestamp == 1999));
+        assert_eq!(changes.last().unwrap().item.timestamp, 3900);
+        // view in the middle starts from first change in view
+        let changes = paint_changes(&data, 3150, 3350, 10.0);
+        assert_eq!(changes[0].item.timestamp, 3200);
+        assert_eq!(changes.last().unwrap().item.timestamp, 3400);
+    }
+}
@@ -1,4 +1,6 @@
 use crate::radix::{radix_value_big_uint, radix_vector_to_string, Radix};
+use crate::wave::derive::value_bits;
+use crate::wave::store::WaveStore;
 use anyhow::{anyhow, Result};
 use num_bigint::BigUint;
 use serde::{Deserialize, Serialize};
@@ -6,10 +8,18 @@ use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::io::Read;
 use trees::Tree;
-use vcd::{IdCode, Scope, ScopeType, Var, VarType};
+use vcd::{IdCode, ReferenceIndex, Scope, ScopeType, Var, VarType};
 
+pub mod derive;
+pub mod expr;
+pub mod fst_parser;
+pub mod lod;
+pub mod search;
+pub mod store;
 pub mod utils;
+pub mod vcd_index;
 pub mod vcd_parser;
+pub mod vcd_writer;
 
 /// like [vcd::Value], basically for (de)serialize
 #[derive(Default, Serialize, Deserialize, Ord, PartialOrd, Eq, PartialEq, Copy, Clone, Debug)]
@@ -81,6 +91,10 @@ pub enum WaveDataValue {
     /// when vec empty, invalid
     Comp(Vec<u8>),
     Raw(Vec<WireValue>),
+    /// value of `real` signals
+    Real(f64),
+    /// value of `string` signals
+    Str(String),
 }
 
 impl From<&WaveDataValue> for Option<BigUint> {
@@ -93,15 +107,40 @@ impl From<&WaveDataValue> for Option<BigUint> {
 }
 
 impl WaveDataValue {
-    /// to string in radix
+    /// to string in radix, width of compressed values is unknown here,
+    /// so use [WaveDataValue::as_radix_width] for signed, fixed-point and float radixes
     pub fn as_radix(&self, radix: Radix) -> String {
         match self {
             WaveDataValue::Comp(v) => {
-                BigUint::from_bytes_le(v).to_str_radix(radix.to_number() as u32)
+                debug_assert!(radix.to_number().is_some(), "{radix} needs signal width");
+                match radix.to_number() {
+                    Some(n) => BigUint::from_bytes_le(v).to_str_radix(n as u32),
+                    None => self.as_radix_width(radix, v.len() as u64 * 8),
+                }
+            }
+            // raw bits are most significant first
+            WaveDataValue::Raw(v) => {
+                radix_vector_to_string(radix, &v.iter().rev().copied().collect())
+            }
+            WaveDataValue::Real(v) => v.to_string(),
+            WaveDataValue::Str(v) => v.to_string(),
+        }
+    }
+    /// to string in radix, signed, fixed-point and float radixes use `width` bits of signal
+    pub fn as_radix_width(&self, radix: Radix, width: u64) -> String {
+        match (self, radix.to_number()) {
+            (WaveDataValue::Comp(_) | WaveDataValue::Raw(_), None) => {
+                let mut bits = value_bits(self, width);
+                bits.reverse();
+                radix_vector_to_string(radix, &bits)
             }
-            WaveDataValue::Raw(v) => radix_vector_to_string(radix, v),
+            _ => self.as_radix(radix),
         }
     }
+    /// whether this value is a bit vector
+    pub fn is_bits(&self) -> bool {
+        matches!(self, WaveDataValue::Comp(_) | WaveDataValue::Raw(_))
+    }
 }
 
 impl Display for WaveDataValue {
@@ -132,16 +171,16 @@ impl Display for WaveDataItem {
 
 impl WaveDataItem {
     /// Compress [WaveDataItem], may change `Raw(_)` to `Comp(_)`
-    pub(crate) fn compress(self) -> Result<Self> {
+    pub fn compress(self) -> Result<Self> {
         if match &self.value {
             WaveDataValue::Comp(v) => v.len(),
             WaveDataValue::Raw(v) => v.len(),
+            WaveDataValue::Real(_) | WaveDataValue::Str(_) => return Ok(self),
         } == 0
         {
             return Err(anyhow!("compressing invalid data!"));
         }
         match &self.value {
-            WaveDataValue::Comp(_) => Ok(self),
             WaveDataValue::Raw(v) => {
                 let rev_v = v.iter().rev().map(|x| x.clone()).collect::<Vec<_>>();
                 let ability = !v.iter().any(|i| i == &WireValue::X || i == &WireValue::Z);
@@ -152,6 +191,7 @@ impl WaveDataItem {
                     Ok(self)
                 }
             }
+            _ => Ok(self),
         }
     }
 }
@@ -177,6 +217,18 @@ pub enum WaveSignalType {
     Wire,
     WOr,
     String,
+    Port,
+    SparseArray,
+    RealParameter,
+    RealTime,
+    ShortReal,
+    Bit,
+    Logic,
+    Int,
+    ShortInt,
+    LongInt,
+    Byte,
+    Enum,
 }
 impl Display for WaveSignalType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -216,6 +268,13 @@ pub enum WaveScopeType {
     Function,
     Begin,
     Fork,
+    Generate,
+    Struct,
+    Union,
+    Class,
+    Interface,
+    Package,
+    Program,
 }
 impl From<ScopeType> for WaveScopeType {
     fn from(value: ScopeType) -> Self {
@@ -261,6 +320,9 @@ pub struct WaveSignalInfo {
     pub name: String,
     pub width: u64,
     pub typ: WaveSignalType,
+    /// Declared bit range as `(msb, lsb)`, when it is known
+    #[serde(default)]
+    pub range: Option<(i64, i64)>,
 }
 impl Display for WaveSignalInfo {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -282,6 +344,10 @@ impl From<&Var> for WaveSignalInfo {
             name: value.reference.to_string(),
             width: value.size.into(),
             typ: value.var_type.into(),
+            range: value.index.as_ref().map(|index| match *index {
+                ReferenceIndex::BitSelect(bit) => (bit.into(), bit.into()),
+                ReferenceIndex::Range(msb, lsb) => (msb.into(), lsb.into()),
+            }),
         }
     }
 }
@@ -330,7 +396,9 @@ pub struct WaveInfo {
 #[derive(Clone)]
 pub struct Wave {
     pub info: WaveInfo,
-    pub data: HashMap<u64, Vec<WaveDataItem>>,
+    pub data: WaveStore,
+    /// Signal data is loaded on demand, `data` only contains requested signals
+    pub lazy: bool,
 }
 
 impl Display for WaveInfo {
@@ -343,29 +411,89 @@ impl Display for WaveInfo {
     }
 }
 
+impl Default for WaveInfo {
+    fn default() -> Self {
+        Self {
+            timescale: (1, Default::default()),
+            range: (0, 0),
+            headers: Default::default(),
+            code_signal_info: Default::default(),
+            code_paths: Default::default(),
+            tree: Tree::new(WaveTreeNode::WaveRoot),
+        }
+    }
+}
+
 impl Display for Wave {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         self.info.fmt(f)
     }
 }
 
+impl WaveInfo {
+    /// Find signal ids whose paths end with `pattern` components, sorted by path
+    pub fn match_paths(&self, pattern: &str) -> Vec<u64> {
+        let pattern = pattern
+            .split(['.', '/'])
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        let mut matches = self
+            .code_paths
+            .iter()
+            .filter(|(_, path)| {
+                path.len() >= pattern.len()
+                    && path[(path.len() - pattern.len())..]
+                        .iter()
+                        .zip(pattern.iter())
+                        .all(|(a, b)| a == b)
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|a, b| a.1.cmp(b.1));
+        matches.into_iter().map(|(id, _)| *id).collect()
+    }
+}
+
 impl Wave {
     /// Find *nearest* value
     pub fn find_value(&self, id: u64, pos: u64) -> Option<WaveDataItem> {
-        // assert: pos keeps increase
-        if let Some(data) = self.data.get(&id) {
-            match data.binary_search_by_key(&pos, |x| x.timestamp) {
-                Ok(index) => {
-                    if data[index].timestamp == pos {
-                        Some(data[index].clone())
-                    } else {
-                        data.get(index - 1).cloned()
-                    }
-                }
-                Err(index) => data.get(index - 1).cloned(),
-            }
+        self.data.get(&id).and_then(|data| data.find(pos))
+    }
+}
+
+/// Supported wave file formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaveFormat {
+    Vcd,
+    Fst,
+}
+
+impl WaveFormat {
+    /// Detect file format by file magic, fallback to file extension
+    ///
+    /// * `data`: whole file data
+    /// * `filename`: file name or path, optional
+    pub fn detect(data: &[u8], filename: Option<&str>) -> Self {
+        if fst_reader::is_fst_file(&mut std::io::Cursor::new(data)) {
+            return WaveFormat::Fst;
+        }
+        match filename
+            .and_then(|f| f.rsplit('.').next())
+            .map(|ext| ext.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("fst") => WaveFormat::Fst,
+            _ => WaveFormat::Vcd,
+        }
+    }
+    /// Detect format of file on disk, only reads file header
+    pub fn detect_file(path: &str) -> Self {
+        let is_fst = std::fs::File::open(path)
+            .map(|file| fst_reader::is_fst_file(&mut std::io::BufReader::new(file)))
+            .unwrap_or(false);
+        if is_fst {
+            WaveFormat::Fst
         } else {
-            None
+            Self::detect(&[], Some(path))
         }
     }
 }
@@ -392,8 +520,9 @@ pub trait WavePreLoader {
 
 #[cfg(test)]
 mod test {
+    use crate::radix::Radix;
     use crate::wave::vcd_parser::Vcd;
-    use crate::wave::WaveLoader;
+    use crate::wave::{WaveDataItem, WaveDataValue, WaveLoader, WireValue};
     use std::fs::File;
     // use trees::Node;
     use crate::wave::utils::Node;
@@ -427,4 +556,50 @@ mod test {
         println!("{}", serde_json::to_string(&Node(tree.root())).unwrap());
         Ok(())
     }
+
+    #[test]
+    fn test_real_string_value() -> anyhow::Result<()> {
+        let item = WaveDataItem {
+            value: WaveDataValue::Real(1.5),
+            timestamp: 0,
+        }
+        .compress()?;
+        assert!(matches!(item.value, WaveDataValue::Real(v) if v == 1.5));
+        assert_eq!(item.value.as_radix(Radix::Hex), "1.5");
+        let item = WaveDataItem {
+            value: WaveDataValue::Str("IDLE".to_string()),
+            timestamp: 0,
+        }
+        .compress()?;
+        assert!(!item.value.is_bits());
+        assert_eq!(item.value.to_string(), "IDLE");
+        Ok(())
+    }
+
+    #[test]
+    fn test_radix_width() {
+        let value = WaveDataValue::Comp(vec![0xfe]);
+        assert_eq!(value.as_radix(Radix::Dec), "254");
+        assert_eq!(value.as_radix_width(Radix::SignedDec, 8), "-2");
+        assert_eq!(value.as_radix_width(Radix::SignedDec, 4), "-2");
+        assert_eq!(value.as_radix_width(Radix::SignedDec, 12), "254");
+        let value = WaveDataValue::Raw(vec![WireValue::V1, WireValue::V1, WireValue::V0]);
+        assert_eq!(value.as_radix_width(Radix::SignedDec, 3), "-2");
+        assert_eq!(value.as_radix_width(Radix::Dec, 3), "6");
+        let value = WaveDataValue::Raw(vec![WireValue::V1, WireValue::X]);
+        assert_eq!(value.as_radix(Radix::Bin), "1x");
+        assert_eq!(value.as_radix_width(Radix::SignedDec, 2), "x");
+    }
+
+    #[test]
+    fn test_raw_bit_order() {
+        use WireValue::*;
+        // raw values from parsers are most significant bit first
+        let value = WaveDataValue::Raw(vec![V1, V0, V1, V0, V0, V0, V0, V1]);
+        assert_eq!(value.as_radix(Radix::Bin), "10100001");
+        assert_eq!(value.as_radix(Radix::Hex), "a1");
+        assert_eq!(value.as_radix(Radix::Dec), "161");
+        let value = WaveDataValue::Raw(vec![V1, V0, X, Z]);
+        assert_eq!(value.as_radix(Radix::Bin), "10xz");
+    }
 }
@@ -0,0 +1,479 @@
+use crate::radix::{f32_to_half, vector_radix_number, Radix};
+use crate::wave::derive::value_bits;
+use crate::wave::expr::{parse_number, Bits};
+use crate::wave::store::{SignalData, STORE_CHECKPOINT_INTERVAL};
+use crate::wave::{WaveDataValue, WireValue};
+use anyhow::{anyhow, bail, Result};
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::FromPrimitive;
+use std::cmp::Ordering;
+
+/// Comparison in value search
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl SearchOp {
+    fn test(&self, ordering: Option<Ordering>) -> bool {
+        match ordering {
+            Some(ordering) => match self {
+                SearchOp::Eq => ordering == Ordering::Equal,
+                SearchOp::Ne => ordering != Ordering::Equal,
+                SearchOp::Lt => ordering == Ordering::Less,
+                SearchOp::Le => ordering != Ordering::Greater,
+                SearchOp::Gt => ordering == Ordering::Greater,
+                SearchOp::Ge => ordering != Ordering::Less,
+            },
+            None => false,
+        }
+    }
+}
+
+/// Value to search in a signal, like `1f`, `> 0x100`, `4'b1x0x`, `x` or `z`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueQuery {
+    /// Value has `x` bits
+    Unknown,
+    /// Value has `z` bits
+    HighZ,
+    /// Compare with number, `x` and `z` bits of number match any bit in `==` and `!=`,
+    /// order is compared in signed, fixed-point and float radix like values are shown
+    Number(SearchOp, Bits, Radix),
+    /// Compare value of real or string signal
+    Text(SearchOp, String),
+}
+
+impl ValueQuery {
+    /// Parse query text, numbers without prefix like `0x` or `8'h` are in `radix`
+    /// * `bits`: whether the searched signal is a bit vector
+    /// * `width`: width of the searched signal
+    pub fn parse(text: &str, radix: Radix, bits: bool, width: u64) -> Result<Self> {
+        let text = text.trim();
+        let (op, value) = [
+            ("==", SearchOp::Eq),
+            ("!=", SearchOp::Ne),
+            ("<=", SearchOp::Le),
+            (">=", SearchOp::Ge),
+            ("=", SearchOp::Eq),
+            ("<", SearchOp::Lt),
+            (">", SearchOp::Gt),
+        ]
+        .into_iter()
+        .find_map(|(prefix, op)| text.strip_prefix(prefix).map(|v| (op, v.trim())))
+        .unwrap_or((SearchOp::Eq, text));
+        if value.is_empty() {
+            bail!("empty value in {}", text);
+        }
+        if !bits {
+            return Ok(ValueQuery::Text(op, value.to_string()));
+        }
+        match (op, value) {
+            (SearchOp::Eq, "x" | "X") => return Ok(ValueQuery::Unknown),
+            (SearchOp::Eq, "z" | "Z") => return Ok(ValueQuery::HighZ),
+            _ => {}
+        }
+        let number = parse_query_number(value, radix.clone(), Some(width))?;
+        if number
+            .0
+            .iter()
+            .any(|b| matches!(b, WireValue::X | WireValue::Z))
+            && !matches!(op, SearchOp::Eq | SearchOp::Ne)
+        {
+            bail!("cannot compare order with x or z in {}", text);
+        }
+        Ok(ValueQuery::Number(op, number, radix))
+    }
+    /// Whether `value` of signal in `width` bits matches
+    pub fn matches(&self, value: &WaveDataValue, width: u64) -> bool {
+        match (self, value) {
+            (ValueQuery::Text(op, text), WaveDataValue::Real(v)) => {
+                op.test(text.parse::<f64>().ok().and_then(|t| v.partial_cmp(&t)))
+            }
+            (ValueQuery::Text(op, text), WaveDataValue::Str(v)) => {
+                op.test(Some(v.as_str().cmp(text.as_str())))
+            }
+            (ValueQuery::Text(..), _) | (_, WaveDataValue::Real(_) | WaveDataValue::Str(_)) => {
+                false
+            }
+            (ValueQuery::Unknown, value) => value_bits(value, width).contains(&WireValue::X),
+            (ValueQuery::HighZ, value) => value_bits(value, width).contains(&WireValue::Z),
+            (ValueQuery::Number(op, number, radix), value) => {
+                let bits = Bits::from_msb(value_bits(value, width));
+                let ordered = !matches!(op, SearchOp::Eq | SearchOp::Ne);
+                match number.to_big() {
+                    Some(_) if ordered && radix.to_number().is_none() => {
+                        let mut target = number.0.clone();
+                        target.resize(bits.0.len(), WireValue::V0);
+                        let target = vector_radix_number(&target, radix);
+                        let value = vector_radix_number(&bits.0, radix);
+                        op.test(value.zip(target).and_then(|(v, t)| v.partial_cmp(&t)))
+                    }
+                    Some(target) => op.test(bits.to_big().map(|v| v.cmp(&target))),
+                    None => {
+                        let bit = |b: &Bits, i: usize| b.0.get(i).copied().unwrap_or(WireValue::V0);
+                        let equal = (0..bits.0.len().max(number.0.len())).all(|i| {
+                            match (bit(number, i), bit(&bits, i)) {
+                                (WireValue::X | WireValue::Z, _) => true,
+                                (a, b) => a == b,
+                            }
+                        });
+                        match op {
+                            SearchOp::Eq => equal,
+                            _ => !equal,
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Transitions to stop at in edge navigation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeKind {
+    /// Initial value, or change to a different value
+    Any,
+    /// `0` to `1`, changes from `x` or `z` are not edges
+    Rising,
+    /// `1` to `0`, changes from `x` or `z` are not edges
+    Falling,
+}
+
+impl EdgeKind {
+    /// Whether change from `prev` to `value` is this edge, `prev` is `None` for initial value,
+    /// rising and falling edges are in 1-bit signals only
+    pub fn matches(&self, prev: Option<&WaveDataValue>, value: &WaveDataValue, width: u64) -> bool {
+        let bit = |v: &WaveDataValue| value_bits(v, 1).first().copied();
+        match (self, prev) {
+            (EdgeKind::Any, None) => true,
+            (EdgeKind::Any, Some(prev)) => match prev.is_bits() && value.is_bits() {
+                true => value_bits(prev, width) != value_bits(value, width),
+                false => prev.to_string() != value.to_string(),
+            },
+            (_, None) => false,
+            (EdgeKind::Rising, Some(prev)) => {
+                width == 1 && bit(prev) == Some(WireValue::V0) && bit(value) == Some(WireValue::V1)
+            }
+            (EdgeKind::Falling, Some(prev)) => {
+                width == 1 && bit(prev) == Some(WireValue::V1) && bit(value) == Some(WireValue::V0)
+            }
+        }
+    }
+}
+
+/// Number in `radix`, or with prefix `0x`, `0o`, `0b`, `0d`, or verilog literal like `8'hff`,
+/// numbers in signed, fixed-point, float and ASCII radixes need `width` of signal
+pub(crate) fn parse_query_number(text: &str, radix: Radix, width: Option<u64>) -> Result<Bits> {
+    if text.contains('\'') {
+        return parse_number(text);
+    }
+    let lower = text.to_ascii_lowercase();
+    let (base, digits) = match (lower.get(..2), lower.get(2..)) {
+        (Some("0x"), Some(digits)) => ('h', digits),
+        (Some("0o"), Some(digits)) => ('o', digits),
+        (Some("0b"), Some(digits)) => ('b', digits),
+        (Some("0d"), Some(digits)) => ('d', digits),
+        _ => match radix {
+            Radix::Bin => ('b', lower.as_str()),
+            Radix::Oct => ('o', lower.as_str()),
+            Radix::Dec => ('d', lower.as_str()),
+            Radix::Hex => ('h', lower.as_str()),
+            radix => {
+                let width = width.ok_or_else(|| anyhow!("{} number needs signal width", radix))?;
+                return parse_radix_number(text, radix, width);
+            }
+        },
+    };
+    parse_number(&format!("'{}{}", base, digits))
+}
+
+/// Bits of number shown in signed, fixed-point, float or ASCII `radix` in `width` bits,
+/// numbers out of range or not exactly representable are rejected
+fn parse_radix_number(text: &str, radix: Radix, width: u64) -> Result<Bits> {
+    let invalid = || anyhow!("invalid {} number {}", radix, text);
+    let float = || text.parse::<f64>().map_err(|_| invalid());
+    let (value, width, signed) = match radix {
+        Radix::Fixed { int, frac, signed } => {
+            // multiple of fraction step only
+            let value = float()? * 2f64.powi(frac as i32);
+            let width = int.map_or(width, |int| width.min(int as u64 + frac as u64));
+            let value = (value.fract() == 0.0).then(|| BigInt::from_f64(value));
+            (value.flatten(), width, signed)
+        }
+        Radix::Half => {
+            let value = text.parse::<f32>().map_err(|_| invalid())?;
+            (f32_to_half(value).map(BigInt::from), 16, false)
+        }
+        Radix::Float => {
+            let value = text.parse::<f32>().map_err(|_| invalid())?;
+            (
+                Some(BigInt::from(value.to_bits())).filter(|_| !value.is_nan()),
+                32,
+                false,
+            )
+        }
+        Radix::Double => {
+            let value = float()?;
+            (
+                Some(BigInt::from(value.to_bits())).filter(|_| !value.is_nan()),
+                64,
+                false,
+            )
+        }
+        Radix::Ascii => {
+            let bytes = text
+                .bytes()
+                .fold(BigUint::default(), |acc, c| acc << 8u8 | BigUint::from(c));
+            (text.is_ascii().then(|| BigInt::from(bytes)), width, false)
+        }
+        _ => (text.parse::<BigInt>().ok(), width, true),
+    };
+    let value = value.ok_or_else(invalid)?;
+    let modulus = BigInt::from(1u8) << width;
+    let range = match signed {
+        true => -(&modulus >> 1u8)..(&modulus >> 1u8),
+        false => BigInt::default()..modulus.clone(),
+    };
+    if !range.contains(&value) {
+        bail!("{} out of range of {} bits", text, width);
+    }
+    let value = match value.sign() {
+        Sign::Minus => value + modulus,
+        _ => value,
+    };
+    Ok(Bits::from_big(value.magnitude(), width))
+}
+
+/// Time of first change after `pos` with value matching `f`
+pub fn search_next(data: &SignalData, pos: u64, f: impl Fn(&WaveDataValue) -> bool) -> Option<u64> {
+    data.iter_at(data.position(pos.saturating_add(1)))
+        .find(|item| f(&item.value))
+        .map(|item| item.timestamp)
+}
+
+/// Time of last change before `pos` with value matching `f`,
+/// searching backward checkpoint by checkpoint
+pub fn search_prev(data: &SignalData, pos: u64, f: impl Fn(&WaveDataValue) -> bool) -> Option<u64> {
+    let mut end = data.position(pos);
+    while end > 0 {
+        let start = (end - 1) / STORE_CHECKPOINT_INTERVAL * STORE_CHECKPOINT_INTERVAL;
+        let found = data
+            .iter_at(start)
+            .take(end - start)
+            .filter(|item| f(&item.value))
+            .last();
+        if let Some(item) = found {
+            return Some(item.timestamp);
+        }
+        end = start;
+    }
+    None
+}
+
+/// Time of first `edge` after `pos` in signal of `width` bits
+pub fn search_next_edge(data: &SignalData, pos: u64, edge: EdgeKind, width: u64) -> Option<u64> {
+    let index = data.position(pos.saturating_add(1));
+    let mut prev = match index {
+        0 => None,
+        index => data.iter_at(index - 1).next().map(|item| item.value),
+    };
+    for item in data.iter_at(index) {
+        if edge.matches(prev.as_ref(), &item.value, width) {
+            return Some(item.timestamp);
+        }
+        prev = Some(item.value);
+    }
+    None
+}
+
+/// Time of last `edge` before `pos` in signal of `width` bits,
+/// searching backward checkpoint by checkpoint
+pub fn search_prev_edge(data: &SignalData, pos: u64, edge: EdgeKind, width: u64) -> Option<u64> {
+    let mut end = data.position(pos);
+    while end > 0 {
+        let start = (end - 1) / STORE_CHECKPOINT_INTERVAL * STORE_CHECKPOINT_INTERVAL;
+        // previous value of first change in range is the last one of range before
+        let mut iter = data.iter_at(start.saturating_sub(1));
+        let mut prev = match start {
+            0 => None,
+            _ => iter.next().map(|item| item.value),
+        };
+        let mut found = None;
+        for item in iter.take(end - start) {
+            if edge.matches(prev.as_ref(), &item.value, width) {
+                found = Some(item.timestamp);
+            }
+            prev = Some(item.value);
+        }
+        if found.is_some() {
+            return found;
+        }
+        end = start;
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use crate::radix::Radix;
+    use crate::wave::search::{
+        search_next, search_next_edge, search_prev, search_prev_edge, EdgeKind, ValueQuery,
+    };
+    use crate::wave::store::WaveStoreBuilder;
+    use crate::wave::WireValue::*;
+    use crate::wave::{WaveDataItem, WaveDataValue};
+
+    #[test]
+    fn test_search() -> anyhow::Result<()> {
+        let mut builder = WaveStoreBuilder::default();
+        // 0, 1, .. 99 at 0, 10, .. 990, then x and z
+        for i in 0..100u64 {
+            let item = WaveDataItem {
+                value: WaveDataValue::Comp(vec![i as u8]),
+                timestamp: i * 10,
+            };
+            builder.push(0, &item);
+        }
+        for (timestamp, bit) in [(1000, X), (1010, Z)] {
+            let item = WaveDataItem {
+                value: WaveDataValue::Raw(vec![V0, V0, V0, V0, V0, V0, V1, bit]),
+                timestamp,
+            };
+            builder.push(0, &item);
+        }
+        let store = builder.finish();
+        let data = store.get(&0).unwrap();
+        let find = |text: &str, radix, pos, forward| -> anyhow::Result<Option<u64>> {
+            let query = ValueQuery::parse(text, radix, true, 8)?;
+            let f = |v: &WaveDataValue| query.matches(v, 8);
+            Ok(match forward {
+                true => search_next(&data, pos, f),
+                false => search_prev(&data, pos, f),
+            })
+        };
+        assert_eq!(find("10", Radix::Hex, 0, true)?, Some(160));
+        assert_eq!(find("10", Radix::Dec, 0, true)?, Some(100));
+        assert_eq!(find("0x10", Radix::Dec, 500, false)?, Some(160));
+        assert_eq!(find("8'd5", Radix::Hex, 50, true)?, None);
+        assert_eq!(find(">= 90", Radix::Dec, 0, true)?, Some(900));
+        // searching backward across checkpoints
+        assert_eq!(find("< 3", Radix::Dec, 990, false)?, Some(20));
+        assert_eq!(find("!= 0", Radix::Dec, 30, false)?, Some(20));
+        assert_eq!(find("x", Radix::Hex, 0, true)?, Some(1000));
+        assert_eq!(find("z", Radix::Hex, 1010, false)?, None);
+        assert_eq!(find("8'b0000001x", Radix::Hex, 0, true)?, Some(20));
+        assert_eq!(find("8'b0000001x", Radix::Hex, 20, true)?, Some(30));
+        assert!(ValueQuery::parse("> 4'b1x", Radix::Hex, true, 8).is_err());
+        assert!(ValueQuery::parse("g", Radix::Hex, true, 8).is_err());
+        assert!(ValueQuery::parse("==", Radix::Hex, true, 8).is_err());
+        let query = ValueQuery::parse("> 1.5", Radix::Hex, false, 64)?;
+        assert!(query.matches(&WaveDataValue::Real(2.0), 64));
+        assert!(!query.matches(&WaveDataValue::Comp(vec![2]), 64));
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_radix() -> anyhow::Result<()> {
+        let matches = |text: &str, radix: Radix, value: Vec<u8>, width| -> anyhow::Result<bool> {
+            let query = ValueQuery::parse(text, radix, true, width)?;
+            Ok(query.matches(&WaveDataValue::Comp(value), width))
+        };
+        assert!(matches("-2", Radix::SignedDec, vec![0xfe], 8)?);
+        assert!(matches("-2", Radix::SignedDec, vec![0xe], 4)?);
+        assert!(!matches("-2", Radix::SignedDec, vec![0xfe, 0x00], 16)?);
+        assert!(matches("< 0", Radix::SignedDec, vec![0x80], 8)?);
+        assert!(!matches("< 0", Radix::SignedDec, vec![0x7f], 8)?);
+        assert!(matches("> -3", Radix::SignedDec, vec![0xfe], 8)?);
+        let fixed = "q4".parse::<Radix>()?;
+        assert!(matches("-0.5", fixed.clone(), vec![0xf8], 8)?);
+        assert!(matches("< 1.25", fixed.clone(), vec![0x13], 8)?);
+        assert!(ValueQuery::parse("0.1", fixed, true, 8).is_err());
+        assert!(matches("-2.125", Radix::Half, vec![0x40, 0xc0], 16)?);
+        assert!(matches(
+            "3.1415927",
+            Radix::Float,
+            vec![0xdb, 0x0f, 0x49, 0x40],
+            32
+        )?);
+        assert!(matches(
+            "> 3",
+            Radix::Float,
+            vec![0xdb, 0x0f, 0x49, 0x40],
+            32
+        )?);
+        assert!(matches(
+            "1",
+            Radix::Double,
+            1f64.to_bits().to_le_bytes().to_vec(),
+            64
+        )?);
+        assert!(matches("Hi", Radix::Ascii, vec![b'i', b'H', 0], 24)?);
+        // prefixed numbers are bits
+        assert!(matches("0xfe", Radix::SignedDec, vec![0xfe], 8)?);
+        assert!(ValueQuery::parse("200", Radix::SignedDec, true, 8).is_err());
+        assert!(ValueQuery::parse("nan", Radix::Float, true, 32).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_edge() {
+        let mut builder = WaveStoreBuilder::default();
+        let values = [
+            (0, V0),
+            (5, V1),
+            (10, X),
+            (15, V1),
+            (20, V1),
+            (25, V0),
+            (30, V1),
+        ];
+        for (timestamp, value) in values {
+            let item = WaveDataItem {
+                value: WaveDataValue::Raw(vec![value]),
+                timestamp,
+            };
+            builder.push(0, &item.compress().unwrap());
+        }
+        // 1 after 0 across checkpoint
+        for i in 0..40u64 {
+            let value = match i {
+                31 => V0,
+                _ => V1,
+            };
+            let item = WaveDataItem {
+                value: WaveDataValue::Raw(vec![value]),
+                timestamp: i,
+            };
+            builder.push(1, &item);
+        }
+        let store = builder.finish();
+        let data = store.get(&0).unwrap();
+        let next = |pos, kind| search_next_edge(&data, pos, kind, 1);
+        let prev = |pos, kind| search_prev_edge(&data, pos, kind, 1);
+        assert_eq!(next(5, EdgeKind::Any), Some(10));
+        // same value dumped again is not a change
+        assert_eq!(next(15, EdgeKind::Any), Some(25));
+        assert_eq!(prev(5, EdgeKind::Any), Some(0));
+        // x to 1 and 1 to 1 are not rising
+        assert_eq!(next(5, EdgeKind::Rising), Some(30));
+        assert_eq!(prev(30, EdgeKind::Rising), Some(5));
+        assert_eq!(next(0, EdgeKind::Falling), Some(25));
+        // initial value is not an edge
+        assert_eq!(prev(25, EdgeKind::Falling), None);
+        assert_eq!(prev(5, EdgeKind::Rising), None);
+        assert_eq!(next(30, EdgeKind::Any), None);
+        let data = store.get(&1).unwrap();
+        assert_eq!(search_prev_edge(&data, 100, EdgeKind::Rising, 1), Some(32));
+        assert_eq!(search_prev_edge(&data, 100, EdgeKind::Falling, 1), Some(31));
+        assert_eq!(search_prev_edge(&data, 32, EdgeKind::Rising, 1), None);
+        assert_eq!(search_next_edge(&data, 0, EdgeKind::Rising, 1), Some(32));
+        let value = WaveDataValue::Comp(vec![1]);
+        assert!(!EdgeKind::Rising.matches(Some(&WaveDataValue::Comp(vec![0])), &value, 8));
+    }
+}
@@ -0,0 +1,704 @@
+use crate::wave::lod::{LodBucket, SignalLod};
+use crate::wave::{WaveDataItem, WaveDataValue, WireValue};
+use std::collections::HashMap;
+
+/// Every such number of changes a checkpoint is recorded for random access
+pub const STORE_CHECKPOINT_INTERVAL: usize = 32;
+
+const TAG_COMP: u8 = 0;
+const TAG_COMP_BYTE: u8 = 1;
+const TAG_RAW: u8 = 2;
+const TAG_REAL: u8 = 3;
+const TAG_STR: u8 = 4;
+
+fn write_varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+fn read_varint(bytes: &[u8], offset: &mut usize) -> u64 {
+    let mut v = 0u64;
+    let mut shift = 0;
+    loop {
+        let b = bytes[*offset];
+        *offset += 1;
+        v |= ((b & 0x7f) as u64) << shift;
+        if b & 0x80 == 0 {
+            return v;
+        }
+        shift += 7;
+    }
+}
+
+fn wire_to_bits(v: WireValue) -> u8 {
+    match v {
+        WireValue::V0 => 0,
+        WireValue::V1 => 1,
+        WireValue::X => 2,
+        WireValue::Z => 3,
+    }
+}
+
+fn bits_to_wire(v: u8) -> WireValue {
+    match v & 0b11 {
+        0 => WireValue::V0,
+        1 => WireValue::V1,
+        2 => WireValue::X,
+        _ => WireValue::Z,
+    }
+}
+
+/// Encode value as tag byte and payload, four-state values use 2 bits per bit
+fn encode_value(buf: &mut Vec<u8>, value: &WaveDataValue) {
+    match value {
+        WaveDataValue::Comp(v) if v.len() == 1 => {
+            buf.push(TAG_COMP_BYTE);
+            buf.push(v[0]);
+        }
+        WaveDataValue::Comp(v) => {
+            buf.push(TAG_COMP);
+            write_varint(buf, v.len() as u64);
+            buf.extend_from_slice(v);
+        }
+        WaveDataValue::Raw(v) => {
+            buf.push(TAG_RAW);
+            write_varint(buf, v.len() as u64);
+            for chunk in v.chunks(4) {
+                buf.push(
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .fold(0u8, |b, (i, w)| b | (wire_to_bits(*w) << (i * 2))),
+                );
+            }
+        }
+        WaveDataValue::Real(v) => {
+            buf.push(TAG_REAL);
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        WaveDataValue::Str(v) => {
+            buf.push(TAG_STR);
+            write_varint(buf, v.len() as u64);
+            buf.extend_from_slice(v.as_bytes());
+        }
+    }
+}
+
+fn decode_value(bytes: &[u8], offset: &mut usize) -> WaveDataValue {
+    let tag = bytes[*offset];
+    *offset += 1;
+    match tag {
+        TAG_COMP_BYTE => {
+            *offset += 1;
+            WaveDataValue::Comp(vec![bytes[*offset - 1]])
+        }
+        TAG_COMP => {
+            let len = read_varint(bytes, offset) as usize;
+            *offset += len;
+            WaveDataValue::Comp(bytes[(*offset - len)..*offset].to_vec())
+        }
+        TAG_RAW => {
+            let len = read_varint(bytes, offset) as usize;
+            let v = (0..len)
+                .map(|i| bits_to_wire(bytes[*offset + i / 4] >> ((i % 4) * 2)))
+                .collect();
+            *offset += (len + 3) / 4;
+            WaveDataValue::Raw(v)
+        }
+        TAG_REAL => {
+            let mut v = [0u8; 8];
+            v.copy_from_slice(&bytes[*offset..(*offset + 8)]);
+            *offset += 8;
+            WaveDataValue::Real(f64::from_le_bytes(v))
+        }
+        _ => {
+            let len = read_varint(bytes, offset) as usize;
+            *offset += len;
+            WaveDataValue::Str(
+                String::from_utf8_lossy(&bytes[(*offset - len)..*offset]).to_string(),
+            )
+        }
+    }
+}
+
+/// Move `offset` over an encoded value without decoding it
+fn skip_value(bytes: &[u8], offset: &mut usize) {
+    let tag = bytes[*offset];
+    *offset += 1;
+    match tag {
+        TAG_COMP_BYTE => *offset += 1,
+        TAG_RAW => {
+            let len = read_varint(bytes, offset) as usize;
+            *offset += (len + 3) / 4;
+        }
+        TAG_REAL => *offset += 8,
+        _ => {
+            let len = read_varint(bytes, offset) as usize;
+            *offset += len;
+        }
+    }
+}
+
+/// Location of one signal's value changes in the arena
+#[derive(Debug, Clone, Default)]
+struct SignalColumn {
+    start: usize,
+    end: usize,
+    /// End of bytes reserved for appending changes, free bytes after `end` are not garbage
+    reserved: usize,
+    len: usize,
+    last_timestamp: u64,
+    /// (timestamp, offset from `start`) of every [STORE_CHECKPOINT_INTERVAL] changes
+    checkpoints: Vec<(u64, usize)>,
+    lod: SignalLod,
+}
+
+/// Value changes of one signal under building
+#[derive(Debug, Clone, Default)]
+struct ColumnBuilder {
+    bytes: Vec<u8>,
+    /// Length of bytes before `bytes` when appending to a stored column
+    base: usize,
+    len: usize,
+    last_timestamp: u64,
+    checkpoints: Vec<(u64, usize)>,
+    /// Summary of changes after each checkpoint
+    lod: Vec<LodBucket>,
+}
+
+impl ColumnBuilder {
+    /// Append a value change, timestamps less than the last one are raised to it
+    fn push(&mut self, item: &WaveDataItem) {
+        let timestamp = item.timestamp.max(self.last_timestamp);
+        if self.len % STORE_CHECKPOINT_INTERVAL == 0 {
+            self.checkpoints
+                .push((timestamp, self.base + self.bytes.len()));
+            self.lod.push(LodBucket::default());
+        }
+        if let Some(bucket) = self.lod.last_mut() {
+            bucket.push(&item.value);
+        }
+        write_varint(&mut self.bytes, timestamp - self.last_timestamp);
+        encode_value(&mut self.bytes, &item.value);
+        self.last_timestamp = timestamp;
+        self.len += 1;
+    }
+}
+
+/// Collects value changes in parsing order, then packs them into [WaveStore]
+#[derive(Debug, Default)]
+pub struct WaveStoreBuilder {
+    columns: HashMap<u64, ColumnBuilder>,
+}
+
+impl WaveStoreBuilder {
+    /// Append a value change, timestamps of a signal must not decrease,
+    /// a decreasing timestamp is stored as the last one of the signal
+    pub fn push(&mut self, id: u64, item: &WaveDataItem) {
+        self.columns.entry(id).or_default().push(item);
+    }
+    pub fn finish(self) -> WaveStore {
+        let mut store = WaveStore {
+            arena: Vec::with_capacity(self.columns.values().map(|c| c.bytes.len()).sum()),
+            columns: HashMap::with_capacity(self.columns.len()),
+            garbage: 0,
+        };
+        for (id, column) in self.columns {
+            store.append_column(id, column);
+        }
+        store
+    }
+}
+
+/// Packed columnar storage of value changes.
+///
+/// Value changes of each signal are stored continuously in a shared arena,
+/// with delta encoded timestamps and tagged values.
+#[derive(Debug, Clone, Default)]
+pub struct WaveStore {
+    arena: Vec<u8>,
+    columns: HashMap<u64, SignalColumn>,
+    /// Bytes in arena no longer referenced by columns
+    garbage: usize,
+}
+
+impl WaveStore {
+    fn append_column(&mut self, id: u64, column: ColumnBuilder) {
+        let start = self.arena.len();
+        self.arena.extend_from_slice(&column.bytes);
+        let mut checkpoints = column.checkpoints;
+        checkpoints.shrink_to_fit();
+        let old = self.columns.insert(
+            id,
+            SignalColumn {
+                start,
+                end: self.arena.len(),
+                reserved: self.arena.len(),
+                len: column.len,
+                last_timestamp: column.last_timestamp,
+                checkpoints,
+                lod: SignalLod::new(column.lod),
+            },
+        );
+        if let Some(old) = old {
+            self.garbage += old.reserved - old.start;
+        }
+    }
+    /// Push changes after stored changes of signal, in reserved bytes of its column
+    /// or at the end of arena, only moving the column when neither has room
+    fn append_changes(&mut self, id: u64, data: SignalData) {
+        let column = match self.columns.get_mut(&id) {
+            Some(column) => column,
+            None => {
+                let mut column = ColumnBuilder::default();
+                data.iter().for_each(|item| column.push(&item));
+                self.append_column(id, column);
+                return;
+            }
+        };
+        // continue last summary bucket if it is not full
+        let lod_start = column.len / STORE_CHECKPOINT_INTERVAL;
+        let mut tail = ColumnBuilder {
+            base: column.end - column.start,
+            len: column.len,
+            last_timestamp: column.last_timestamp,
+            lod: column.lod.get(lod_start).into_iter().collect(),
+            ..Default::default()
+        };
+        data.iter().for_each(|item| tail.push(&item));
+        let end = column.end + tail.bytes.len();
+        if end <= column.reserved {
+            self.arena[column.end..end].copy_from_slice(&tail.bytes);
+        } else if column.reserved == self.arena.len() {
+            self.arena.truncate(column.end);
+            self.arena.extend_from_slice(&tail.bytes);
+            column.reserved = end;
+        } else {
+            // move to the end, reserving half of its size for later changes
+            let start = self.arena.len();
+            self.arena.extend_from_within(column.start..column.end);
+            self.arena.extend_from_slice(&tail.bytes);
+            let size = self.arena.len() - start;
+            self.arena.resize(self.arena.len() + size / 2, 0);
+            self.garbage += column.reserved - column.start;
+            column.start = start;
+            column.reserved = self.arena.len();
+        }
+        column.end = column.start + tail.base + tail.bytes.len();
+        column.len = tail.len;
+        column.last_timestamp = tail.last_timestamp;
+        column.checkpoints.extend(tail.checkpoints);
+        for (index, bucket) in tail.lod.into_iter().enumerate() {
+            column.lod.set(lod_start + index, bucket);
+        }
+    }
+    /// Move columns to a new arena when too many bytes are unused
+    fn compact(&mut self) {
+        if self.garbage <= self.arena.len() / 2 {
+            return;
+        }
+        let mut arena = Vec::with_capacity(self.arena.len() - self.garbage);
+        for column in self.columns.values_mut() {
+            let start = arena.len();
+            arena.extend_from_slice(&self.arena[column.start..column.reserved]);
+            column.end = start + column.end - column.start;
+            column.start = start;
+            column.reserved = arena.len();
+        }
+        self.arena = arena;
+        self.garbage = 0;
+    }
+    /// Value changes of signal
+    pub fn get(&self, id: &u64) -> Option<SignalData<'_>> {
+        self.columns.get(id).map(|column| SignalData {
+            bytes: &self.arena[column.start..column.end],
+            column,
+        })
+    }
+    pub fn contains_key(&self, id: &u64) -> bool {
+        self.columns.contains_key(id)
+    }
+    /// Ids of stored signals
+    pub fn keys(&self) -> impl Iterator<Item = &u64> {
+        self.columns.keys()
+    }
+    /// Count of stored signals
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+    /// Replace value changes of signal
+    pub fn insert(&mut self, id: u64, items: &[WaveDataItem]) {
+        let mut column = ColumnBuilder::default();
+        items.iter().for_each(|item| column.push(item));
+        self.append_column(id, column);
+        self.compact();
+    }
+    /// Remove changes of signal at or after `pos`, their bytes are kept for appending
+    pub fn truncate(&mut self, id: u64, pos: u64) {
+        let (index, offset, last_timestamp, bucket) = match self.get(&id) {
+            Some(data) => {
+                let index = data.position(pos);
+                if index >= data.len() {
+                    return;
+                }
+                let iter = data.iter_at(index);
+                // summary of changes kept in the last bucket
+                let checkpoint = index / STORE_CHECKPOINT_INTERVAL * STORE_CHECKPOINT_INTERVAL;
+                let bucket = (index > checkpoint).then(|| {
+                    data.iter_at(checkpoint).take(index - checkpoint).fold(
+                        LodBucket::default(),
+                        |mut bucket, item| {
+                            bucket.push(&item.value);
+                            bucket
+                        },
+                    )
+                });
+                (index, iter.offset, iter.timestamp, bucket)
+            }
+            None => return,
+        };
+        if let Some(column) = self.columns.get_mut(&id) {
+            let buckets = (index + STORE_CHECKPOINT_INTERVAL - 1) / STORE_CHECKPOINT_INTERVAL;
+            column.end = column.start + offset;
+            column.len = index;
+            column.last_timestamp = last_timestamp;
+            column.checkpoints.truncate(buckets);
+            column.lod.truncate(buckets);
+            if let Some(bucket) = bucket {
+                column.lod.set(buckets - 1, bucket);
+            }
+        }
+    }
+    /// Move signals from other store into this store
+    pub fn extend(&mut self, other: WaveStore) {
+        for (id, column) in other.columns {
+            let start = self.arena.len();
+            self.arena
+                .extend_from_slice(&other.arena[column.start..column.end]);
+            let old = self.columns.insert(
+                id,
+                SignalColumn {
+                    start,
+                    end: self.arena.len(),
+                    reserved: self.arena.len(),
+                    ..column
+                },
+            );
+            if let Some(old) = old {
+                self.garbage += old.reserved - old.start;
+            }
+        }
+        self.compact();
+    }
+    /// Append changes in other store after changes of the same signals,
+    /// timestamps in other store must not be less than existing ones
+    pub fn append(&mut self, other: WaveStore) {
+        for id in other.columns.keys() {
+            if let Some(data) = other.get(id) {
+                self.append_changes(*id, data);
+            }
+        }
+        self.compact();
+    }
+    /// Keep only signals specified by the predicate
+    pub fn retain<F: FnMut(&u64) -> bool>(&mut self, mut f: F) {
+        let garbage = &mut self.garbage;
+        self.columns.retain(|id, column| {
+            let keep = f(id);
+            if !keep {
+                *garbage += column.reserved - column.start;
+            }
+            keep
+        });
+    }
+    /// Approximate heap memory used in bytes
+    pub fn memory_size(&self) -> usize {
+        self.arena.capacity()
+            + self.columns.capacity()
+                * (std::mem::size_of::<u64>() + std::mem::size_of::<SignalColumn>())
+            + self
+                .columns
+                .values()
+                .map(|c| {
+                    c.checkpoints.capacity() * std::mem::size_of::<(u64, usize)>()
+                        + c.lod.memory_size()
+                })
+                .sum::<usize>()
+    }
+}
+
+/// Borrowed value changes of one signal
+#[derive(Debug, Clone, Copy)]
+pub struct SignalData<'a> {
+    bytes: &'a [u8],
+    column: &'a SignalColumn,
+}
+
+impl<'a> SignalData<'a> {
+    pub fn len(&self) -> usize {
+        self.column.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.column.len == 0
+    }
+    pub fn iter(&self) -> SignalDataIter<'a> {
+        self.iter_at(0)
+    }
+    /// Iterate from change at `index`
+    pub fn iter_at(&self, index: usize) -> SignalDataIter<'a> {
+        let checkpoint = index / STORE_CHECKPOINT_INTERVAL;
+        let mut iter = self.iter_checkpoint(checkpoint);
+        for _ in 0..(index % STORE_CHECKPOINT_INTERVAL) {
+            iter.skip_one();
+        }
+        iter
+    }
+    fn iter_checkpoint(&self, checkpoint: usize) -> SignalDataIter<'a> {
+        match self.column.checkpoints.get(checkpoint) {
+            Some((timestamp, offset)) => {
+                // recover timestamp before checkpoint from its delta
+                let mut delta_offset = *offset;
+                let delta = read_varint(self.bytes, &mut delta_offset);
+                SignalDataIter {
+                    bytes: self.bytes,
+                    offset: *offset,
+                    index: checkpoint * STORE_CHECKPOINT_INTERVAL,
+                    timestamp: timestamp - delta,
+                }
+            }
+            None => SignalDataIter {
+                bytes: self.bytes,
+                offset: self.bytes.len(),
+                index: self.column.len,
+                timestamp: 0,
+            },
+        }
+    }
+    /// Count of changes before `pos`, like `Err(index)` from binary search
+    pub fn position(&self, pos: u64) -> usize {
+        let checkpoint = self.column.checkpoints.partition_point(|c| c.0 < pos);
+        if checkpoint == 0 {
+            return 0;
+        }
+        let mut iter = self.iter_checkpoint(checkpoint - 1);
+        while let Some(timestamp) = iter.peek_timestamp() {
+            if timestamp >= pos {
+                break;
+            }
+            iter.skip_one();
+        }
+        iter.index
+    }
+    /// Summary of changes from index `start` to `end`, see [SignalLod::summary]
+    pub fn summary(&self, start: usize, end: usize) -> LodBucket {
+        self.column.lod.summary(start, end)
+    }
+    /// Find last change at or before `pos`
+    pub fn find(&self, pos: u64) -> Option<WaveDataItem> {
+        let index = self.position(pos.saturating_add(1));
+        if index == 0 {
+            None
+        } else {
+            self.iter_at(index - 1).next()
+        }
+    }
+}
+
+/// Decoding iterator over [SignalData]
+#[derive(Debug, Clone)]
+pub struct SignalDataIter<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    /// Index of next change
+    index: usize,
+    /// Timestamp of last change, or checkpoint
+    timestamp: u64,
+}
+
+impl<'a> SignalDataIter<'a> {
+    fn peek_timestamp(&self) -> Option<u64> {
+        if self.offset >= self.bytes.len() {
+            None
+        } else {
+            let mut offset = self.offset;
+            Some(self.timestamp + read_varint(self.bytes, &mut offset))
+        }
+    }
+    fn skip_one(&mut self) {
+        if self.offset < self.bytes.len() {
+            self.timestamp += read_varint(self.bytes, &mut self.offset);
+            skip_value(self.bytes, &mut self.offset);
+            self.index += 1;
+        }
+    }
+}
+
+impl<'a> Iterator for SignalDataIter<'a> {
+    type Item = WaveDataItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.bytes.len() {
+            return None;
+        }
+        self.timestamp += read_varint(self.bytes, &mut self.offset);
+        let value = decode_value(self.bytes, &mut self.offset);
+        self.index += 1;
+        Some(WaveDataItem {
+            value,
+            timestamp: self.timestamp,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::wave::store::{WaveStore, WaveStoreBuilder, STORE_CHECKPOINT_INTERVAL};
+    use crate::wave::{WaveDataItem, WaveDataValue, WireValue};
+
+    fn items() -> Vec<WaveDataItem> {
+        (0..(STORE_CHECKPOINT_INTERVAL as u64 * 3 + 5))
+            .map(|i| WaveDataItem {
+                value: match i % 5 {
+                    0 => WaveDataValue::Comp(vec![i as u8]),
+                    1 => WaveDataValue::Comp(vec![0x12, 0x34, i as u8]),
+                    2 => WaveDataValue::Raw(vec![
+                        WireValue::V1,
+                        WireValue::X,
+                        WireValue::Z,
+                        WireValue::V0,
+                        WireValue::V1,
+                    ]),
+                    3 => WaveDataValue::Real(i as f64 / 3.0),
+                    _ => WaveDataValue::Str(format!("state_{i}")),
+                },
+                timestamp: i * 10 + i * i,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_store_roundtrip() {
+        let items = items();
+        let mut builder = WaveStoreBuilder::default();
+        items.iter().for_each(|item| builder.push(7, item));
+        let store = builder.finish();
+        let data = store.get(&7).unwrap();
+        assert_eq!(data.len(), items.len());
+        for (a, b) in items.iter().zip(data.iter()) {
+            assert_eq!(a.timestamp, b.timestamp);
+            assert_eq!(a.value.to_string(), b.value.to_string());
+        }
+        for index in [0, 1, STORE_CHECKPOINT_INTERVAL, items.len() - 1] {
+            assert_eq!(
+                data.iter_at(index).next().unwrap().timestamp,
+                items[index].timestamp
+            );
+        }
+    }
+
+    #[test]
+    fn test_store_find() {
+        let items = items();
+        let mut store = WaveStoreBuilder::default().finish();
+        store.insert(1, &items);
+        let data = store.get(&1).unwrap();
+        for (index, item) in items.iter().enumerate() {
+            assert_eq!(data.position(item.timestamp), index);
+            assert_eq!(data.position(item.timestamp + 1), index + 1);
+            assert_eq!(data.find(item.timestamp).unwrap().timestamp, item.timestamp);
+            assert_eq!(
+                data.find(item.timestamp + 1).unwrap().timestamp,
+                item.timestamp
+            );
+        }
+        assert!(store.get(&2).is_none());
+        // decreasing timestamp is kept at the last one
+        let mut builder = WaveStoreBuilder::default();
+        for timestamp in [10, 5, 20] {
+            builder.push(
+                3,
+                &WaveDataItem {
+                    value: WaveDataValue::Comp(vec![1]),
+                    timestamp,
+                },
+            );
+        }
+        let store = builder.finish();
+        let timestamps = store.get(&3).unwrap().iter().map(|i| i.timestamp);
+        assert_eq!(timestamps.collect::<Vec<_>>(), vec![10, 10, 20]);
+    }
+
+    #[test]
+    fn test_store_append() {
+        let items = items();
+        let mut store = WaveStoreBuilder::default().finish();
+        store.insert(2, &items[..3]);
+        for part in items[3..].chunks(7) {
+            let mut other = WaveStoreBuilder::default();
+            part.iter().for_each(|item| {
+                other.push(1, item);
+                other.push(2, item);
+            });
+            store.append(other.finish());
+        }
+        assert_eq!(store.get(&1).unwrap().len(), items.len() - 3);
+        let data = store.get(&2).unwrap();
+        assert_eq!(data.len(), items.len());
+        for (a, b) in items.iter().zip(data.iter()) {
+            assert_eq!(a.timestamp, b.timestamp);
+            assert_eq!(a.value.to_string(), b.value.to_string());
+        }
+        for item in &items {
+            assert_eq!(data.find(item.timestamp).unwrap().timestamp, item.timestamp);
+        }
+        // summaries match changes stored at once
+        let mut built = WaveStore::default();
+        built.insert(2, &items);
+        let built = built.get(&2).unwrap();
+        let n = STORE_CHECKPOINT_INTERVAL;
+        for (start, end) in [(0, items.len()), (n, 2 * n + 1), (3 * n, items.len())] {
+            assert_eq!(data.summary(start, end), built.summary(start, end));
+        }
+    }
+
+    #[test]
+    fn test_store_truncate() {
+        let items = items();
+        let n = STORE_CHECKPOINT_INTERVAL;
+        for len in [0, 1, n, n + 1, 3 * n, items.len() - 1] {
+            let mut store = WaveStore::default();
+            store.insert(1, &items);
+            store.truncate(1, items[len].timestamp);
+            // then the same changes are appended again
+            let mut other = WaveStoreBuilder::default();
+            items[len..].iter().for_each(|item| other.push(1, item));
+            let truncated = store.get(&1).unwrap();
+            assert_eq!(truncated.len(), len);
+            assert_eq!(
+                truncated.find(u64::MAX).map(|item| item.timestamp),
+                len.checked_sub(1).map(|i| items[i].timestamp)
+            );
+            store.append(other.finish());
+            let mut built = WaveStore::default();
+            built.insert(1, &items);
+            let (data, built) = (store.get(&1).unwrap(), built.get(&1).unwrap());
+            assert_eq!(data.len(), items.len());
+            for (a, b) in data.iter().zip(built.iter()) {
+                assert_eq!(a.timestamp, b.timestamp);
+                assert_eq!(a.value.to_string(), b.value.to_string());
+            }
+            for start in [0, n, 2 * n + 3] {
+                assert_eq!(
+                    data.summary(start, items.len()),
+                    built.summary(start, items.len())
+                );
+            }
+        }
+    }
+}
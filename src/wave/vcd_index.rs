@@ -0,0 +1,458 @@
+use crate::wave::store::{WaveStore, WaveStoreBuilder};
+use crate::wave::vcd_parser::vcd_wave_info;
+use crate::wave::WaveDataValue::{Raw, Real, Str};
+use crate::wave::{WaveDataItem, WaveInfo, WireValue};
+use anyhow::{anyhow, Result};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Read, Seek, SeekFrom};
+use tracing::info;
+use vcd::IdCode;
+
+/// Minimal size in bytes of a chunk in [VcdIndex]
+pub const VCD_INDEX_CHUNK_SIZE: u64 = 256 * 1024;
+
+/// Offsets of value changes in a vcd file, used to load signals on demand.
+///
+/// Value changes are split into chunks beginning with a timestamp line,
+/// signals only record chunks that contain their value changes.
+#[derive(Debug, Default, Clone)]
+pub struct VcdIndex {
+    /// Start timestamp and file offset of each chunk
+    pub chunks: Vec<(u64, u64)>,
+    /// Chunks containing value changes, indexed by signal id
+    pub signal_chunks: HashMap<u64, Vec<u32>>,
+    /// Timestamp range of value changes
+    pub range: (u64, u64),
+    /// Offset of file end, after the last complete line
+    pub end: u64,
+}
+
+/// Token parsed in vcd value changes section
+enum VcdToken<'a> {
+    Timestamp(u64),
+    /// (value, id code)
+    Scalar(u8, &'a str),
+    /// (type char, value, id code)
+    Vector(u8, &'a str, &'a str),
+}
+
+/// Tokenizer state kept between whitespace separated tokens
+#[derive(Debug, Default)]
+struct VcdTokenizer {
+    in_comment: bool,
+    /// Type char of vector value waiting for id code
+    pending: Option<u8>,
+    pending_value: String,
+}
+
+impl VcdTokenizer {
+    fn feed<'a>(&'a mut self, token: &'a str) -> Option<VcdToken<'a>> {
+        if self.in_comment {
+            if token == "$end" {
+                self.in_comment = false;
+            }
+            return None;
+        }
+        if let Some(kind) = self.pending.take() {
+            return Some(VcdToken::Vector(kind, self.pending_value.as_str(), token));
+        }
+        let bytes = token.as_bytes();
+        match bytes[0] {
+            b'$' => {
+                if token == "$comment" {
+                    self.in_comment = true;
+                }
+                None
+            }
+            b'#' => token[1..].parse().ok().map(VcdToken::Timestamp),
+            b'b' | b'B' | b'r' | b'R' | b's' | b'S' => {
+                self.pending = Some(bytes[0].to_ascii_lowercase());
+                self.pending_value.clear();
+                self.pending_value.push_str(&token[1..]);
+                None
+            }
+            b'0' | b'1' | b'x' | b'X' | b'z' | b'Z' if bytes.len() > 1 => {
+                Some(VcdToken::Scalar(bytes[0], &token[1..]))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn vcd_wire_value(c: u8) -> WireValue {
+    match c {
+        b'0' => WireValue::V0,
+        b'1' => WireValue::V1,
+        b'z' | b'Z' => WireValue::Z,
+        _ => WireValue::X,
+    }
+}
+
+fn vcd_id(code: &str) -> Option<u64> {
+    code.parse::<IdCode>().ok().map(|IdCode(id)| id)
+}
+
+/// Parse value changes in text, `timestamp` is updated by timestamp tokens
+fn parse_value_changes<F>(
+    text: &str,
+    tokenizer: &mut VcdTokenizer,
+    timestamp: &mut u64,
+    mut handler: F,
+) -> Result<()>
+where
+    F: FnMut(u64, WaveDataItem) -> Result<()>,
+{
+    for token in text.split_ascii_whitespace() {
+        let (code, value) = match tokenizer.feed(token) {
+            Some(VcdToken::Timestamp(t)) => {
+                if t < *timestamp {
+                    return Err(anyhow!("timestamp #{} is before #{}", t, timestamp));
+                }
+                *timestamp = t;
+                continue;
+            }
+            Some(VcdToken::Scalar(v, code)) => (code, Raw(vec![vcd_wire_value(v)])),
+            Some(VcdToken::Vector(kind, v, code)) => (
+                code,
+                match kind {
+                    b'r' => Real(v.parse()?),
+                    b's' => Str(v.to_string()),
+                    _ => Raw(v.bytes().map(vcd_wire_value).collect()),
+                },
+            ),
+            None => continue,
+        };
+        if let Some(id) = vcd_id(code) {
+            handler(
+                id,
+                WaveDataItem {
+                    value,
+                    timestamp: *timestamp,
+                },
+            )?;
+        }
+    }
+    Ok(())
+}
+
+impl VcdIndex {
+    /// Scan vcd file to parse header and build value changes index,
+    /// the last line is left to [VcdTail] when it is still being written
+    ///
+    /// * `progress_handler`: called with bytes scanned, return `false` to cancel
+    pub fn build<R, F>(reader: &mut R, mut progress_handler: F) -> Result<(WaveInfo, Self)>
+    where
+        R: BufRead,
+        F: FnMut(u64) -> bool,
+    {
+        const END_DEFINITIONS: &str = "$enddefinitions";
+        info!("start indexing vcd file");
+        #[cfg(not(target_arch = "wasm32"))]
+        let perf_start = std::time::Instant::now();
+        let mut header = vec![];
+        let mut line = vec![];
+        let mut offset = 0u64;
+        let mut definitions_end = false;
+        loop {
+            line.clear();
+            let sz = reader.read_until(b'\n', &mut line)?;
+            if sz == 0 {
+                return Err(anyhow!("cannot find end of vcd header"));
+            }
+            offset += sz as u64;
+            header.extend_from_slice(&line);
+            let text = String::from_utf8_lossy(&line);
+            let rest = if definitions_end {
+                Some(text.as_ref())
+            } else {
+                text.find(END_DEFINITIONS).map(|p| {
+                    definitions_end = true;
+                    &text[(p + END_DEFINITIONS.len())..]
+                })
+            };
+            if rest.map(|r| r.contains("$end")).unwrap_or(false) {
+                break;
+            }
+        }
+        let header = vcd::Parser::new(header.as_slice()).parse_header()?;
+        let mut index = Self {
+            chunks: vec![(0, offset)],
+            range: (u64::MAX, 0),
+            ..Default::default()
+        };
+        let mut tokenizer = VcdTokenizer::default();
+        let mut notified = offset;
+        loop {
+            line.clear();
+            let sz = reader.read_until(b'\n', &mut line)?;
+            if sz == 0 || line.last() != Some(&b'\n') {
+                break;
+            }
+            let line_offset = offset;
+            offset += sz as u64;
+            let text = String::from_utf8_lossy(&line);
+            if !tokenizer.in_comment && text.starts_with('#') {
+                let (_, chunk_offset) = index.chunks[index.chunks.len() - 1];
+                if line_offset - chunk_offset >= VCD_INDEX_CHUNK_SIZE {
+                    if let Ok(timestamp) = text[1..].trim().parse() {
+                        index.chunks.push((timestamp, line_offset));
+                    }
+                }
+            }
+            let chunk = (index.chunks.len() - 1) as u32;
+            for token in text.split_ascii_whitespace() {
+                let code = match tokenizer.feed(token) {
+                    Some(VcdToken::Timestamp(t)) => {
+                        index.range = (index.range.0.min(t), index.range.1.max(t));
+                        continue;
+                    }
+                    Some(VcdToken::Scalar(_, code)) | Some(VcdToken::Vector(_, _, code)) => code,
+                    None => continue,
+                };
+                if let Some(id) = vcd_id(code) {
+                    let list = index.signal_chunks.entry(id).or_default();
+                    if list.last() != Some(&chunk) {
+                        list.push(chunk);
+                    }
+                }
+            }
+            if offset - notified >= VCD_INDEX_CHUNK_SIZE {
+                notified = offset;
+                if !progress_handler(offset) {
+                    return Err(anyhow!("vcd indexing canceled"));
+                }
+            }
+        }
+        if index.range.0 > index.range.1 {
+            index.range = (0, 0);
+        }
+        index.end = offset;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let perf_stop = std::time::Instant::now();
+            info!(
+                "index vcd use time: {:?}, {} chunks",
+                perf_stop - perf_start,
+                index.chunks.len()
+            );
+        }
+        Ok((vcd_wave_info(&header, index.range)?, index))
+    }
+
+    /// Load value changes of signals from indexed file,
+    /// signals without changes are loaded as empty
+    pub fn load_signals<R>(&self, reader: &mut R, ids: &[u64]) -> Result<WaveStore>
+    where
+        R: Read + Seek,
+    {
+        let wanted: HashSet<u64> = ids.iter().copied().collect();
+        let mut chunks = ids
+            .iter()
+            .filter_map(|id| self.signal_chunks.get(id))
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+        chunks.sort_unstable();
+        chunks.dedup();
+        let mut data = WaveStoreBuilder::default();
+        let mut buf = vec![];
+        for chunk in chunks {
+            let (mut timestamp, start) = self.chunks[chunk as usize];
+            let end = self
+                .chunks
+                .get(chunk as usize + 1)
+                .map(|c| c.1)
+                .unwrap_or(self.end);
+            reader.seek(SeekFrom::Start(start))?;
+            buf.resize((end - start) as usize, 0);
+            reader.read_exact(&mut buf)?;
+            let text = String::from_utf8_lossy(&buf);
+            let mut tokenizer = VcdTokenizer::default();
+            parse_value_changes(&text, &mut tokenizer, &mut timestamp, |id, item| {
+                if wanted.contains(&id) {
+                    data.push(id, &item.compress()?);
+                }
+                Ok(())
+            })?;
+        }
+        let mut data = data.finish();
+        for id in ids {
+            if !data.contains_key(id) {
+                data.insert(*id, &[]);
+            }
+        }
+        Ok(data)
+    }
+    /// Add value changes parsed by [VcdTail] as a new chunk
+    ///
+    /// * `start`: timestamp and offset of tail before parsing
+    pub fn extend(&mut self, start: (u64, u64), tail: &VcdTail, data: &WaveStore) {
+        let chunk = self.chunks.len() as u32;
+        self.chunks.push(start);
+        for id in data.keys() {
+            self.signal_chunks.entry(*id).or_default().push(chunk);
+        }
+        self.range.1 = self.range.1.max(tail.timestamp);
+        self.end = tail.offset;
+    }
+}
+
+/// Incremental parser of value changes appended to a vcd file,
+/// used to follow files still being written.
+#[derive(Debug, Default)]
+pub struct VcdTail {
+    /// Offset of first byte not parsed yet
+    pub offset: u64,
+    /// Last parsed timestamp
+    pub timestamp: u64,
+    tokenizer: VcdTokenizer,
+}
+
+impl VcdTail {
+    pub fn new(offset: u64, timestamp: u64) -> Self {
+        Self {
+            offset,
+            timestamp,
+            ..Default::default()
+        }
+    }
+    /// Parse complete lines appended after `offset`,
+    /// returns `None` when no complete line is appended,
+    /// or error when file is truncated or rewritten
+    pub fn poll<R>(&mut self, reader: &mut R) -> Result<Option<WaveStore>>
+    where
+        R: Read + Seek,
+    {
+        let end = reader.seek(SeekFrom::End(0))?;
+        if end < self.offset {
+            return Err(anyhow!("file is truncated"));
+        }
+        if end == self.offset {
+            return Ok(None);
+        }
+        reader.seek(SeekFrom::Start(self.offset))?;
+        let mut buf = vec![0u8; (end - self.offset) as usize];
+        reader.read_exact(&mut buf)?;
+        // last line may be still being written
+        let len = match buf.iter().rposition(|c| *c == b'\n') {
+            Some(p) => p + 1,
+            None => return Ok(None),
+        };
+        let text = String::from_utf8_lossy(&buf[..len]);
+        if text.contains("$enddefinitions") {
+            return Err(anyhow!("file is rewritten"));
+        }
+        let mut data = WaveStoreBuilder::default();
+        parse_value_changes(
+            &text,
+            &mut self.tokenizer,
+            &mut self.timestamp,
+            |id, item| {
+                data.push(id, &item.compress()?);
+                Ok(())
+            },
+        )?;
+        self.offset += len as u64;
+        Ok(Some(data.finish()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::wave::vcd_index::{VcdIndex, VcdTail};
+    use crate::wave::vcd_parser::Vcd;
+    use crate::wave::WaveLoader;
+    use std::fs::File;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_vcd_index() -> anyhow::Result<()> {
+        let wave = Vcd::load(&mut File::open("data/cpu_ila_commit.vcd")?, |_, _| {}, None)?;
+        let mut reader = BufReader::new(File::open("data/cpu_ila_commit.vcd")?);
+        let (info, index) = VcdIndex::build(&mut reader, |_| true)?;
+        assert_eq!(info.range, wave.info.range);
+        assert_eq!(
+            info.code_signal_info.len(),
+            wave.info.code_signal_info.len()
+        );
+        let ids = wave.data.keys().copied().collect::<Vec<_>>();
+        let data = index.load_signals(&mut reader, &ids)?;
+        for id in ids {
+            let expected = wave.data.get(&id).unwrap();
+            let loaded = data.get(&id).unwrap();
+            assert_eq!(expected.len(), loaded.len());
+            for (a, b) in expected.iter().zip(loaded.iter()) {
+                assert_eq!(a.timestamp, b.timestamp);
+                assert_eq!(a.value.to_string(), b.value.to_string());
+            }
+        }
+        let data = index.load_signals(&mut reader, &[u64::MAX])?;
+        assert!(data.get(&u64::MAX).unwrap().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_vcd_index_partial_line() -> anyhow::Result<()> {
+        let text = std::fs::read("data/cpu_ila_commit.vcd")?;
+        let wave = Vcd::load(&mut Cursor::new(text.as_slice()), |_, _| {}, None)?;
+        // file is indexed while a line is being written
+        let line_end = text.len() / 2
+            + text[text.len() / 2..]
+                .iter()
+                .position(|c| *c == b'\n')
+                .unwrap();
+        let partial = &text[..line_end + 3];
+        assert_ne!(partial.last(), Some(&b'\n'));
+        let (_, index) = VcdIndex::build(&mut Cursor::new(partial), |_| true)?;
+        assert_eq!(index.end, line_end as u64 + 1);
+        let ids = wave.data.keys().copied().collect::<Vec<_>>();
+        let mut data = index.load_signals(&mut Cursor::new(partial), &ids)?;
+        // then the rest of file is appended
+        let mut tail = VcdTail::new(index.end, index.range.1);
+        if let Some(appended) = tail.poll(&mut Cursor::new(text.as_slice()))? {
+            data.append(appended);
+        }
+        assert_eq!(tail.timestamp, wave.info.range.1);
+        for id in ids {
+            let expected = wave.data.get(&id).unwrap();
+            let loaded = data.get(&id).unwrap();
+            assert_eq!(expected.len(), loaded.len());
+            for (a, b) in expected.iter().zip(loaded.iter()) {
+                assert_eq!(a.timestamp, b.timestamp);
+                assert_eq!(a.value.to_string(), b.value.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_vcd_tail() -> anyhow::Result<()> {
+        let text = std::fs::read("data/cpu_ila_commit.vcd")?;
+        let wave = Vcd::load(&mut Cursor::new(text.as_slice()), |_, _| {}, None)?;
+        let header_end = text
+            .windows(b"$enddefinitions".len())
+            .position(|w| w == b"$enddefinitions")
+            .unwrap();
+        let header_end =
+            header_end + text[header_end..].iter().position(|c| *c == b'\n').unwrap() + 1;
+        let mut tail = VcdTail::new(header_end as u64, 0);
+        let mut data = crate::wave::store::WaveStore::default();
+        // feed file in small pieces, breaking lines
+        for end in (header_end..text.len()).step_by(97).chain([text.len()]) {
+            if let Some(appended) = tail.poll(&mut Cursor::new(&text[..end]))? {
+                data.append(appended);
+            }
+        }
+        assert_eq!(tail.timestamp, wave.info.range.1);
+        for id in wave.data.keys() {
+            let expected = wave.data.get(id).unwrap();
+            let loaded = data.get(id).unwrap();
+            assert_eq!(expected.len(), loaded.len());
+            for (a, b) in expected.iter().zip(loaded.iter()) {
+                assert_eq!(a.timestamp, b.timestamp);
+                assert_eq!(a.value.to_string(), b.value.to_string());
+            }
+        }
+        Ok(())
+    }
+}
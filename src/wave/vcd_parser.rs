@@ -1,4 +1,5 @@
-use crate::wave::WaveDataValue::Raw;
+use crate::wave::store::WaveStoreBuilder;
+use crate::wave::WaveDataValue::{Raw, Real, Str};
 use crate::wave::WaveTreeNode::WaveRoot;
 use crate::wave::{
     Wave, WaveDataItem, WaveInfo, WaveLoader, WavePreLoader, WaveScopeInfo,
@@ -105,6 +106,15 @@ pub fn vcd_code_name(header: &Header) -> HashMap<IdCode, WaveSignalInfo> {
     map
 }
 
+/// Id of scope from its name and `seed`, which is 0 at top level and id of parent scope plus 1
+/// in nested scopes, fst parser uses it too so that scope ids are the same in both formats
+pub fn scope_hash(name: &str, seed: u64) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    name.hash(&mut hasher);
+    seed.hash(&mut hasher);
+    hasher.finish()
+}
+
 fn vcd_iterate_tree(
     mut tree: Tree<WaveTreeNode>,
     items: &[ScopeItem],
@@ -116,10 +126,7 @@ fn vcd_iterate_tree(
     for item in items.iter() {
         match item {
             ScopeItem::Scope(scope) => {
-                let mut hasher = DefaultHasher::new();
-                scope.identifier.hash(&mut hasher);
-                scope_id.hash(&mut hasher);
-                let id = hasher.finish();
+                let id = scope_hash(&scope.identifier, scope_id);
                 let node = Tree::new(WaveTreeNode::WaveScope(
                     // bfs cannot specify id, so use hash now. TODO: dfs
                     WaveScopeInfo::from_scope(id, scope),
@@ -173,7 +180,7 @@ pub fn vcd_tree(header: &Header) -> Result<Tree<WaveTreeNode>> {
     let root = Tree::new(WaveRoot);
     fn on_scope(tree: Tree<WaveTreeNode>, scope: &Scope, scope_id: u64) -> Tree<WaveTreeNode> {
         let children = merge_scope_items(scope.children.clone());
-        vcd_iterate_tree(tree, children.as_slice(), on_scope, on_var, scope_id + 1)
+        vcd_iterate_tree(tree, children.as_slice(), on_scope, on_var, scope_id.wrapping_add(1))
     }
     fn on_var(tree: Tree<WaveTreeNode>, _var: &Var) -> Tree<WaveTreeNode> {
         tree.deep_clone()
@@ -309,6 +316,50 @@ where
     }
 }
 
+/// Build [WaveInfo] from vcd header
+///
+/// * `range`: timestamp range of value changes
+pub fn vcd_wave_info(header: &Header, range: (u64, u64)) -> Result<WaveInfo> {
+    let code_info = vcd_code_name(header)
+        .into_iter()
+        .map(|i| {
+            let IdCode(id) = i.0;
+            (id, i.1)
+        })
+        .collect();
+    let code_paths = vcd_code_path(header)?
+        .into_iter()
+        .map(|(i, path)| {
+            let IdCode(id) = i;
+            (id, path)
+        })
+        .collect();
+    let tree = vcd_tree(header)?;
+    let mut headers: HashMap<String, String> = HashMap::new();
+    if let Some(c) = header.comment.as_ref() {
+        headers.insert("comment".to_string(), c.to_string());
+    }
+    if let Some(c) = header.date.as_ref() {
+        headers.insert("date".to_string(), c.to_string());
+    }
+    if let Some(c) = header.version.as_ref() {
+        headers.insert("version".to_string(), c.to_string());
+    }
+    let timescale = if let Some(c) = header.timescale.as_ref() {
+        (c.0 as u64, c.1.into())
+    } else {
+        (1, WaveTimescaleUnit::default())
+    };
+    Ok(WaveInfo {
+        timescale,
+        range,
+        headers,
+        code_signal_info: code_info,
+        code_paths,
+        tree,
+    })
+}
+
 impl WavePreLoader for Vcd {
     fn last_timestamp<T>(reader: BufReader<T>) -> (Option<u64>, std::io::Result<BufReader<T>>)
     where
@@ -332,37 +383,7 @@ impl WaveLoader for Vcd {
         let perf_start = std::time::Instant::now();
         let mut parser = vcd::Parser::new(reader);
         let header = parser.parse_header()?;
-        let code_info = vcd_code_name(&header)
-            .into_iter()
-            .map(|i| {
-                let IdCode(id) = i.0;
-                (id, i.1)
-            })
-            .collect();
-        let code_paths = vcd_code_path(&header)?
-            .into_iter()
-            .map(|(i, path)| {
-                let IdCode(id) = i;
-                (id, path)
-            })
-            .collect();
-        let tree = vcd_tree(&header)?;
-        let mut headers: HashMap<String, String> = HashMap::new();
-        if let Some(c) = header.comment.as_ref() {
-            headers.insert("comment".to_string(), c.to_string());
-        }
-        if let Some(c) = header.date.as_ref() {
-            headers.insert("date".to_string(), c.to_string());
-        }
-        if let Some(c) = header.version.as_ref() {
-            headers.insert("version".to_string(), c.to_string());
-        }
-        let timescale = if let Some(c) = header.timescale.as_ref() {
-            (c.0 as u64, c.1.into())
-        } else {
-            (1, WaveTimescaleUnit::default())
-        };
-        let mut data: HashMap<u64, Vec<WaveDataItem>> = HashMap::new();
+        let mut data = WaveStoreBuilder::default();
         let mut timestamp = 0u64;
         let mut time_start = 0xfffffffffffffu64;
         let mut time_stop = 0u64;
@@ -401,11 +422,7 @@ impl WaveLoader for Vcd {
                         timestamp,
                     }
                     .compress()?;
-                    if let Some(list) = data.get_mut(&id) {
-                        list.push(item);
-                    } else {
-                        data.insert(id, vec![item]);
-                    }
+                    data.push(id, &item);
                 }
                 Command::ChangeVector(i, v) => {
                     let IdCode(id) = i;
@@ -414,14 +431,24 @@ impl WaveLoader for Vcd {
                         timestamp,
                     }
                     .compress()?;
-                    if let Some(list) = data.get_mut(&id) {
-                        list.push(item);
-                    } else {
-                        data.insert(id, vec![item]);
-                    }
+                    data.push(id, &item);
+                }
+                Command::ChangeReal(i, v) => {
+                    let IdCode(id) = i;
+                    let item = WaveDataItem {
+                        value: Real(v),
+                        timestamp,
+                    };
+                    data.push(id, &item);
+                }
+                Command::ChangeString(i, v) => {
+                    let IdCode(id) = i;
+                    let item = WaveDataItem {
+                        value: Str(v),
+                        timestamp,
+                    };
+                    data.push(id, &item);
                 }
-                Command::ChangeReal(_, _) => {}
-                Command::ChangeString(_, _) => {}
                 _ => {}
             }
         }
@@ -431,15 +458,9 @@ impl WaveLoader for Vcd {
             info!("parse vcd use time: {:?}", perf_stop - perf_start);
         }
         Ok(Wave {
-            info: WaveInfo {
-                timescale,
-                range: (time_start, time_stop),
-                headers,
-                code_signal_info: code_info,
-                code_paths,
-                tree,
-            },
-            data,
+            info: vcd_wave_info(&header, (time_start, time_stop))?,
+            data: data.finish(),
+            lazy: false,
         })
     }
 }
@@ -447,8 +468,8 @@ impl WaveLoader for Vcd {
 #[cfg(test)]
 mod test {
     use crate::radix::radix_vector_to_string_n;
-    use crate::wave::vcd_parser::{vcd_code_name, vcd_header_show, vcd_tree_show, Vcd};
-    use crate::wave::WaveLoader;
+    use crate::wave::vcd_parser::{scope_hash, vcd_code_name, vcd_header_show, vcd_tree_show, Vcd};
+    use crate::wave::{WaveLoader, WaveTreeNode};
     use anyhow::Result;
     use std::fs::File;
     use std::io::Read;
@@ -498,6 +519,23 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_scope_id() -> Result<()> {
+        let text = "$scope module top $end\n$scope module sub $end\n$var wire 1 ! a $end\n\
+            $upscope $end\n$upscope $end\n$enddefinitions $end\n#0\n0!\n";
+        let wave = Vcd::load(&mut text.as_bytes(), |_, _| {}, None)?;
+        let scope_id = |node: &trees::Node<WaveTreeNode>| match node.data() {
+            WaveTreeNode::WaveScope(scope) => scope.id,
+            _ => panic!("not a scope"),
+        };
+        // same ids are computed in fst parser
+        let top = wave.info.tree.root().iter().next().unwrap();
+        assert_eq!(scope_id(top), scope_hash("top", 0));
+        let sub = top.iter().next().unwrap();
+        assert_eq!(scope_id(sub), scope_hash("sub", scope_id(top) + 1));
+        Ok(())
+    }
+
     fn testing_vcd_parser(path: &str) -> Result<()> {
         info!("optimize_vcd_parser({})", path);
         if let Ok(mut input) = File::open(path) {
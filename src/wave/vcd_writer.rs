@@ -0,0 +1,321 @@
+use crate::wave::{
+    Wave, WaveDataValue, WaveScopeType, WaveSignalInfo, WaveSignalType, WaveTreeNode,
+};
+use anyhow::{anyhow, bail, Result};
+use num_bigint::BigUint;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use trees::Node;
+
+/// Identifier code of `index`-th var, same encoding as [vcd::IdCode]
+fn vcd_id_code(index: usize) -> String {
+    let mut code = String::new();
+    let mut i = index;
+    loop {
+        code.push((b'!' + (i % 94) as u8) as char);
+        i /= 94;
+        if i == 0 {
+            break;
+        }
+        i -= 1;
+    }
+    code
+}
+
+/// Keyword of var type, types unknown to vcd are mapped to the closest one
+fn vcd_var_type(typ: &WaveSignalType) -> &'static str {
+    use WaveSignalType::*;
+    match typ {
+        Event => "event",
+        Integer | Int | ShortInt | LongInt | Byte => "integer",
+        Parameter => "parameter",
+        Real | RealParameter | RealTime | ShortReal => "real",
+        Reg | Logic | Bit | Enum => "reg",
+        Supply0 => "supply0",
+        Supply1 => "supply1",
+        Time => "time",
+        Tri => "tri",
+        TriAnd => "triand",
+        TriOr => "trior",
+        TriReg => "trireg",
+        Tri0 => "tri0",
+        Tri1 => "tri1",
+        WAnd => "wand",
+        Wire | Port | SparseArray => "wire",
+        WOr => "wor",
+        String => "string",
+    }
+}
+
+/// Keyword of scope type, types unknown to vcd are written as `module`
+fn vcd_scope_type(typ: &WaveScopeType) -> &'static str {
+    match typ {
+        WaveScopeType::Task => "task",
+        WaveScopeType::Function => "function",
+        WaveScopeType::Begin => "begin",
+        WaveScopeType::Fork => "fork",
+        _ => "module",
+    }
+}
+
+/// Bit range after var name, declared range is kept, none for real, string and event vars
+fn vcd_var_range(var: &WaveSignalInfo) -> String {
+    match (vcd_var_type(&var.typ), var.range, var.width) {
+        ("real" | "string" | "event", _, _) => String::new(),
+        (_, Some((msb, lsb)), _) if msb == lsb => format!(" [{msb}]"),
+        (_, Some((msb, lsb)), _) => format!(" [{msb}:{lsb}]"),
+        (_, None, 0 | 1) => String::new(),
+        (_, None, width) => format!(" [{}:0]", width - 1),
+    }
+}
+
+/// Value change line without identifier code, reals that are not finite cannot be written
+fn vcd_value(value: &WaveDataValue, width: u64) -> Result<String> {
+    let bits = match value {
+        WaveDataValue::Comp(v) => BigUint::from_bytes_le(v).to_str_radix(2),
+        WaveDataValue::Raw(v) => v.iter().map(|x| x.to_string()).collect(),
+        WaveDataValue::Real(v) if !v.is_finite() => bail!("real value {} is not finite", v),
+        WaveDataValue::Real(v) => return Ok(format!("r{v} ")),
+        WaveDataValue::Str(v) => return Ok(format!("s{} ", v.replace(' ', "_"))),
+    };
+    Ok(match width <= 1 && bits.len() == 1 {
+        true => bits,
+        false => format!("b{bits} "),
+    })
+}
+
+fn tree_contains(node: &Node<WaveTreeNode>, ids: &HashSet<u64>) -> bool {
+    node.iter().any(|child| match child.data() {
+        WaveTreeNode::WaveVar(v) => ids.contains(&v.id),
+        WaveTreeNode::WaveScope(_) => tree_contains(child, ids),
+        _ => false,
+    })
+}
+
+fn write_scope<W: Write>(
+    writer: &mut W,
+    node: &Node<WaveTreeNode>,
+    ids: &HashSet<u64>,
+    codes: &mut HashMap<u64, String>,
+) -> Result<()> {
+    for child in node.iter() {
+        match child.data() {
+            WaveTreeNode::WaveScope(scope) if tree_contains(child, ids) => {
+                writeln!(
+                    writer,
+                    "$scope {} {} $end",
+                    vcd_scope_type(&scope.typ),
+                    scope.name
+                )?;
+                write_scope(writer, child, ids, codes)?;
+                writeln!(writer, "$upscope $end")?;
+            }
+            WaveTreeNode::WaveVar(var) if ids.contains(&var.id) => {
+                let index = codes.len();
+                let code = codes.entry(var.id).or_insert_with(|| vcd_id_code(index));
+                writeln!(
+                    writer,
+                    "$var {} {} {} {}{} $end",
+                    vcd_var_type(&var.typ),
+                    var.width.max(1),
+                    code,
+                    var.name,
+                    vcd_var_range(var)
+                )?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Write signals `ids` of `wave` in time window `range` as vcd,
+/// keeping scope hierarchy, var types, widths and timescale.
+/// Values at `range.0` are written in `$dumpvars`, timestamps are not shifted.
+pub fn write_vcd<W: Write>(
+    writer: &mut W,
+    wave: &Wave,
+    ids: &[u64],
+    range: (u64, u64),
+) -> Result<()> {
+    let (from, to) = range;
+    if let Some(date) = wave.info.headers.get("date") {
+        writeln!(writer, "$date\n\t{}\n$end", date.trim())?;
+    }
+    writeln!(
+        writer,
+        "$version\n\trvcd {}\n$end",
+        env!("CARGO_PKG_VERSION")
+    )?;
+    writeln!(writer, "$comment\n\texported from #{from} to #{to}\n$end")?;
+    writeln!(
+        writer,
+        "$timescale {}{} $end",
+        wave.info.timescale.0, wave.info.timescale.1
+    )?;
+    let selected = ids.iter().copied().collect::<HashSet<_>>();
+    let mut codes = HashMap::new();
+    write_scope(writer, wave.info.tree.root(), &selected, &mut codes)?;
+    writeln!(writer, "$enddefinitions $end")?;
+    // keep order of `ids` for initial values
+    let signals = ids
+        .iter()
+        .filter_map(|id| codes.get(id).map(|code| (*id, code)))
+        .collect::<Vec<_>>();
+    let width = |id: u64| {
+        wave.info
+            .code_signal_info
+            .get(&id)
+            .map(|info| info.width)
+            .unwrap_or(1)
+    };
+    let value = |id: u64, value: &WaveDataValue| {
+        vcd_value(value, width(id)).map_err(|e| {
+            let path = wave.info.code_paths.get(&id).map(|p| p.join("."));
+            anyhow!("{}: {}", path.unwrap_or_else(|| id.to_string()), e)
+        })
+    };
+    writeln!(writer, "#{from}\n$dumpvars")?;
+    let mut changes = vec![];
+    for (index, (id, code)) in signals.iter().enumerate() {
+        match wave.data.get(id) {
+            Some(data) => {
+                if let Some(item) = data.find(from) {
+                    writeln!(writer, "{}{}", value(*id, &item.value)?, code)?;
+                }
+                changes.extend(
+                    data.iter_at(data.position(from.saturating_add(1)))
+                        .take_while(|item| item.timestamp <= to)
+                        .map(|item| (item.timestamp, index, item.value)),
+                );
+            }
+            None => {
+                let info = wave.info.code_signal_info.get(id);
+                match (info.map(|info| vcd_var_type(&info.typ)), width(*id)) {
+                    // no unknown value of these types
+                    (Some("real" | "string" | "event"), _) => {}
+                    (_, 0 | 1) => writeln!(writer, "x{code}")?,
+                    _ => writeln!(writer, "bx {code}")?,
+                }
+            }
+        }
+    }
+    writeln!(writer, "$end")?;
+    changes.sort_by_key(|(timestamp, index, _)| (*timestamp, *index));
+    let mut last_timestamp = from;
+    for (timestamp, index, item) in changes {
+        if timestamp != last_timestamp {
+            writeln!(writer, "#{timestamp}")?;
+            last_timestamp = timestamp;
+        }
+        let (id, code) = signals[index];
+        writeln!(writer, "{}{}", value(id, &item)?, code)?;
+    }
+    if last_timestamp < to && to != u64::MAX {
+        writeln!(writer, "#{to}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::wave::store::WaveStore;
+    use crate::wave::vcd_parser::Vcd;
+    use crate::wave::vcd_writer::{vcd_id_code, write_vcd};
+    use crate::wave::{WaveDataItem, WaveDataValue, WaveLoader};
+    use std::fs::File;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_id_code() {
+        for i in [0, 1, 93, 94, 95, 94 * 95, 1 << 20] {
+            let vcd::IdCode(id) = vcd::IdCode::from_str(vcd_id_code(i).as_str()).unwrap();
+            assert_eq!(id, i as u64);
+        }
+    }
+
+    #[test]
+    fn test_write_vcd() -> anyhow::Result<()> {
+        let wave = Vcd::load(&mut File::open("data/cpu_ila_commit.vcd")?, |_, _| {}, None)?;
+        let ids = ["commits_0_w_en", "commits_0_data"]
+            .map(|name| {
+                *wave
+                    .info
+                    .code_paths
+                    .iter()
+                    .find(|(_, path)| path.last().unwrap() == name)
+                    .unwrap()
+                    .0
+            })
+            .to_vec();
+        let mut output = vec![];
+        write_vcd(&mut output, &wave, &ids, (200, 300))?;
+        let exported = Vcd::load(&mut output.as_slice(), |_, _| {}, None)?;
+        assert_eq!(exported.info.code_paths.len(), 2);
+        assert_eq!(exported.info.timescale.0, wave.info.timescale.0);
+        for id in &ids {
+            let path = &wave.info.code_paths[id];
+            let (new_id, _) = exported
+                .info
+                .code_paths
+                .iter()
+                .find(|(_, p)| *p == path)
+                .unwrap();
+            assert_eq!(
+                exported.info.code_signal_info[new_id].width,
+                wave.info.code_signal_info[id].width
+            );
+            for pos in 200..=300 {
+                assert_eq!(
+                    exported
+                        .find_value(*new_id, pos)
+                        .map(|v| v.value.to_string()),
+                    wave.find_value(*id, pos).map(|v| v.value.to_string())
+                );
+            }
+            assert!(exported.find_value(*new_id, 199).is_none());
+        }
+        Ok(())
+    }
+    #[test]
+    fn test_write_vcd_var_types() -> anyhow::Result<()> {
+        let text = "$scope module top $end\n$var real 64 ! r $end\n\
+            $var wire 8 \" d [15:8] $end\n$var event 1 # e $end\n$upscope $end\n\
+            $enddefinitions $end\n#0\nr1.5 !\nb10100101 \"\n#10\nr-2.25 !\n1#\n";
+        let mut wave = Vcd::load(&mut text.as_bytes(), |_, _| {}, None)?;
+        let mut ids = wave
+            .info
+            .code_signal_info
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        ids.sort();
+        let mut output = vec![];
+        write_vcd(&mut output, &wave, &ids, (0, 10))?;
+        let output = String::from_utf8(output)?;
+        assert!(output.contains("$var real 64 ! r $end"));
+        assert!(output.contains(" d [15:8] $end"));
+        assert!(output.contains(" e $end"));
+        let exported = Vcd::load(&mut output.as_bytes(), |_, _| {}, None)?;
+        assert_eq!(exported.info.code_signal_info.len(), 3);
+        for id in &ids {
+            assert_eq!(
+                exported.find_value(*id, 10).map(|v| v.value.to_string()),
+                wave.find_value(*id, 10).map(|v| v.value.to_string())
+            );
+        }
+        // not finite reals are rejected
+        let mut data = WaveStore::default();
+        let value = WaveDataValue::Real(f64::NAN);
+        data.insert(
+            ids[0],
+            &[WaveDataItem {
+                value,
+                timestamp: 0,
+            }],
+        );
+        wave.data = data;
+        assert!(write_vcd(&mut vec![], &wave, &ids, (0, 10)).is_err());
+        Ok(())
+    }
+}